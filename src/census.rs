@@ -0,0 +1,150 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+};
+
+use serde::Serialize;
+
+use crate::{
+    error::{Error, Result},
+    sniff::{sniff, SNIFF_LEN},
+    DataReaders, Id, IdCache, MinimizedIdHeader,
+};
+
+/// Everything known about one type id across the cache
+#[derive(Debug, Serialize)]
+pub struct TypeCensus {
+    pub type_id: Id,
+    pub name: String,
+    pub count: usize,
+    pub total_size: u64,
+    pub median_size: u64,
+    /// Number of assets with data in the bundle, stream and gpu_resources files
+    pub in_bundle: usize,
+    pub in_stream: usize,
+    pub in_gpu: usize,
+    /// How many assets were sniffed
+    pub sampled: usize,
+    /// Sniffed formats as `part:format`, with how many sampled parts had them
+    pub magics: BTreeMap<String, usize>,
+}
+
+fn read_head(r: &mut BufReader<File>, offset: u64) -> std::io::Result<Vec<u8>> {
+    let mut buf = Vec::with_capacity(SNIFF_LEN);
+    r.seek(SeekFrom::Start(offset))?;
+    r.take(SNIFF_LEN as u64).read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+/// Format of each part of an asset that has data, as `part:format`
+pub fn sniff_parts(h: &MinimizedIdHeader, readers: &mut DataReaders) -> Vec<String> {
+    let mut found = Vec::new();
+    for (part, offset, size) in [
+        ("bundle", h.data_offset, h.data_size),
+        ("stream", h.stream_data_offset as u64, h.stream_data_size),
+        ("gpu", h.gpu_data_offset, h.gpu_data_size),
+    ] {
+        if size == 0 {
+            continue;
+        }
+        let reader = match part {
+            "bundle" => Some(readers.bundle()),
+            "stream" => readers.stream().as_mut(),
+            _ => readers.gpu().as_mut(),
+        };
+        let format = match reader.map(|r| read_head(r, offset)) {
+            Some(Ok(head)) => match sniff(&head) {
+                Some(magic) => format!("{:?}", magic),
+                None => "unknown".to_string(),
+            },
+            _ => "unreadable".to_string(),
+        };
+        found.push(format!("{}:{}", part, format));
+    }
+    found
+}
+
+/// Tallies every type id in the cache. With a data directory, up to `samples` assets of each type are
+/// read to sniff what format their data is in.
+pub fn census(cache: &IdCache, data_path: Option<&str>, samples: usize) -> Result<Vec<TypeCensus>> {
+    let mut bundles: Vec<&Id> = cache.bundles.keys().collect();
+    bundles.sort_by_key(|b| u64::from(**b));
+
+    let mut by_type: HashMap<Id, Vec<(Id, &MinimizedIdHeader)>> = HashMap::new();
+    for bundle in bundles {
+        for h in &cache.bundles[bundle] {
+            by_type.entry(h.type_id).or_default().push((*bundle, h));
+        }
+    }
+
+    let mut census: Vec<TypeCensus> = Vec::new();
+    let mut to_sniff: HashMap<Id, Vec<(usize, &MinimizedIdHeader)>> = HashMap::new();
+    for (type_id, assets) in &by_type {
+        let mut sizes: Vec<u64> = assets.iter().map(|(_, h)| h.total_size()).collect();
+        sizes.sort_unstable();
+        census.push(TypeCensus {
+            type_id: *type_id,
+            name: format!("{:?}", type_id.as_enum()),
+            count: assets.len(),
+            total_size: sizes.iter().sum(),
+            median_size: sizes[sizes.len() / 2],
+            in_bundle: assets.iter().filter(|(_, h)| h.data_size > 0).count(),
+            in_stream: assets.iter().filter(|(_, h)| h.stream_data_size > 0).count(),
+            in_gpu: assets.iter().filter(|(_, h)| h.gpu_data_size > 0).count(),
+            sampled: 0,
+            magics: BTreeMap::new(),
+        });
+        if data_path.is_some() {
+            for (bundle, h) in assets.iter().take(samples) {
+                to_sniff.entry(*bundle).or_default().push((census.len() - 1, h));
+            }
+        }
+    }
+
+    if let Some(data_path) = data_path {
+        // one pass per bundle, so each bundle's files only get opened once
+        for (bundle, assets) in to_sniff {
+            let mut readers = match DataReaders::open(data_path, &bundle) {
+                Ok(r) => r,
+                Err(e) if e.is_asset_error() || matches!(e, Error::MissingBundle(_)) => {
+                    eprintln!("Skipping bundle {}: {}", bundle, e);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            for (i, h) in assets {
+                census[i].sampled += 1;
+                for format in sniff_parts(h, &mut readers) {
+                    *census[i].magics.entry(format).or_default() += 1;
+                }
+            }
+        }
+    }
+
+    census.sort_by(|a, b| b.count.cmp(&a.count).then(u64::from(a.type_id).cmp(&u64::from(b.type_id))));
+    Ok(census)
+}
+
+pub fn print_census(census: &[TypeCensus]) {
+    println!(
+        "{:<16} {:<16} {:>8} {:>14} {:>10} {:>8} {:>8} {:>8}  sniffed",
+        "type id", "type", "count", "total", "median", "bundle", "stream", "gpu"
+    );
+    for t in census {
+        let magics: Vec<String> = t.magics.iter().map(|(m, c)| format!("{} x{}", m, c)).collect();
+        println!(
+            "{} {:<16} {:>8} {:>14} {:>10} {:>8} {:>8} {:>8}  {}",
+            t.type_id,
+            t.name,
+            t.count,
+            t.total_size,
+            t.median_size,
+            t.in_bundle,
+            t.in_stream,
+            t.in_gpu,
+            magics.join(", ")
+        );
+    }
+    println!("{} types", census.len());
+}
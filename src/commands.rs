@@ -0,0 +1,481 @@
+use std::{collections::HashMap, path::Path};
+
+use wildmatch::WildMatch;
+
+use crate::{
+    error::{Error, Result},
+    extract::{convert, read_bundle},
+    pndb::Pndb,
+    types::package,
+    DataHeader, DataTypes, DataReaders, Id, IdCache, MinimizedIdHeader, Part,
+};
+
+fn sorted_bundles(cache: &IdCache) -> Vec<&Id> {
+    let mut bundles: Vec<&Id> = cache.bundles.keys().collect();
+    bundles.sort_by_key(|b| u64::from(**b));
+    bundles
+}
+
+/// Asset count and combined size per type, largest count first
+fn type_counts<'a>(headers: impl Iterator<Item = &'a MinimizedIdHeader>) -> Vec<(Id, usize, u64)> {
+    let mut counts: HashMap<Id, (usize, u64)> = HashMap::new();
+    for h in headers {
+        let entry = counts.entry(h.type_id).or_default();
+        entry.0 += 1;
+        entry.1 += h.total_size();
+    }
+    let mut counts: Vec<(Id, usize, u64)> = counts.into_iter().map(|(t, (c, s))| (t, c, s)).collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(u64::from(a.0).cmp(&u64::from(b.0))));
+    counts
+}
+
+fn print_type_counts(counts: &[(Id, usize, u64)]) {
+    for (type_id, count, size) in counts {
+        println!(
+            "  {} {:<16} {:>8} assets {:>12} bytes",
+            type_id,
+            format!("{:?}", type_id.as_enum()),
+            count,
+            size
+        );
+    }
+}
+
+pub fn cache_info(cache: &IdCache, path: &Path) -> Result<()> {
+    let file_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or_default();
+    let headers = cache.bundles.values().flatten();
+    println!("{} ({} bytes)", path.display(), file_size);
+    if let Some(build_id) = cache.build_id {
+        println!("built from game build {}", build_id);
+    }
+    println!(
+        "{} bundles, {} assets, {} bytes of asset data",
+        cache.bundles.len(),
+        headers.clone().count(),
+        headers.clone().map(|h| h.total_size()).sum::<u64>()
+    );
+    print_type_counts(&type_counts(headers));
+    Ok(())
+}
+
+pub fn list_bundles(cache: &IdCache, namedb: &Pndb) {
+    let packages = package::bundle_packages(cache);
+    for bundle in sorted_bundles(cache) {
+        let headers = &cache.bundles[bundle];
+        println!(
+            "{} {:>6} assets {:>12} bytes {}",
+            bundle,
+            headers.len(),
+            headers.iter().map(|h| h.total_size()).sum::<u64>(),
+            package::bundle_name(&packages, namedb, bundle).unwrap_or_default()
+        );
+    }
+    println!("{} bundles", cache.bundles.len());
+}
+
+/// Bytes per type in the bundle, stream and gpu_resources files
+fn part_sizes<'a>(headers: impl Iterator<Item = &'a DataHeader>) -> HashMap<Id, [u64; 3]> {
+    let mut sizes: HashMap<Id, [u64; 3]> = HashMap::new();
+    for d in headers {
+        let entry = sizes.entry(d.type_id).or_default();
+        entry[0] += d.data_size as u64;
+        entry[1] += d.stream_data_size as u64;
+        entry[2] += d.gpu_data_size as u64;
+    }
+    sizes
+}
+
+fn print_file(label: &str, path: &Path) {
+    match std::fs::metadata(path) {
+        Ok(m) => println!("  {:<14} {} ({} bytes)", label, path.display(), m.len()),
+        Err(_) => println!("  {:<14} {} (missing)", label, path.display()),
+    }
+}
+
+/// Prints what's in a bundle, or every bundle entry of an asset. Without a data directory only what's
+/// in the cache can be shown.
+pub fn info(cache: &IdCache, namedb: &Pndb, data_path: Option<&str>, id: Id, experimental: bool) -> Result<()> {
+    if let Some(headers) = cache.bundles.get(&id) {
+        println!("bundle {}", id);
+        let Some(data_path) = data_path else {
+            println!("{} assets", headers.len());
+            print_type_counts(&type_counts(headers.iter()));
+            return Ok(());
+        };
+        return bundle_info(data_path, id);
+    }
+
+    let mut found = false;
+    for bundle in sorted_bundles(cache) {
+        for (index, h) in cache.bundles[bundle].iter().enumerate() {
+            if h.id != id {
+                continue;
+            }
+            found = true;
+            println!("bundle {} index {}", bundle, index);
+            match data_path {
+                Some(data_path) => asset_info(cache, data_path, bundle, index, h, experimental)?,
+                None => {
+                    println!("  type {} ({:?})", h.type_id, h.type_id.as_enum());
+                    println!("  bundle data {:#x} + {}", h.data_offset, h.data_size);
+                    println!("  stream data {:#x} + {}", h.stream_data_offset, h.stream_data_size);
+                    println!("  gpu data {:#x} + {}", h.gpu_data_offset, h.gpu_data_size);
+                }
+            }
+        }
+    }
+    if !found {
+        return Err(Error::IdNotFound { id, bundle: None });
+    }
+    if let Some(name) = namedb.name_database.get(&id) {
+        println!("name {}", name);
+    }
+    Ok(())
+}
+
+fn bundle_info(data_path: &str, id: Id) -> Result<()> {
+    let path = Path::new(data_path).join(id.to_string());
+    let mut readers = DataReaders::open(data_path, &id)?;
+    let bundle = read_bundle(readers.bundle())?;
+
+    println!("files");
+    print_file("bundle", &path);
+    print_file("stream", &path.with_extension("stream"));
+    print_file("gpu_resources", &path.with_extension("gpu_resources"));
+
+    let h = &bundle.header;
+    println!("header");
+    println!("  type_count     {}", h.type_count);
+    println!("  data_count     {}", h.data_count);
+    println!("  unk10          {:#010x}", h.unk10);
+    println!("  unk14          {:#010x}", h.unk14);
+    println!("  unk18          {:#018x}", h.unk18);
+    println!("  unk20          {:#018x}", h.unk20);
+    println!("  unk28          {:#018x}", h.unk28);
+    println!("  unk30_pad      {:#x} {:#x}", h.unk30_pad.0, h.unk30_pad.1);
+
+    let sizes = part_sizes(bundle.data_headers.iter());
+    println!("types");
+    println!(
+        "  {:<16} {:<16} {:>6} {:>10} {:>10} {:>12} {:>12} {:>12}",
+        "type id", "type", "count", "unk10", "unk14", "bundle", "stream", "gpu"
+    );
+    for t in &bundle.data_types {
+        let [b, s, g] = sizes.get(&t.type_id).copied().unwrap_or_default();
+        println!(
+            "  {} {:<16} {:>6} {:>#10x} {:>#10x} {:>12} {:>12} {:>12}",
+            t.type_id,
+            format!("{:?}", t.type_id.as_enum()),
+            t.data_count,
+            t.unk10,
+            t.unk14,
+            b,
+            s,
+            g
+        );
+    }
+    let [b, s, g] = sizes.values().fold([0; 3], |acc, x| [acc[0] + x[0], acc[1] + x[1], acc[2] + x[2]]);
+    println!(
+        "  {:<16} {:<16} {:>6} {:>10} {:>10} {:>12} {:>12} {:>12}",
+        "total", "", bundle.data_headers.len(), "", "", b, s, g
+    );
+    Ok(())
+}
+
+fn asset_info(
+    cache: &IdCache,
+    data_path: &str,
+    bundle_id: &Id,
+    index: usize,
+    h: &MinimizedIdHeader,
+    experimental: bool,
+) -> Result<()> {
+    let mut readers = DataReaders::open(data_path, bundle_id)?;
+    let bundle = read_bundle(readers.bundle())?;
+    // the cache keeps headers in bundle order, but fall back to searching in case it's stale
+    let mut d = match bundle.data_headers.get(index) {
+        Some(d) if d.unk_id == h.id && d.type_id == h.type_id => *d,
+        _ => match bundle.data_headers.iter().find(|d| d.unk_id == h.id && d.type_id == h.type_id) {
+            Some(d) => *d,
+            None => return Err(Error::IdNotFound { id: h.id, bundle: Some(*bundle_id) }),
+        },
+    };
+
+    println!("  type               {} ({:?})", d.type_id, d.type_enum);
+    println!("  data_offset        {:#x}", d.data_offset);
+    println!("  data_size          {}", d.data_size);
+    println!("  stream_data_offset {:#x}", d.stream_data_offset);
+    println!("  stream_data_size   {}", d.stream_data_size);
+    println!("  gpu_data_offset    {:#x}", d.gpu_data_offset);
+    println!("  gpu_data_size      {}", d.gpu_data_size);
+    println!("  unk1c              {:#010x}", d.unk1c);
+    println!("  unk28              {:#018x}", d.unk28);
+    println!("  unk30              {:#018x}", d.unk30);
+    println!("  unk44              {:#010x}", d.unk44);
+    println!("  unk48              {:#010x}", d.unk48);
+    println!("  unk4c              {:#010x}", d.unk4c);
+
+    if !experimental && crate::types::is_experimental(d.type_enum) {
+        println!("  decoding {:?} is experimental, pass --experimental to see its details", d.type_enum);
+        return Ok(());
+    }
+    match crate::types::summarize(cache, bundle_id, &mut d, &mut readers) {
+        Ok(Some(summary)) => {
+            for (label, value) in summary {
+                println!("  {:<18} {}", label, value);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => println!("  failed to read {:?}: {}", d.type_enum, e),
+    }
+    Ok(())
+}
+
+pub fn list_packages(cache: &IdCache, namedb: &Pndb) {
+    let packages = package::bundle_packages(cache);
+    for bundle in sorted_bundles(cache) {
+        for p in packages.get(bundle).map(|p| p.as_slice()).unwrap_or_default() {
+            println!(
+                "{} {} {}",
+                bundle,
+                p,
+                namedb.name_database.get(p).map(|n| n.as_str()).unwrap_or_default()
+            );
+        }
+    }
+    println!("{} bundles without a package", cache.bundles.len() - packages.len());
+}
+
+/// Compares each bundle's contents to its packages, returning how many bundles don't match
+pub fn check_packages(
+    cache: &IdCache,
+    namedb: &Pndb,
+    data_path: &str,
+    bundles: &[Id],
+    verbose: bool,
+) -> Result<usize> {
+    let packages = package::bundle_packages(cache);
+    let mut mismatched = 0;
+    for bundle in sorted_bundles(cache) {
+        if !bundles.is_empty() && !bundles.contains(bundle) {
+            continue;
+        }
+        let Some(package_ids) = packages.get(bundle) else {
+            continue;
+        };
+        let mut readers = DataReaders::open(data_path, bundle)?;
+        for package_id in package_ids {
+            let (_, h) = cache.get_by_id(*package_id, Some(DataTypes::Package), *bundle)?;
+            let package = match package::read_package(&DataHeader::from(h), &mut readers) {
+                Ok(package) => package,
+                Err(e) => {
+                    println!("{} {}: failed to read package: {}", bundle, package_id, e);
+                    mismatched += 1;
+                    continue;
+                }
+            };
+            let check = package::check_package(cache, bundle, *package_id, &package)?;
+            if check.missing.is_empty() && check.undeclared.is_empty() {
+                continue;
+            }
+            mismatched += 1;
+            println!(
+                "{} {}: {} missing, {} undeclared",
+                bundle,
+                package::bundle_name(&packages, namedb, bundle).unwrap_or(&package_id.to_string()),
+                check.missing.len(),
+                check.undeclared.len()
+            );
+            if verbose {
+                for (what, resources) in [("missing", &check.missing), ("undeclared", &check.undeclared)] {
+                    for r in resources {
+                        println!(
+                            "  {} {:?} {} {}",
+                            what,
+                            r.type_id.as_enum(),
+                            r.name,
+                            namedb.name_database.get(&r.name).map(|n| n.as_str()).unwrap_or_default()
+                        );
+                    }
+                }
+            }
+        }
+    }
+    Ok(mismatched)
+}
+
+/// What `cat` writes
+#[derive(Debug, Clone, Copy)]
+pub enum CatOutput {
+    /// One part of the asset as it's stored
+    Raw(Part),
+    /// The converted asset, with `experimental` allowing the experimental decoders
+    Converted { experimental: bool },
+}
+
+/// Reads one part of an asset, or the converted asset
+pub fn cat(
+    cache: &IdCache,
+    namedb: &Pndb,
+    data_path: &str,
+    id: Id,
+    bundle: Option<Id>,
+    output: CatOutput,
+) -> Result<Vec<u8>> {
+    let (bundle, h) = cache.get_by_id(id, None, bundle.unwrap_or(Id::invalid()))?;
+    let mut readers = DataReaders::open(data_path, &bundle)?;
+    let mut d = DataHeader::from(h);
+
+    let part = match output {
+        CatOutput::Raw(part) => part,
+        CatOutput::Converted { experimental: false } if crate::types::is_experimental(d.type_enum) => {
+            return Err(Error::UnsupportedType(format!(
+                "conversion of {:?} assets without --experimental",
+                d.type_enum
+            )));
+        }
+        CatOutput::Converted { .. } => {
+            return match convert(cache, namedb, &bundle, &mut d, &mut readers)? {
+                Some(converted) => Ok(converted.data),
+                None => Err(Error::UnsupportedType(format!(
+                    "conversion of {:?} assets",
+                    d.type_enum
+                ))),
+            };
+        }
+    };
+
+    match part {
+        Part::Bundle => d.get_bundle_buf(readers.bundle()),
+        Part::Stream => d.get_stream_buf(readers.stream()),
+        Part::Gpu => d.get_gpu_buf(readers.gpu()),
+    }
+}
+
+/// Reads a level and writes it as a glTF scene referencing its units' meshes
+pub fn scene(
+    cache: &IdCache,
+    namedb: &Pndb,
+    data_path: &str,
+    id: Id,
+    bundle: Option<Id>,
+    out: &Path,
+    experimental: bool,
+) -> Result<usize> {
+    if !experimental {
+        return Err(Error::UnsupportedType("level scenes without --experimental".to_string()));
+    }
+    let (bundle, h) = cache.get_by_id(id, Some(DataTypes::Level), bundle.unwrap_or(Id::invalid()))?;
+    let mut readers = DataReaders::open(data_path, &bundle)?;
+    let d = DataHeader::from(h);
+    let scene = crate::types::level::read_level(cache, namedb, &d, &mut readers)?;
+    std::fs::write(out, crate::types::level::scene_to_glb(cache, data_path, &scene)?)?;
+    Ok(scene.units.len())
+}
+
+/// Exports a unit with its skeleton and the given animation clips as glTF, returning how many clips
+/// were written. Clips are only decoded with `experimental`, the unit and skeleton are exported either way.
+pub fn animate(
+    cache: &IdCache,
+    namedb: &Pndb,
+    data_path: &str,
+    unit: Id,
+    clip_ids: &[Id],
+    out: &Path,
+    experimental: bool,
+) -> Result<usize> {
+    if !experimental && !clip_ids.is_empty() {
+        return Err(Error::UnsupportedType("animation clips without --experimental".to_string()));
+    }
+    let mut clips = Vec::new();
+    for id in clip_ids {
+        let (bundle, h) = cache.get_by_id(*id, Some(DataTypes::Animation), Id::invalid())?;
+        let mut readers = DataReaders::open(data_path, &bundle)?;
+        let found = crate::types::animation::read_animation(&DataHeader::from(h), &mut readers)?;
+        let name = namedb.name_database.get(id).cloned().unwrap_or(id.to_string());
+        let count = found.len();
+        for (i, mut clip) in found.into_iter().enumerate() {
+            // resources usually hold a single clip, which takes the resource's name
+            clip.name = match count {
+                1 => name.clone(),
+                _ => format!("{}/{}", name, i),
+            };
+            clips.push(clip);
+        }
+    }
+    std::fs::write(out, crate::types::animation::animated_unit_to_glb(cache, data_path, unit, &clips)?)?;
+    Ok(clips.len())
+}
+
+/// Writes a unit's collision shapes next to its visual mesh, as obj when the output ends in .obj and
+/// glTF otherwise, returning how many shapes were written
+pub fn collision(cache: &IdCache, data_path: &str, unit: Id, out: &Path) -> Result<usize> {
+    let (data, count) = match out.extension().and_then(|e| e.to_str()) {
+        Some("obj") => crate::types::collision::collision_to_obj(cache, data_path, unit)?,
+        _ => crate::types::collision::collision_to_glb(cache, data_path, unit)?,
+    };
+    std::fs::write(out, data)?;
+    Ok(count)
+}
+
+/// Writes the navigation meshes of a navdata resource, as obj when the output ends in .obj and glTF
+/// otherwise, returning how many meshes were written
+pub fn navmesh(cache: &IdCache, data_path: &str, id: Id, bundle: Option<Id>, out: &Path) -> Result<usize> {
+    let (bundle, h) = cache.get_by_id(id, Some(DataTypes::Navdata), bundle.unwrap_or(Id::invalid()))?;
+    let mut readers = DataReaders::open(data_path, &bundle)?;
+    let meshes = crate::types::navmesh::read_navdata(&DataHeader::from(h), &mut readers)?;
+    let data = match out.extension().and_then(|e| e.to_str()) {
+        Some("obj") => crate::types::navmesh::navmeshes_to_obj(&meshes)?,
+        _ => crate::types::navmesh::navmeshes_to_glb(&meshes)?,
+    };
+    std::fs::write(out, data)?;
+    Ok(meshes.len())
+}
+
+pub fn search(cache: &IdCache, namedb: &Pndb, pattern: &str, type_id: Option<Id>) {
+    let pattern = WildMatch::new(pattern);
+    let mut found: HashMap<Id, (Id, Vec<Id>)> = HashMap::new();
+    for bundle in sorted_bundles(cache) {
+        for h in &cache.bundles[bundle] {
+            if type_id.is_some_and(|t| t != h.type_id) {
+                continue;
+            }
+            let Some(name) = namedb.name_database.get(&h.id) else {
+                continue;
+            };
+            if pattern.matches(name) {
+                found.entry(h.id).or_insert((h.type_id, Vec::new())).1.push(*bundle);
+            }
+        }
+    }
+
+    let mut found: Vec<_> = found.into_iter().collect();
+    found.sort_by(|a, b| namedb.name_database[&a.0].cmp(&namedb.name_database[&b.0]));
+    for (id, (type_id, bundles)) in &found {
+        println!(
+            "{} {:<16} {} [{}]",
+            id,
+            format!("{:?}", type_id.as_enum()),
+            namedb.name_database[id],
+            bundles.iter().map(|b| b.to_string()).collect::<Vec<_>>().join(", ")
+        );
+    }
+    println!("{} matches", found.len());
+}
+
+pub fn names(namedb: &Pndb, ids: &[Id]) {
+    if ids.is_empty() {
+        let mut names: Vec<(&Id, &String)> = namedb.name_database.iter().collect();
+        names.sort_by(|a, b| a.1.cmp(b.1));
+        for (id, name) in names {
+            println!("{} {}", id, name);
+        }
+        return;
+    }
+    for id in ids {
+        match namedb.name_database.get(id) {
+            Some(name) => println!("{} {}", id, name),
+            None => println!("{} <unknown>", id),
+        }
+    }
+}
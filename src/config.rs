@@ -0,0 +1,106 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+
+/// Contents of the config file, e.g.
+///
+/// ```toml
+/// default_profile = "live"
+///
+/// [profiles.live]
+/// data = "~/.steam/steam/steamapps/common/Helldivers 2/data"
+/// cache = "~/hd2/live/ids.cache"
+/// pndb = ["~/hd2/assets.pndb", "~/hd2/extra.pndb"]
+/// output = "~/hd2/live/out"
+/// keep_going = true
+/// ```
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Profile used when none is given with --profile
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: HashMap<String, Profile>,
+}
+
+/// Settings for one game install. Anything given on the command line or through the environment wins.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub data: Option<String>,
+    pub cache: Option<String>,
+    /// Name databases, merged in order so later ones override earlier ones
+    #[serde(default)]
+    pub pndb: Vec<String>,
+    pub output: Option<String>,
+    #[serde(default)]
+    pub one_folder: bool,
+    #[serde(default)]
+    pub keep_going: bool,
+    pub report: Option<String>,
+}
+
+/// `$XDG_CONFIG_HOME/helldivers2-rs/config.toml`, falling back to `~/.config`
+pub fn default_path() -> Option<PathBuf> {
+    let config_home = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_home.join("helldivers2-rs").join("config.toml"))
+}
+
+/// Expands a leading `~/` to the home directory
+pub fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => Path::new(&home).join(rest).to_string_lossy().into_owned(),
+        _ => path.to_string(),
+    }
+}
+
+impl Config {
+    /// Reads the config from `path`, or from the default location if it exists there.
+    /// A missing default config is not an error, a missing explicit one is.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match default_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Default::default()),
+            },
+        };
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| Error::Config(format!("{}: {}", path.display(), e)))?;
+        toml::from_str(&text).map_err(|e| Error::Config(format!("{}: {}", path.display(), e)))
+    }
+
+    /// The named profile, or the default one if no name is given. Paths have `~` expanded.
+    pub fn profile(&self, name: Option<&str>) -> Result<Profile> {
+        let Some(name) = name.or(self.default_profile.as_deref()) else {
+            return Ok(Default::default());
+        };
+        let Some(profile) = self.profiles.get(name) else {
+            let mut known: Vec<&str> = self.profiles.keys().map(|k| k.as_str()).collect();
+            known.sort();
+            return Err(Error::Config(format!(
+                "no profile named {:?} (known profiles: {})",
+                name,
+                known.join(", ")
+            )));
+        };
+
+        let expand = |p: &Option<String>| p.as_deref().map(expand_home);
+        Ok(Profile {
+            data: expand(&profile.data),
+            cache: expand(&profile.cache),
+            pndb: profile.pndb.iter().map(|p| expand_home(p)).collect(),
+            output: expand(&profile.output),
+            report: expand(&profile.report),
+            ..profile.clone()
+        })
+    }
+}
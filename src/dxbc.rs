@@ -0,0 +1,434 @@
+// Direct3D shader containers, which both DXBC (shader model 4 and 5) and DXIL (6 and up) shaders come
+// in: a header, then chunks tagged with a fourcc. Reflection comes from the resource definitions
+// (RDEF), the input/output signatures and, for DXIL, the pipeline state validation chunk (PSV0).
+
+use serde::Serialize;
+
+use crate::error::{Error, Result};
+
+const MAGIC: &[u8; 4] = b"DXBC";
+const HEADER_SIZE: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Dxbc,
+    Dxil,
+}
+
+/// What a container says about its shader
+#[derive(Debug, Clone, Serialize)]
+pub struct Reflection {
+    pub format: Format,
+    /// Stage and model, e.g. vs_5_0
+    pub shader_model: Option<String>,
+    pub chunks: Vec<String>,
+    pub constant_buffers: Vec<ConstantBuffer>,
+    pub bindings: Vec<Binding>,
+    pub inputs: Vec<SignatureElement>,
+    pub outputs: Vec<SignatureElement>,
+    /// DXIL resource bindings, which PSV0 keeps without names
+    pub resources: Vec<PsvResource>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConstantBuffer {
+    pub name: String,
+    pub size: u32,
+    pub variables: Vec<Variable>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Variable {
+    pub name: String,
+    pub offset: u32,
+    pub size: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Binding {
+    pub name: String,
+    pub kind: &'static str,
+    pub dimension: &'static str,
+    pub bind_point: u32,
+    pub bind_count: u32,
+    pub space: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SignatureElement {
+    pub semantic: String,
+    pub index: u32,
+    pub register: u32,
+    pub system_value: u32,
+    /// Component type and count, e.g. float3
+    pub format: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PsvResource {
+    pub kind: &'static str,
+    pub space: u32,
+    pub lower_bound: u32,
+    pub upper_bound: u32,
+}
+
+fn decode_err(what: impl std::fmt::Display) -> Error {
+    Error::Decode(format!("shader container, {}", what))
+}
+
+fn u32_at(buf: &[u8], at: usize) -> Result<u32> {
+    buf.get(at..at + 4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| decode_err(format!("read past the end at {:#x}", at)))
+}
+
+fn string_at(buf: &[u8], at: usize) -> Result<String> {
+    let s = buf.get(at..).ok_or_else(|| decode_err(format!("string at {:#x} past the end", at)))?;
+    let end = s.iter().position(|b| *b == 0).unwrap_or(s.len());
+    Ok(String::from_utf8_lossy(&s[..end]).into_owned())
+}
+
+/// Size of the container at the start of `buf`, if there's a well formed one
+fn container_size(buf: &[u8]) -> Option<usize> {
+    if buf.get(..4)? != MAGIC {
+        return None;
+    }
+    let size = u32_at(buf, 24).ok()? as usize;
+    let chunks = u32_at(buf, 28).ok()? as usize;
+    (size >= HEADER_SIZE + chunks * 4 && size <= buf.len()).then_some(size)
+}
+
+/// Every container in a buffer, however the shader package around them is laid out. Containers can
+/// start at any offset.
+pub fn find_containers(buf: &[u8]) -> Vec<(usize, &[u8])> {
+    let mut found = Vec::new();
+    let mut at = 0;
+    while let Some(next) = buf.get(at..).and_then(|b| b.windows(4).position(|w| w == MAGIC)) {
+        at += next;
+        match container_size(&buf[at..]) {
+            Some(size) => {
+                found.push((at, &buf[at..at + size]));
+                at += size;
+            }
+            None => at += 1,
+        }
+    }
+    found
+}
+
+/// The chunks of a container as fourcc and data
+fn chunks(buf: &[u8]) -> Result<Vec<([u8; 4], &[u8])>> {
+    let size = container_size(buf).ok_or_else(|| decode_err("bad header"))?;
+    let count = u32_at(buf, 28)? as usize;
+    let mut chunks = Vec::with_capacity(count);
+    for i in 0..count {
+        let at = u32_at(buf, HEADER_SIZE + i * 4)? as usize;
+        let len = u32_at(buf, at + 4)? as usize;
+        let data = buf
+            .get(at + 8..at + 8 + len)
+            .filter(|_| at + 8 + len <= size)
+            .ok_or_else(|| decode_err(format!("chunk {} runs past the end", i)))?;
+        chunks.push((buf[at..at + 4].try_into().unwrap(), data));
+    }
+    Ok(chunks)
+}
+
+pub fn reflect(buf: &[u8]) -> Result<Reflection> {
+    let chunks = chunks(buf)?;
+    let mut reflection = Reflection {
+        format: Format::Dxbc,
+        shader_model: None,
+        chunks: chunks.iter().map(|(tag, _)| String::from_utf8_lossy(tag).into_owned()).collect(),
+        constant_buffers: Vec::new(),
+        bindings: Vec::new(),
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        resources: Vec::new(),
+    };
+    for (tag, data) in &chunks {
+        match tag {
+            b"RDEF" => read_rdef(data, &mut reflection)?,
+            b"ISGN" | b"ISG1" => reflection.inputs = read_signature(tag, data)?,
+            b"OSGN" | b"OSG1" | b"OSG5" => reflection.outputs = read_signature(tag, data)?,
+            b"PSV0" => reflection.resources = read_psv(data)?,
+            b"SHDR" | b"SHEX" | b"DXIL" => {
+                if tag == b"DXIL" {
+                    reflection.format = Format::Dxil;
+                }
+                reflection.shader_model = Some(shader_model(u32_at(data, 0)?));
+            }
+            _ => {}
+        }
+    }
+    Ok(reflection)
+}
+
+/// The version token of the bytecode: program type in the high 16 bits, then major and minor nibbles
+fn shader_model(version: u32) -> String {
+    let stage = match version >> 16 {
+        0 | 0xffff => "ps",
+        1 | 0xfffe => "vs",
+        2 => "gs",
+        3 => "hs",
+        4 => "ds",
+        5 => "cs",
+        6 => "lib",
+        _ => "unknown",
+    };
+    format!("{}_{}_{}", stage, version >> 4 & 0xf, version & 0xf)
+}
+
+/// Resource definitions: constant buffers and their variables, then bound resources. Variables and
+/// bindings grew in shader model 5 and 5.1.
+fn read_rdef(data: &[u8], reflection: &mut Reflection) -> Result<()> {
+    let (cb_count, cb_at) = (u32_at(data, 0)? as usize, u32_at(data, 4)? as usize);
+    let (bind_count, bind_at) = (u32_at(data, 8)? as usize, u32_at(data, 12)? as usize);
+    let (minor, major) = (data.get(16).copied().unwrap_or(0), data.get(17).copied().unwrap_or(0));
+    let variable_size = if major >= 5 { 40 } else { 24 };
+    let binding_size = if (major, minor) >= (5, 1) { 40 } else { 32 };
+
+    for i in 0..cb_count {
+        let at = cb_at + i * 24;
+        let (var_count, var_at) = (u32_at(data, at + 4)? as usize, u32_at(data, at + 8)? as usize);
+        let variables = (0..var_count)
+            .map(|v| {
+                let at = var_at + v * variable_size;
+                Ok(Variable {
+                    name: string_at(data, u32_at(data, at)? as usize)?,
+                    offset: u32_at(data, at + 4)?,
+                    size: u32_at(data, at + 8)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        reflection.constant_buffers.push(ConstantBuffer {
+            name: string_at(data, u32_at(data, at)? as usize)?,
+            size: u32_at(data, at + 12)?,
+            variables,
+        });
+    }
+
+    for i in 0..bind_count {
+        let at = bind_at + i * binding_size;
+        let kind = match u32_at(data, at + 4)? {
+            0 => "cbuffer",
+            1 => "tbuffer",
+            2 => "texture",
+            3 => "sampler",
+            4 => "rw_typed",
+            5 => "structured",
+            6 => "rw_structured",
+            7 => "byte_address",
+            8 => "rw_byte_address",
+            9 => "append_structured",
+            10 => "consume_structured",
+            11 => "rw_structured_with_counter",
+            _ => "unknown",
+        };
+        let dimension = match u32_at(data, at + 12)? {
+            0 => "unknown",
+            1 => "buffer",
+            2 => "texture1d",
+            3 => "texture1d_array",
+            4 => "texture2d",
+            5 => "texture2d_array",
+            6 => "texture2d_ms",
+            7 => "texture2d_ms_array",
+            8 => "texture3d",
+            9 => "texture_cube",
+            10 => "texture_cube_array",
+            11 => "buffer_ex",
+            _ => "other",
+        };
+        reflection.bindings.push(Binding {
+            name: string_at(data, u32_at(data, at)? as usize)?,
+            kind,
+            dimension,
+            bind_point: u32_at(data, at + 20)?,
+            bind_count: u32_at(data, at + 24)?,
+            space: if binding_size == 40 { u32_at(data, at + 32)? } else { 0 },
+        });
+    }
+    Ok(())
+}
+
+/// Input or output signature: a count, then elements of name, semantic index, system value, component
+/// type, register and component mask. The 1 and 5 variants add a stream, and the 1 variant a minimum
+/// precision.
+fn read_signature(tag: &[u8; 4], data: &[u8]) -> Result<Vec<SignatureElement>> {
+    let (size, start) = match tag {
+        b"ISG1" | b"OSG1" => (32, 4),
+        b"OSG5" => (28, 4),
+        _ => (24, 0),
+    };
+    let count = u32_at(data, 0)? as usize;
+    (0..count)
+        .map(|i| {
+            let at = 8 + i * size + start;
+            let component = match u32_at(data, at + 12)? {
+                1 => "uint",
+                2 => "int",
+                3 => "float",
+                _ => "unknown",
+            };
+            let mask = data.get(at + 20).copied().unwrap_or(0).count_ones();
+            Ok(SignatureElement {
+                semantic: string_at(data, u32_at(data, at)? as usize)?,
+                index: u32_at(data, at + 4)?,
+                system_value: u32_at(data, at + 8)?,
+                register: u32_at(data, at + 16)?,
+                format: if mask > 1 { format!("{}{}", component, mask) } else { component.to_string() },
+            })
+        })
+        .collect()
+}
+
+/// Pipeline state validation: the size and contents of the runtime info, then the resource count and
+/// size of each resource record
+fn read_psv(data: &[u8]) -> Result<Vec<PsvResource>> {
+    let info_size = u32_at(data, 0)? as usize;
+    let at = 4 + info_size;
+    if at + 4 > data.len() {
+        return Ok(Vec::new());
+    }
+    let count = u32_at(data, at)? as usize;
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    let record_size = u32_at(data, at + 4)? as usize;
+    if record_size < 16 {
+        return Err(decode_err(format!("psv resource records of {} bytes", record_size)));
+    }
+    (0..count)
+        .map(|i| {
+            let at = at + 8 + i * record_size;
+            let kind = match u32_at(data, at)? {
+                1 => "sampler",
+                2 => "cbv",
+                3 => "srv_typed",
+                4 => "srv_raw",
+                5 => "srv_structured",
+                6 => "uav_typed",
+                7 => "uav_raw",
+                8 => "uav_structured",
+                9 => "uav_structured_with_counter",
+                _ => "invalid",
+            };
+            Ok(PsvResource {
+                kind,
+                space: u32_at(data, at + 4)?,
+                lower_bound: u32_at(data, at + 8)?,
+                upper_bound: u32_at(data, at + 12)?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u32s(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    /// A container assembled by hand from the chunks given, with a zeroed checksum
+    fn container(chunks: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut offsets = Vec::new();
+        let mut body = Vec::new();
+        let start = HEADER_SIZE + chunks.len() * 4;
+        for (tag, data) in chunks {
+            offsets.push((start + body.len()) as u32);
+            body.extend_from_slice(*tag);
+            body.extend(u32s(&[data.len() as u32]));
+            body.extend(data);
+        }
+        let mut buf = MAGIC.to_vec();
+        buf.extend([0; 16]);
+        buf.extend(u32s(&[1, (start + body.len()) as u32, chunks.len() as u32]));
+        buf.extend(u32s(&offsets));
+        buf.extend(body);
+        buf
+    }
+
+    /// Resource definitions for shader model 5.0 with one constant buffer holding one variable, bound
+    /// at b0
+    fn rdef() -> Vec<u8> {
+        let mut data = u32s(&[1, 28, 1, 92]);
+        data.extend([0, 5, 0xfe, 0xff]);
+        data.extend(u32s(&[0, 0]));
+        data.extend(u32s(&[124, 1, 52, 16, 0, 0]));
+        data.extend(u32s(&[132, 0, 16, 0, 0, 0, 0, 0, 0, 0]));
+        data.extend(u32s(&[124, 0, 0, 0, 0, 0, 1, 0]));
+        data.extend(b"Globals\0world\0");
+        data
+    }
+
+    /// An input signature of a float3 position and the vertex id
+    fn isgn() -> Vec<u8> {
+        let mut data = u32s(&[2, 8]);
+        data.extend(u32s(&[56, 0, 0, 3, 0, 0x0707]));
+        data.extend(u32s(&[65, 0, 6, 1, 1, 0x0101]));
+        data.extend(b"POSITION\0SV_VertexID\0");
+        data
+    }
+
+    fn vertex_shader() -> Vec<u8> {
+        container(&[(b"RDEF", rdef()), (b"ISGN", isgn()), (b"SHEX", u32s(&[0x10050, 2]))])
+    }
+
+    #[test]
+    fn finds_containers_at_any_offset() {
+        let first = vertex_shader();
+        let second = container(&[(b"SHEX", u32s(&[0x50, 2]))]);
+        let mut buf = vec![1, 2, 3];
+        buf.extend(&first);
+        buf.push(0xff);
+        // a magic without a sane header after it isn't a container
+        buf.extend(MAGIC);
+        buf.extend([0; 28]);
+        buf.extend(&second);
+        let found = find_containers(&buf);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0], (3, first.as_slice()));
+        assert_eq!(found[1], (3 + first.len() + 1 + 32, second.as_slice()));
+    }
+
+    #[test]
+    fn skips_truncated_containers() {
+        let mut buf = vertex_shader();
+        buf.truncate(buf.len() - 1);
+        assert!(find_containers(&buf).is_empty());
+    }
+
+    #[test]
+    fn reflects_a_vertex_shader() {
+        let reflection = reflect(&vertex_shader()).unwrap();
+        assert_eq!(reflection.format, Format::Dxbc);
+        assert_eq!(reflection.shader_model.as_deref(), Some("vs_5_0"));
+        assert_eq!(reflection.chunks, ["RDEF", "ISGN", "SHEX"]);
+
+        let cb = &reflection.constant_buffers[0];
+        assert_eq!((cb.name.as_str(), cb.size), ("Globals", 16));
+        assert_eq!(cb.variables[0].name, "world");
+        assert_eq!((cb.variables[0].offset, cb.variables[0].size), (0, 16));
+        let b = &reflection.bindings[0];
+        assert_eq!((b.name.as_str(), b.kind, b.bind_point, b.bind_count), ("Globals", "cbuffer", 0, 1));
+
+        let inputs: Vec<_> = reflection
+            .inputs
+            .iter()
+            .map(|e| (e.semantic.as_str(), e.register, e.system_value, e.format.as_str()))
+            .collect();
+        assert_eq!(inputs, [("POSITION", 0, 0, "float3"), ("SV_VertexID", 1, 6, "uint")]);
+    }
+
+    #[test]
+    fn rejects_chunks_past_the_end() {
+        let mut buf = vertex_shader();
+        // claim the last chunk is longer than the container
+        let at = buf.len() - 12;
+        buf[at..at + 4].copy_from_slice(&16u32.to_le_bytes());
+        assert!(reflect(&buf).is_err());
+    }
+}
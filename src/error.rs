@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+
+use crate::Id;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("tried to open nonexistent file {0:?}")]
+    MissingBundle(PathBuf),
+
+    /// .stream or .gpu_resources file an asset points into doesn't exist
+    #[error("{0} file referenced but not found")]
+    MissingCompanionFile(&'static str),
+
+    /// Asset's range runs past the end of the file or part it's read from
+    #[error("asset {id} has truncated {part} data")]
+    TruncatedAsset { id: Id, part: &'static str },
+
+    /// Asset has no data at all in the requested part, which is not corruption
+    #[error("asset {id} has no {part} data")]
+    MissingPart { id: Id, part: &'static str },
+
+    #[error("bad magic at {pos:#x}: {found}")]
+    BadMagic { pos: u64, found: String },
+
+    #[error("invalid id {0:?}")]
+    InvalidId(String),
+
+    #[error("unsupported {0}")]
+    UnsupportedType(String),
+
+    #[error("failed to decode {0}")]
+    Decode(String),
+
+    #[error("bundle {0} not in cache")]
+    UnknownBundle(Id),
+
+    #[error("config error: {0}")]
+    Config(String),
+
+    #[error("id {id} not found in cache{}", bundle.map(|b| format!(" for bundle {}", b)).unwrap_or_default())]
+    IdNotFound { id: Id, bundle: Option<Id> },
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Parse(binrw::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Png(#[from] png::EncodingError),
+
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+}
+
+impl Error {
+    /// Whether the error is confined to a single asset, so extraction can carry on with the next one
+    pub fn is_asset_error(&self) -> bool {
+        matches!(
+            self,
+            Error::MissingCompanionFile(_)
+                | Error::TruncatedAsset { .. }
+                | Error::MissingPart { .. }
+                | Error::BadMagic { .. }
+                | Error::UnsupportedType(_)
+                | Error::Decode(_)
+                | Error::IdNotFound { .. }
+                | Error::Parse(_)
+        )
+    }
+}
+
+impl From<binrw::Error> for Error {
+    fn from(e: binrw::Error) -> Self {
+        match e {
+            binrw::Error::BadMagic { pos, found } => Error::BadMagic {
+                pos,
+                found: format!("{:x?}", found),
+            },
+            e => Error::Parse(e),
+        }
+    }
+}
@@ -1,298 +1,245 @@
-// use crate::types::unit::*;
-
 use super::structs::*;
+use crate::{
+    error::{Error, Result},
+    report::Report,
+    selection::{SelectedAsset, Selection},
+    types::package,
+};
 use binrw::BinReaderExt;
 use std::{
-    collections::HashMap,
+    collections::HashSet,
     fs::File,
-    io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write},
-    path::{Path, PathBuf},
+    io::{BufReader, Seek, SeekFrom, Write},
+    path::Path,
 };
 
-// TODO: combine extract_single and extract_files, using a vec of ids instead of singular id / bundle id?
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ExtractOptions {
+    pub one_folder: bool,
+    /// Names bundle folders after the bundle's package when the name database knows it
+    pub package_folders: bool,
+    /// Carry on past any error, not just ones confined to a single asset
+    pub keep_going: bool,
+    /// Also extract the resources selected assets refer to, like the materials of a particle effect
+    pub dependencies: bool,
+    /// Also convert the types whose decoders are experimental
+    pub experimental: bool,
+}
 
-pub fn extract_single(
+/// Extracts every asset in the selection, converting the types we know and dumping the rest raw.
+pub fn extract(
     cache: &IdCache,
-    output_path: &String,
-    data_path: &String,
-    id: Id,
+    output_path: &str,
+    data_path: &str,
+    selection: &Selection,
+    opts: &ExtractOptions,
     namedb: &crate::pndb::Pndb,
-) -> anyhow::Result<()> {
-    let (bundle_id, h) = cache.get_by_id(id, None, Id::invalid())?;
-    let path = Path::new(data_path).join(bundle_id.to_string());
-    if !path.exists() {
-        return Err(anyhow::anyhow!("tried to open nonexistent file {:?}", path));
+    report: &mut Report,
+) -> Result<()> {
+    let mut assets = selection.resolve(cache, namedb);
+    if opts.dependencies {
+        assets = add_dependencies(cache, data_path, assets);
     }
-    // println!("{:?}", path);
-    let reader = BufReader::new(File::open(path)?);
-    let mut out_path = PathBuf::from(output_path); //.join(bundle_file);
-
-    let mut stream_path = Path::new(&data_path).join(bundle_id.to_string());
-    stream_path.set_extension("stream");
-    let mut stream_reader: Option<BufReader<File>> = None;
-    let mut stream_size = 0;
-    if stream_path.exists() {
-        let file = File::open(stream_path.clone())?;
-        stream_size = file.metadata()?.len();
-        stream_reader = Some(BufReader::new(file));
-    }
-
-    let mut gpu_path = Path::new(&data_path).join(bundle_id.to_string());
-    gpu_path.set_extension("gpu_resources");
-    let mut gpu_reader: Option<BufReader<File>> = None;
-    let mut gpu_size = 0;
-    if gpu_path.exists() {
-        let file = File::open(gpu_path.clone())?;
-        gpu_size = file.metadata()?.len();
-        gpu_reader = Some(BufReader::new(file));
-    }
-    let mut d: DataHeader = h.into();
-
-    let mut readers: DataReaders = DataReaders(reader, stream_reader, gpu_reader);
-
-    if export_special(
-        cache,
-        &Id::invalid(),
-        &mut d,
-        &mut readers,
-        &out_path,
-        namedb,
-    )? {
+    if assets.is_empty() {
+        println!("Nothing to extract.");
         return Ok(());
     }
 
-    let mut bundle_buf: Vec<u8> = Vec::new();
-    let mut stream_buf: Vec<u8> = Vec::new();
-    let mut gpu_buf: Vec<u8> = Vec::new();
-    if h.data_size != 0 {
-        bundle_buf = vec![0u8; h.data_size as usize];
-        readers.bundle().seek(SeekFrom::Start(h.data_offset))?;
-        readers.bundle().read_exact(&mut bundle_buf)?;
-    }
-    if h.stream_data_size != 0 && u64::from(h.stream_data_offset) < stream_size {
-        stream_buf = vec![0u8; h.stream_data_size as usize];
-        if readers.stream().is_none() {
-            panic!(
-                "Stream file referenced but {:?} not found.",
-                stream_path.clone()
-            );
-        }
-        if let Some(ref mut sf_reader) = readers.stream() {
-            sf_reader.seek(SeekFrom::Start(h.stream_data_offset as u64))?;
-            sf_reader.read_exact(&mut stream_buf)?;
-        }
-    }
-    if h.gpu_data_size != 0 && h.gpu_data_offset < gpu_size {
-        gpu_buf = vec![0u8; h.gpu_data_size as usize];
-        if readers.gpu().is_none() {
-            panic!(
-                "GPU Resources referenced but {:?} not founh.",
-                gpu_path.clone()
-            );
+    let packages = package::bundle_packages(cache);
+    for bundle_assets in assets.chunk_by(|a, b| a.bundle == b.bundle) {
+        let bundle_id = bundle_assets[0].bundle;
+        let mut readers = match DataReaders::open(data_path, &bundle_id) {
+            Ok(r) => r,
+            Err(e) => {
+                if !opts.keep_going && !e.is_asset_error() {
+                    return Err(e);
+                }
+                println!("Failed to open bundle {}: {}", bundle_id, e);
+                report.record(bundle_id, Id::invalid(), Id::invalid(), Err(e.to_string()));
+                continue;
+            }
+        };
+
+        let mut out_path = Path::new(output_path).join(bundle_id.to_string());
+        if opts.package_folders {
+            if let Some(name) = package::bundle_name(&packages, namedb, &bundle_id) {
+                out_path = Path::new(output_path).join(name);
+            }
         }
-        if let Some(ref mut gpu_reader) = readers.gpu() {
-            gpu_reader.seek(SeekFrom::Start(h.gpu_data_size as u64))?;
-            gpu_reader.read_exact(&mut gpu_buf)?;
+        if opts.one_folder {
+            out_path = Path::new(output_path).to_path_buf();
         }
-    }
 
-    let enum_type: DataTypes = num::FromPrimitive::from_u64(d.type_id.into()).unwrap_or_default();
-    let type_folder = format!("{:?}_{:x?}", enum_type, d.type_id);
-    out_path = out_path.join(type_folder);
-    if namedb.name_database.contains_key(&d.unk_id) {
-        out_path = out_path.join(namedb.name_database.get(&d.unk_id).unwrap());
-    }
-    if !out_path.exists() {
-        let _ = std::fs::create_dir_all(&out_path);
+        for asset in bundle_assets {
+            let mut d: DataHeader = asset.header.into();
+            let experimental = crate::types::is_experimental(d.type_enum);
+            let special = if opts.experimental || !experimental {
+                export_special(cache, asset, &mut d, &mut readers, &out_path, data_path, namedb)
+            } else {
+                Ok(None)
+            };
+            let result = match special {
+                Ok(Some(written)) => Ok(Some((written, None))),
+                Ok(None) => export_raw(asset.index, &d, &mut readers, &out_path, namedb, experimental)
+                    .map(|raw| raw.map(|(written, e)| (written, e.map(|e| e.to_string())))),
+                // keep what the asset had when it can't be converted, it used to be dumped raw anyway
+                Err(e) if e.is_asset_error() => match export_raw(asset.index, &d, &mut readers, &out_path, namedb, true) {
+                    Ok(Some((written, None))) => Ok(Some((written, Some(format!("{}, dumped it raw", e))))),
+                    Ok(Some((written, Some(raw_error)))) => {
+                        Ok(Some((written, Some(format!("{}, dumped it raw but {}", e, raw_error)))))
+                    }
+                    Ok(None) | Err(_) => Err(e),
+                },
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(Some((written, None))) => report.record(bundle_id, d.unk_id, d.type_id, Ok(written)),
+                Ok(Some((written, Some(e)))) => {
+                    println!("Partly extracted {} from {}: {}", d.unk_id, bundle_id, e);
+                    report.partial(bundle_id, d.unk_id, d.type_id, written, e);
+                }
+                Ok(None) => report.skip(bundle_id, d.unk_id, d.type_id),
+                // one broken asset shouldn't take the rest of the bundle down with it
+                Err(e) => {
+                    if !opts.keep_going && !e.is_asset_error() {
+                        return Err(e);
+                    }
+                    println!("Failed to extract {} from {}: {}", d.unk_id, bundle_id, e);
+                    report.record(bundle_id, d.unk_id, d.type_id, Err(e.to_string()));
+                }
+            }
+        }
     }
-    out_path = out_path.join(format!("{}_{}", d.unk4c, d.unk_id));
+    Ok(())
+}
 
-    if !bundle_buf.is_empty() {
-        let mut bundle = out_path.clone();
-        bundle.set_extension("bundle.".to_owned() + d.type_enum.extension());
-        let mut out_file = File::create(bundle)?;
-        out_file.write_all(&bundle_buf)?;
-    }
-    if !stream_buf.is_empty() {
-        let mut stream = out_path.clone();
-        stream.set_extension("stream.".to_owned() + d.type_enum.extension());
-        let mut out_file = BufWriter::new(File::create(stream)?);
-        out_file.write_all(&stream_buf)?;
-    }
-    if !gpu_buf.is_empty() {
-        let mut gpu = out_path.clone();
-        gpu.set_extension("gpu.".to_owned() + d.type_enum.extension());
-        let mut out_file = File::create(gpu)?;
-        out_file.write_all(&gpu_buf)?;
+/// Adds what the assets refer to, and what that refers to in turn, keeping them grouped by bundle.
+/// Assets that can't be read just don't add anything, extracting them will report the error.
+pub fn add_dependencies(cache: &IdCache, data_path: &str, mut assets: Vec<SelectedAsset>) -> Vec<SelectedAsset> {
+    let mut seen: HashSet<Id> = assets.iter().map(|a| a.header.id).collect();
+    let mut next = 0;
+    while next < assets.len() {
+        let asset = assets[next];
+        next += 1;
+        let d = DataHeader::from(asset.header);
+        let found = DataReaders::open(data_path, &asset.bundle)
+            .and_then(|mut r| crate::types::dependencies(cache, &d, &mut r));
+        for id in found.unwrap_or_default() {
+            if !seen.insert(id) {
+                continue;
+            }
+            let Ok((bundle, header)) = cache.get_by_id(id, None, Id::invalid()) else {
+                continue;
+            };
+            if let Some(index) = cache.bundles[&bundle].iter().position(|h| h.id == id) {
+                assets.push(SelectedAsset { bundle, index, header });
+            }
+        }
     }
-
-    Ok(())
+    assets.sort_by_key(|a| (u64::from(a.bundle), a.index));
+    assets
 }
 
-pub fn extract_files(
-    cache: &IdCache,
-    output_path: &String,
-    data_path: &String,
-    bundle_file: &String,
-    select_type: Option<DataTypes>,
-    one_folder: bool,
+/// Dumps whichever of the bundle/stream/gpu parts of an asset can be read. Returns `None` if the
+/// asset has no data at all, and the error of the last part that couldn't be read alongside what
+/// was written if only some could. `unconverted` is for assets whose converter wasn't run or failed,
+/// so the extension of what they convert to doesn't apply to their parts.
+fn export_raw(
+    index: usize,
+    d: &DataHeader,
+    readers: &mut DataReaders,
+    out_path: &Path,
     namedb: &crate::pndb::Pndb,
-) -> anyhow::Result<()> {
-    let path = Path::new(data_path).join(bundle_file);
-    if !path.exists() {
-        panic!("Tried to open nonexistent file {:?}", path);
+    unconverted: bool,
+) -> Result<Option<(u64, Option<Error>)>> {
+    let mut parts: Vec<(&str, Vec<u8>)> = Vec::new();
+    let mut last_error: Option<Error> = None;
+    for (part, size) in [
+        ("bundle", d.data_size),
+        ("stream", d.stream_data_size),
+        ("gpu", d.gpu_data_size),
+    ] {
+        if size == 0 {
+            continue;
+        }
+        let buf = match part {
+            "bundle" => d.get_bundle_buf(readers.bundle()),
+            "stream" => d.get_stream_buf(readers.stream()),
+            _ => d.get_gpu_buf(readers.gpu()),
+        };
+        match buf {
+            Ok(buf) => parts.push((part, buf)),
+            Err(e) if e.is_asset_error() => last_error = Some(e),
+            Err(e) => return Err(e),
+        }
     }
-    // println!("{:?}", path);
-    let f = File::open(path)?;
-
-    let mut reader = BufReader::new(f);
-
-    let header: Header = reader.read_le()?;
-
-    let types: Vec<DataType> = read_types(&mut reader, header)?;
-    let mut types_dict: HashMap<Id, &DataType> = Default::default();
-    for t in &types {
-        types_dict.insert(t.type_id, t);
+    if parts.is_empty() {
+        return match last_error {
+            Some(e) => Err(e),
+            None => Ok(None),
+        };
     }
 
-    let mut data_headers: Vec<DataHeader> = read_data_headers(&mut reader, &types)?;
-    let mut stream_path = Path::new(&data_path).join(bundle_file);
-    stream_path.set_extension("stream");
-    let stream_reader: Option<BufReader<File>>;
-    let mut stream_size = 0;
-
-    let mut gpu_path = Path::new(&data_path).join(bundle_file);
-    gpu_path.set_extension("gpu_resources");
-    let gpu_reader: Option<BufReader<File>>;
-    let mut gpu_size = 0;
-
-    let mut readers = DataReaders::new(reader);
-
-    if stream_path.exists() {
-        let file = File::open(stream_path.clone())?;
-        stream_size = file.metadata()?.len();
-        stream_reader = Some(BufReader::new(file));
-        readers.set_stream(stream_reader.unwrap())
+    let mut out_path = out_path.join(format!("{:?}_{:x?}", d.type_enum, d.type_id));
+    if let Some(name) = namedb.name_database.get(&d.unk_id) {
+        out_path = out_path.join(name);
     }
-    if gpu_path.exists() {
-        let file = File::open(gpu_path.clone())?;
-        gpu_size = file.metadata()?.len();
-        gpu_reader = Some(BufReader::new(file));
-        readers.set_gpu(gpu_reader.unwrap())
+    std::fs::create_dir_all(&out_path)?;
+    out_path = out_path.join(format!("{}_{}", index, d.unk_id));
+
+    let mut written = 0;
+    for (part, buf) in parts {
+        // for types we don't know, guess from the data itself
+        let extension = match d.type_enum.extension() {
+            _ if unconverted => "bin",
+            "bin" => crate::sniff::sniff(&buf).map(|m| m.extension()).unwrap_or("bin"),
+            extension => extension,
+        };
+        let mut path = out_path.clone();
+        path.set_extension(format!("{}.{}", part, extension));
+        let mut out_file = File::create(path)?;
+        out_file.write_all(&buf)?;
+        written += buf.len() as u64;
     }
+    Ok(Some((written, last_error)))
+}
 
-    // println!("{:#?}", types_dict);
-
-    for i in 0..data_headers.len() {
-        let d = data_headers.get_mut(i).unwrap();
-        // println!("{:#?}", d.unk_id);
-        d.type_enum = num::FromPrimitive::from_u64(d.type_id.into()).unwrap_or_default();
-        if select_type.is_some() && d.type_enum != select_type.unwrap() {
+/// Ids of every bundle file in the data directory, sorted
+pub fn bundle_ids(data_path: &str) -> Result<Vec<Id>> {
+    let mut ids = Vec::new();
+    for entry in std::fs::read_dir(data_path)? {
+        let bundle_name = entry?.file_name();
+        let Some(bundle_name) = bundle_name.to_str() else {
             continue;
-        }
-        // let file_ext = match d.type_enum {
-        //     DataTypes::WwiseWem => "wem",
-        //     DataTypes::WwiseBNK => "bnk",
-        //     DataTypes::Havok => "hxt",
-        //     DataTypes::Texture => "dds",
-        //     DataTypes::Model => "obj",
-        //     _ => "bin",
-        // };
-        let mut out_path = Path::new(output_path).join(bundle_file);
-        if one_folder {
-            out_path = Path::new(output_path).to_path_buf();
-        }
-        if export_special(
-            cache,
-            &Id::from(u64::from_str_radix(bundle_file, 16)?),
-            d,
-            &mut readers,
-            &out_path,
-            namedb,
-        )? {
-            // return Ok(());
+        };
+        if bundle_name.contains('.') || bundle_name == "game" {
             continue;
         }
-        let mut bundle_buf: Vec<u8> = Vec::new();
-        let mut stream_buf: Vec<u8> = Vec::new();
-        let mut gpu_buf: Vec<u8> = Vec::new();
-        if d.data_size != 0 {
-            bundle_buf = vec![0u8; d.data_size as usize];
-            // this is wrong, why did i do this
-            // let seek_pos = d.data_offset + types_dict.get(&d.type_id).unwrap().unk10 as u64;
-            // if seek_pos + d.data_offset >= bundle_size {
-            //     continue;
-            // }
-            readers.bundle().seek(SeekFrom::Start(d.data_offset))?;
-            readers.bundle().read_exact(&mut bundle_buf)?;
-        }
-        if d.stream_data_size != 0 && u64::from(d.stream_data_offset) < stream_size {
-            stream_buf = vec![0u8; d.stream_data_size as usize];
-            if readers.stream().is_none() {
-                panic!(
-                    "Stream file referenced but {:?} not found.",
-                    stream_path.clone()
-                );
-            }
-            if let Some(ref mut sf_reader) = readers.stream() {
-                sf_reader.seek(SeekFrom::Start(d.stream_data_offset as u64))?;
-                sf_reader.read_exact(&mut stream_buf)?;
-            }
-        }
-        if d.gpu_data_size != 0 && d.gpu_data_offset < gpu_size {
-            gpu_buf = vec![0u8; d.gpu_data_size as usize];
-            if readers.gpu().is_none() {
-                panic!(
-                    "GPU Resources referenced but {:?} not found.",
-                    gpu_path.clone()
-                );
-            }
-            if let Some(ref mut gpu_reader) = readers.gpu() {
-                gpu_reader.seek(SeekFrom::Start(d.gpu_data_size as u64))?;
-                gpu_reader.read_exact(&mut gpu_buf)?;
-            }
-        }
-
-        let enum_type: DataTypes =
-            num::FromPrimitive::from_u64(d.type_id.into()).unwrap_or_default();
-        let type_folder = format!("{:?}_{:x?}", enum_type, d.type_id);
-        out_path = out_path.join(type_folder);
-        if namedb.name_database.contains_key(&d.unk_id) {
-            out_path = out_path.join(namedb.name_database.get(&d.unk_id).unwrap());
-        }
-        if !out_path.exists() {
-            let _ = std::fs::create_dir_all(&out_path);
+        if let Ok(bundle_id) = bundle_name.parse::<Id>() {
+            ids.push(bundle_id);
         }
-        out_path = out_path.join(format!("{}_{}", i, d.unk_id));
+    }
+    ids.sort_by_key(|b| u64::from(*b));
+    Ok(ids)
+}
 
-        if !bundle_buf.is_empty() {
-            let mut bundle = out_path.clone();
-            bundle.set_extension("bundle.".to_owned() + d.type_enum.extension());
-            let mut out_file = File::create(bundle)?;
-            out_file.write_all(&bundle_buf)?;
-        }
-        if !stream_buf.is_empty() {
-            let mut stream = out_path.clone();
-            stream.set_extension("stream.".to_owned() + d.type_enum.extension());
-            let out_file = File::create(stream);
-            if out_file.is_err() {
-                println!("{:?}", out_file.err());
-                continue;
-            }
-            let mut out_file = out_file.unwrap();
-            out_file.write_all(&stream_buf)?;
-        }
-        if !gpu_buf.is_empty() {
-            let mut gpu = out_path.clone();
-            gpu.set_extension("gpu.".to_owned() + d.type_enum.extension());
-            let mut out_file = File::create(gpu)?;
-            out_file.write_all(&gpu_buf)?;
-        }
+/// Reads a bundle's header, type table and data headers from the start of the file
+pub fn read_bundle(r: &mut BufReader<File>) -> Result<Bundle> {
+    r.seek(SeekFrom::Start(0))?;
+    let header: Header = r.read_le()?;
+    let data_types = read_types(r, &header)?;
+    let mut data_headers = read_data_headers(r, &data_types)?;
+    for d in &mut data_headers {
+        d.type_enum = d.type_id.as_enum();
     }
-    Ok(())
+    Ok(Bundle {
+        header,
+        data_types,
+        data_headers,
+    })
 }
 
-pub fn read_types(r: &mut BufReader<File>, h: Header) -> anyhow::Result<Vec<DataType>> {
+pub fn read_types(r: &mut BufReader<File>, h: &Header) -> Result<Vec<DataType>> {
     let mut types: Vec<DataType> = vec![];
     for i in 0..h.type_count {
         let t: DataType = r.read_le()?;
@@ -307,7 +254,7 @@ pub fn read_types(r: &mut BufReader<File>, h: Header) -> anyhow::Result<Vec<Data
 pub fn read_data_headers(
     r: &mut BufReader<File>,
     t: &Vec<DataType>,
-) -> anyhow::Result<Vec<DataHeader>> {
+) -> Result<Vec<DataHeader>> {
     let mut headers: Vec<DataHeader> = vec![];
     for type1 in t {
         for _ in 0..type1.data_count {
@@ -318,42 +265,90 @@ pub fn read_data_headers(
     Ok(headers)
 }
 
-pub fn export_special(
+/// An asset converted by `convert`
+pub struct Converted {
+    pub data: Vec<u8>,
+    /// The file name stored in the asset, if any
+    pub file_name: Option<String>,
+    /// Files that came out of the same decoding, to write next to the converted asset
+    pub attachments: crate::types::Attachments,
+}
+
+/// Converts an asset of a type we have a handler for. `None` for types that only get dumped raw.
+pub fn convert(
     cache: &IdCache,
+    namedb: &crate::pndb::Pndb,
     bundle_id: &Id,
     d: &mut DataHeader,
     r: &mut DataReaders,
-    out_path: &Path,
-    namedb: &crate::pndb::Pndb,
-) -> anyhow::Result<bool> {
-    let (out_buf, mut file_name) = match d.type_enum {
+) -> Result<Option<Converted>> {
+    let mut attachments = Vec::new();
+    let (data, file_name) = match d.type_enum {
         DataTypes::Texture => crate::types::texture::extract_texture(d, r)?,
         DataTypes::Unit => crate::types::unit::extract_unit(cache, d, r)?,
         DataTypes::WwiseBNK => crate::types::wwise::extract_bank(cache, bundle_id, d, r)?,
         DataTypes::WwiseWem => crate::types::wwise::extract_wem(d, r)?,
         DataTypes::String => crate::types::string::extract_strings(d, r)?,
+        DataTypes::Package => crate::types::package::extract_package(d, r)?,
+        DataTypes::Lua => crate::types::lua::extract_lua(d, r)?,
+        DataTypes::Level => crate::types::level::extract_level(cache, namedb, d, r)?,
+        DataTypes::Physics | DataTypes::HavokPhysicsProperties | DataTypes::RagdollProfile => {
+            crate::types::physics::extract_physics(d, r)?
+        }
+        DataTypes::Animation => crate::types::animation::extract_animation(d, r)?,
+        DataTypes::Bones => crate::types::bones::extract_bones(d, r)?,
+        DataTypes::Shader | DataTypes::ShaderLibrary => {
+            let (json, blobs) = crate::types::shader::extract_shaders(d, r)?;
+            attachments = blobs;
+            (json, None)
+        }
+        DataTypes::Font => crate::types::font::extract_font(cache, namedb, d, r)?,
+        DataTypes::Navdata => crate::types::navmesh::extract_navdata(d, r)?,
         _ => {
-            return Ok(false);
+            return Ok(None);
         }
     };
+    Ok(Some(Converted {
+        data,
+        file_name,
+        attachments,
+    }))
+}
+
+pub fn export_special(
+    cache: &IdCache,
+    asset: &SelectedAsset,
+    d: &mut DataHeader,
+    r: &mut DataReaders,
+    out_path: &Path,
+    data_path: &str,
+    namedb: &crate::pndb::Pndb,
+) -> Result<Option<u64>> {
+    let Some(Converted {
+        data: out_buf,
+        mut file_name,
+        mut attachments,
+    }) = convert(cache, namedb, &asset.bundle, d, r)?
+    else {
+        return Ok(None);
+    };
 
     // println!("{:?}", file_name);
 
-    let enum_type: DataTypes = num::FromPrimitive::from_u64(d.type_id.into()).unwrap_or_default();
-    let mut out_path = out_path.join(format!("{:?}", enum_type));
+    let mut out_path = out_path.join(format!("{:?}", d.type_enum));
     // if !out_path.exists() {
     //     let _ = std::fs::create_dir_all(&out_path);
     // }
     // out_path = out_path.join(format!("{}_{}", d.unk4c, d.unk_id));
 
-    if file_name.is_none() && namedb.name_database.contains_key(&d.unk_id) {
+    if file_name.is_none() {
         file_name = namedb.name_database.get(&d.unk_id).cloned();
     }
 
     let name = if let Some(file_name) = file_name {
         file_name
     } else {
-        format!("{}_{}", d.unk4c, d.unk_id)
+        format!("{}_{}", asset.index, d.unk_id)
     };
     // println!("{:?}", name);
     out_path = out_path.join(name);
@@ -362,17 +357,25 @@ pub fn export_special(
     //     || (out_path.is_file() && !out_path.parent().unwrap().exists())
     // {
     // }
-    let p = if out_path.is_dir() {
-        out_path.clone()
-    } else {
-        out_path.parent().unwrap().to_path_buf()
-    };
-    std::fs::create_dir_all(p)?;
+    if let Some(p) = out_path.parent() {
+        std::fs::create_dir_all(p)?;
+    }
 
     // println!("{:?}", &out_path);
 
     let mut out_file = File::create(&out_path)?;
     out_file.write_all(&out_buf)?;
+    let mut written = out_buf.len() as u64;
+
+    attachments.extend(crate::types::attachments(cache, data_path, d, r)?);
+    if !attachments.is_empty() {
+        let dir = out_path.with_extension("");
+        std::fs::create_dir_all(&dir)?;
+        for (name, data) in attachments {
+            std::fs::write(dir.join(name), &data)?;
+            written += data.len() as u64;
+        }
+    }
 
-    Ok(true)
+    Ok(Some(written))
 }
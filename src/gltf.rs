@@ -0,0 +1,206 @@
+use serde_json::{json, Value};
+
+use crate::error::Result;
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+
+/// Minimal binary glTF (.glb) builder. Everything lives in a single buffer, and the json parts are
+/// pushed as raw values so callers can add whatever extras they need.
+#[derive(Debug, Default)]
+pub struct Glb {
+    pub bin: Vec<u8>,
+    pub buffer_views: Vec<Value>,
+    pub accessors: Vec<Value>,
+    pub meshes: Vec<Value>,
+    pub nodes: Vec<Value>,
+    pub skins: Vec<Value>,
+    pub animations: Vec<Value>,
+    /// Root nodes of the default scene
+    pub scene: Vec<usize>,
+}
+
+impl Glb {
+    pub fn push_view(&mut self, data: &[u8], target: Option<u32>) -> usize {
+        // accessors need 4 byte alignment
+        while !self.bin.len().is_multiple_of(4) {
+            self.bin.push(0);
+        }
+        let mut view = json!({
+            "buffer": 0,
+            "byteOffset": self.bin.len(),
+            "byteLength": data.len(),
+        });
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+        self.bin.extend_from_slice(data);
+        self.buffer_views.push(view);
+        self.buffer_views.len() - 1
+    }
+
+    pub fn push_accessor(&mut self, accessor: Value) -> usize {
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn push_floats<const N: usize>(
+        &mut self,
+        values: &[[f32; N]],
+        kind: &str,
+        bounds: bool,
+        target: Option<u32>,
+    ) -> usize {
+        let data: Vec<u8> = values
+            .iter()
+            .flatten()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+        let view = self.push_view(&data, target);
+        let mut accessor = json!({
+            "bufferView": view,
+            "componentType": FLOAT,
+            "count": values.len(),
+            "type": kind,
+        });
+        // POSITION and animation input accessors are required to have bounds
+        if bounds && !values.is_empty() {
+            let mut min = [f32::MAX; N];
+            let mut max = [f32::MIN; N];
+            for v in values {
+                for i in 0..N {
+                    min[i] = min[i].min(v[i]);
+                    max[i] = max[i].max(v[i]);
+                }
+            }
+            accessor["min"] = json!(min.to_vec());
+            accessor["max"] = json!(max.to_vec());
+        }
+        self.push_accessor(accessor)
+    }
+
+    pub fn push_vec2(&mut self, values: &[[f32; 2]]) -> usize {
+        self.push_floats(values, "VEC2", false, Some(ARRAY_BUFFER))
+    }
+
+    pub fn push_vec3(&mut self, values: &[[f32; 3]], bounds: bool) -> usize {
+        self.push_floats(values, "VEC3", bounds, Some(ARRAY_BUFFER))
+    }
+
+    /// Keyframe times for an animation sampler
+    pub fn push_times(&mut self, times: &[f32]) -> usize {
+        let values: Vec<[f32; 1]> = times.iter().map(|t| [*t]).collect();
+        self.push_floats(&values, "SCALAR", true, None)
+    }
+
+    /// Keyframe values for an animation sampler, VEC3 or VEC4
+    pub fn push_keys<const N: usize>(&mut self, values: &[[f32; N]]) -> usize {
+        let kind = if N == 4 { "VEC4" } else { "VEC3" };
+        self.push_floats(values, kind, false, None)
+    }
+
+    pub fn push_indices(&mut self, indices: &[u32]) -> usize {
+        let data: Vec<u8> = indices.iter().flat_map(|x| x.to_le_bytes()).collect();
+        let view = self.push_view(&data, Some(ELEMENT_ARRAY_BUFFER));
+        self.push_accessor(json!({
+            "bufferView": view,
+            "componentType": UNSIGNED_INT,
+            "count": indices.len(),
+            "type": "SCALAR",
+        }))
+    }
+
+    pub fn push_mesh(&mut self, mesh: Value) -> usize {
+        self.meshes.push(mesh);
+        self.meshes.len() - 1
+    }
+
+    pub fn push_node(&mut self, node: Value) -> usize {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    pub fn push_skin(&mut self, skin: Value) -> usize {
+        self.skins.push(skin);
+        self.skins.len() - 1
+    }
+
+    pub fn push_animation(&mut self, animation: Value) -> usize {
+        self.animations.push(animation);
+        self.animations.len() - 1
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut root = json!({
+            "asset": { "version": "2.0", "generator": "helldivers2-rs" },
+            "scene": 0,
+            "scenes": [{ "nodes": self.scene }],
+        });
+        // top level arrays aren't allowed to be empty
+        for (key, values) in [
+            ("nodes", &self.nodes),
+            ("meshes", &self.meshes),
+            ("skins", &self.skins),
+            ("animations", &self.animations),
+            ("accessors", &self.accessors),
+            ("bufferViews", &self.buffer_views),
+        ] {
+            if !values.is_empty() {
+                root[key] = json!(values);
+            }
+        }
+        if !self.bin.is_empty() {
+            root["buffers"] = json!([{ "byteLength": self.bin.len() }]);
+        }
+        root
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut json = serde_json::to_vec(&self.to_json())?;
+        while !json.len().is_multiple_of(4) {
+            json.push(b' ');
+        }
+        let mut bin = self.bin.clone();
+        while !bin.len().is_multiple_of(4) {
+            bin.push(0);
+        }
+        let bin_chunk = if bin.is_empty() { 0 } else { 8 + bin.len() };
+
+        let mut out = Vec::with_capacity(12 + 8 + json.len() + bin_chunk);
+        out.extend_from_slice(b"glTF");
+        out.extend_from_slice(&2u32.to_le_bytes());
+        out.extend_from_slice(&((12 + 8 + json.len() + bin_chunk) as u32).to_le_bytes());
+        out.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        out.extend_from_slice(b"JSON");
+        out.extend_from_slice(&json);
+        if !bin.is_empty() {
+            out.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+            out.extend_from_slice(b"BIN\0");
+            out.extend_from_slice(&bin);
+        }
+        Ok(out)
+    }
+}
+
+/// Rotation matrix, with the rotated axes as rows the way the engine stores them, to an x, y, z, w
+/// quaternion
+pub fn matrix_to_quat(r: &[[f32; 3]; 3]) -> [f32; 4] {
+    // written for column vectors, so work on the transpose
+    let m = |row: usize, col: usize| r[col][row];
+    let trace = m(0, 0) + m(1, 1) + m(2, 2);
+    if trace > 0.0 {
+        let s = (trace + 1.0).sqrt() * 2.0;
+        [(m(2, 1) - m(1, 2)) / s, (m(0, 2) - m(2, 0)) / s, (m(1, 0) - m(0, 1)) / s, 0.25 * s]
+    } else if m(0, 0) > m(1, 1) && m(0, 0) > m(2, 2) {
+        let s = (1.0 + m(0, 0) - m(1, 1) - m(2, 2)).sqrt() * 2.0;
+        [0.25 * s, (m(0, 1) + m(1, 0)) / s, (m(0, 2) + m(2, 0)) / s, (m(2, 1) - m(1, 2)) / s]
+    } else if m(1, 1) > m(2, 2) {
+        let s = (1.0 + m(1, 1) - m(0, 0) - m(2, 2)).sqrt() * 2.0;
+        [(m(0, 1) + m(1, 0)) / s, 0.25 * s, (m(1, 2) + m(2, 1)) / s, (m(0, 2) - m(2, 0)) / s]
+    } else {
+        let s = (1.0 + m(2, 2) - m(0, 0) - m(1, 1)).sqrt() * 2.0;
+        [(m(0, 2) + m(2, 0)) / s, (m(1, 2) + m(2, 1)) / s, 0.25 * s, (m(1, 0) - m(0, 1)) / s]
+    }
+}
@@ -0,0 +1,89 @@
+// MurmurHash64A, which Stingray uses for resource names and type names
+
+const M: u64 = 0xc6a4a7935bd1e995;
+const R: u32 = 47;
+
+pub const fn murmur64a(key: &[u8], seed: u64) -> u64 {
+    let len = key.len();
+    let mut h = seed ^ (len as u64).wrapping_mul(M);
+
+    let mut i = 0;
+    while i + 8 <= len {
+        let mut k = u64::from_le_bytes([
+            key[i],
+            key[i + 1],
+            key[i + 2],
+            key[i + 3],
+            key[i + 4],
+            key[i + 5],
+            key[i + 6],
+            key[i + 7],
+        ]);
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h ^= k;
+        h = h.wrapping_mul(M);
+        i += 8;
+    }
+
+    if i < len {
+        let mut j = 0;
+        while i + j < len {
+            h ^= (key[i + j] as u64) << (8 * j);
+            j += 1;
+        }
+        h = h.wrapping_mul(M);
+    }
+
+    h ^= h >> R;
+    h = h.wrapping_mul(M);
+    h ^= h >> R;
+    h
+}
+
+/// Type id of a resource type, e.g. `type_hash("texture")`
+pub const fn type_hash(name: &str) -> u64 {
+    murmur64a(name.as_bytes(), 0)
+}
+
+/// 32 bit name hash, the top half of the 64 bit one. Used for bone and node names.
+pub const fn hash32(name: &str) -> u32 {
+    (murmur64a(name.as_bytes(), 0) >> 32) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn type_hashes_match_the_game() {
+        // ids as they appear in the bundles' type tables
+        assert_eq!(type_hash("texture"), 0xCD4238C6_A0C69E32);
+        assert_eq!(type_hash("unit"), 0xE0A48D0B_E9A7453F);
+        assert_eq!(type_hash("strings"), 0x0D972BAB_10B40FD3);
+        assert_eq!(type_hash("lua"), 0xA14E8DFA_2CD117E2);
+        assert_eq!(type_hash("wwise_bank"), 0x535A7BD3_E650D799);
+        assert_eq!(type_hash("wwise_stream"), 0x504B5523_5D21440E);
+        assert_eq!(type_hash("wwise_dep"), 0xAF32095C_82F2B070);
+    }
+
+    #[test]
+    fn empty_name_hashes_to_zero() {
+        assert_eq!(type_hash(""), 0);
+        assert_eq!(hash32(""), 0);
+    }
+
+    #[test]
+    fn hash32_is_the_top_half() {
+        for name in ["texture", "root_point", "a", "exactly8", "more than eight bytes"] {
+            assert_eq!(hash32(name) as u64, type_hash(name) >> 32);
+        }
+        assert_eq!(hash32("unit"), 0xE0A48D0B);
+    }
+
+    #[test]
+    fn seed_changes_the_hash() {
+        assert_ne!(murmur64a(b"texture", 1), type_hash("texture"));
+    }
+}
@@ -0,0 +1,1090 @@
+// Havok tagfiles (TAG0, 2015 and later) and packfiles (older, 0x57e0e057). Both get decoded into the same
+// generic tree: the type table plus every object, with pointers between objects kept as references.
+
+use std::collections::HashMap;
+
+use serde::{ser::SerializeMap, Serialize, Serializer};
+
+use crate::error::{Error, Result};
+
+const PACKFILE_MAGIC: [u8; 8] = [0x57, 0xe0, 0xe0, 0x57, 0x10, 0xc0, 0xc0, 0x10];
+
+// Types can nest by value, which a broken type table could make endless
+const MAX_DEPTH: usize = 32;
+
+/// A decoded value. Pointers become `Ref`s to the index of the object they point to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    String(String),
+    Ref(usize),
+    Array(Vec<Value>),
+    /// Members in declaration order, base classes first
+    Object(Vec<(String, Value)>),
+    /// Data we don't know the layout of
+    Bytes(Vec<u8>),
+}
+
+impl Value {
+    /// Member of an object, with or without the `m_` prefix older files use
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        let Value::Object(members) = self else {
+            return None;
+        };
+        members
+            .iter()
+            .find(|(n, _)| n == name || n.strip_prefix("m_") == Some(name))
+            .map(|(_, v)| v)
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::Int(i) => Some(i as f64),
+            Value::UInt(u) => Some(u as f64),
+            Value::Float(f) => Some(f),
+            _ => None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        match *self {
+            Value::Int(i) => usize::try_from(i).ok(),
+            Value::UInt(u) => usize::try_from(u).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> &[Value] {
+        match self {
+            Value::Array(values) => values,
+            _ => &[],
+        }
+    }
+
+    /// Byte arrays come out of tagfiles as arrays of ints
+    pub fn as_bytes(&self) -> Option<Vec<u8>> {
+        match self {
+            Value::Bytes(bytes) => Some(bytes.clone()),
+            Value::Array(values) => values.iter().map(|v| v.as_usize().and_then(|b| u8::try_from(b).ok())).collect(),
+            _ => None,
+        }
+    }
+
+    /// Every number in the value depth first, for vectors and transforms whatever type wraps them
+    pub fn floats(&self) -> Vec<f32> {
+        match self {
+            Value::Array(values) => values.iter().flat_map(|v| v.floats()).collect(),
+            Value::Object(members) => members.iter().flat_map(|(_, v)| v.floats()).collect(),
+            v => v.as_f64().map(|f| vec![f as f32]).unwrap_or_default(),
+        }
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, s: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            Value::Null => s.serialize_unit(),
+            Value::Bool(b) => s.serialize_bool(*b),
+            Value::Int(i) => s.serialize_i64(*i),
+            Value::UInt(u) => s.serialize_u64(*u),
+            Value::Float(f) => s.serialize_f64(*f),
+            Value::String(v) => s.serialize_str(v),
+            Value::Ref(index) => {
+                let mut map = s.serialize_map(Some(1))?;
+                map.serialize_entry("$ref", index)?;
+                map.end()
+            }
+            Value::Array(values) => values.serialize(s),
+            Value::Object(members) => {
+                let mut map = s.serialize_map(Some(members.len()))?;
+                for (name, value) in members {
+                    map.serialize_entry(name, value)?;
+                }
+                map.end()
+            }
+            Value::Bytes(bytes) => s.serialize_str(&bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Tagfile,
+    /// A tagfile holding only types, which other tagfiles can refer to
+    Compendium,
+    Packfile,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HavokFile {
+    pub format: Format,
+    /// SDK version, e.g. "20160200" for tagfiles or "hk_2014.1.0-r1" for packfiles
+    pub version: String,
+    pub types: Vec<HavokType>,
+    pub objects: Vec<HavokObject>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HavokType {
+    pub index: usize,
+    pub name: String,
+    /// "class", "int", "array" etc
+    pub kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<u64>,
+    /// Packfiles only name their classes, with a hash of the layout
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub members: Vec<HavokMember>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HavokMember {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_name: String,
+    pub offset: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HavokObject {
+    /// What `Value::Ref`s to this object hold
+    pub index: usize,
+    #[serde(rename = "type")]
+    pub type_name: String,
+    pub value: Value,
+}
+
+impl HavokFile {
+    pub fn object(&self, index: usize) -> Option<&HavokObject> {
+        self.objects.iter().find(|o| o.index == index)
+    }
+
+    /// Objects of a class, matching the name without template parameters
+    pub fn objects_of<'a>(&'a self, class: &'a str) -> impl Iterator<Item = &'a HavokObject> {
+        self.objects.iter().filter(move |o| o.type_name.split('<').next() == Some(class))
+    }
+}
+
+/// Decodes a tagfile or packfile
+pub fn read_havok(buf: &[u8]) -> Result<HavokFile> {
+    if buf.starts_with(&PACKFILE_MAGIC) {
+        read_packfile(buf)
+    } else if matches!(buf.get(4..8), Some(b"TAG0" | b"TCM0")) {
+        read_tagfile(buf)
+    } else {
+        Err(Error::Decode("havok file, unknown magic".to_string()))
+    }
+}
+
+/// Finds a tagfile or packfile inside a resource, which may have a header of its own in front
+pub fn find_havok(buf: &[u8]) -> Option<&[u8]> {
+    (0..buf.len().saturating_sub(8)).step_by(4).find_map(|i| {
+        let rest = &buf[i..];
+        if rest.starts_with(&PACKFILE_MAGIC) {
+            return Some(rest);
+        }
+        if !matches!(rest.get(4..8), Some(b"TAG0" | b"TCM0")) {
+            return None;
+        }
+        let size = (u32::from_be_bytes(rest[0..4].try_into().unwrap()) & 0x3fff_ffff) as usize;
+        rest.get(..size)
+    })
+}
+
+fn decode_err(what: impl std::fmt::Display) -> Error {
+    Error::Decode(format!("havok file, {}", what))
+}
+
+fn u32_at(buf: &[u8], at: usize) -> Result<u32> {
+    buf.get(at..at + 4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| decode_err(format!("read past the end at {:#x}", at)))
+}
+
+fn cstr(buf: &[u8]) -> String {
+    let end = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..end]).into_owned()
+}
+
+// Tagfiles
+
+struct Section<'a> {
+    tag: [u8; 4],
+    data: &'a [u8],
+}
+
+/// Splits a run of sections. Each starts with a big endian size (which includes the 8 header bytes) with
+/// flags in the top two bits, then a four character tag.
+fn sections(buf: &[u8]) -> Result<Vec<Section<'_>>> {
+    let mut found = Vec::new();
+    let mut pos = 0;
+    while pos + 8 <= buf.len() {
+        let size = (u32::from_be_bytes(buf[pos..pos + 4].try_into().unwrap()) & 0x3fff_ffff) as usize;
+        if size < 8 || pos + size > buf.len() {
+            return Err(decode_err(format!("section at {:#x} runs past the end", pos)));
+        }
+        found.push(Section {
+            tag: buf[pos + 4..pos + 8].try_into().unwrap(),
+            data: &buf[pos + 8..pos + size],
+        });
+        pos += size;
+    }
+    Ok(found)
+}
+
+fn section<'a, 'b>(sections: &'b [Section<'a>], tags: &[&[u8; 4]]) -> Option<&'b Section<'a>> {
+    sections.iter().find(|s| tags.contains(&&s.tag))
+}
+
+/// Reads the variable length big endian integers the type sections are made of. The top bits of the
+/// first byte say how many bytes follow.
+struct Packed<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl Packed<'_> {
+    fn done(&self) -> bool {
+        self.pos >= self.buf.len()
+    }
+
+    fn byte(&mut self) -> Result<u64> {
+        let b = self.buf.get(self.pos).ok_or_else(|| decode_err("type data ends early"))?;
+        self.pos += 1;
+        Ok(*b as u64)
+    }
+
+    fn read(&mut self) -> Result<u64> {
+        let first = self.byte()?;
+        let (extra, mask) = match first {
+            0x00..=0x7f => return Ok(first),
+            0x80..=0xbf => (1, 0x3fff),
+            0xc0..=0xdf => (2, 0x1f_ffff),
+            0xe0..=0xe7 => (3, 0x7ff_ffff),
+            // the long forms drop the first byte entirely
+            0xe8 => (4, 0xffff_ffff),
+            0xf0 => (8, u64::MAX),
+            _ => return Err(decode_err(format!("bad packed int {:#x}", first))),
+        };
+        let mut value = first;
+        for _ in 0..extra {
+            value = (value << 8) | self.byte()?;
+        }
+        Ok(value & mask)
+    }
+
+    fn index(&mut self) -> Result<usize> {
+        Ok(self.read()? as usize)
+    }
+}
+
+// Format flags in the type bodies, saying which fields follow
+const HAS_SUB_TYPE: u64 = 0x01;
+const HAS_POINTER: u64 = 0x02;
+const HAS_VERSION: u64 = 0x04;
+const HAS_SIZE: u64 = 0x08;
+const HAS_ABSTRACT_VALUE: u64 = 0x10;
+const HAS_MEMBERS: u64 = 0x20;
+const HAS_INTERFACES: u64 = 0x40;
+const HAS_ATTRIBUTE: u64 = 0x80;
+
+// Low nibble of the sub type
+const KIND_VOID: u64 = 0;
+const KIND_OPAQUE: u64 = 1;
+const KIND_BOOL: u64 = 2;
+const KIND_STRING: u64 = 3;
+const KIND_INT: u64 = 4;
+const KIND_FLOAT: u64 = 5;
+const KIND_POINTER: u64 = 6;
+const KIND_CLASS: u64 = 7;
+const KIND_ARRAY: u64 = 8;
+const KIND_TUPLE: u64 = 9;
+
+const INT_SIGNED: u64 = 0x200;
+
+// Item flags, in the top byte of the type index
+const ITEM_OBJECT: u32 = 0x10;
+
+fn kind_name(sub_type: u64) -> &'static str {
+    match sub_type & 0xf {
+        KIND_VOID => "void",
+        KIND_OPAQUE => "opaque",
+        KIND_BOOL => "bool",
+        KIND_STRING => "string",
+        KIND_INT => "int",
+        KIND_FLOAT => "float",
+        KIND_POINTER => "pointer",
+        KIND_CLASS => "class",
+        KIND_ARRAY => "array",
+        KIND_TUPLE => "tuple",
+        _ => "unknown",
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct TagType {
+    name: String,
+    /// Template parameters, names starting with 't' hold a type index and 'v' a value
+    templates: Vec<(String, u64)>,
+    parent: usize,
+    sub_type: Option<u64>,
+    pointer: usize,
+    version: Option<u64>,
+    size: Option<u32>,
+    members: Vec<TagMember>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct TagMember {
+    name: String,
+    offset: u32,
+    type_index: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Item {
+    flags: u32,
+    type_index: usize,
+    offset: usize,
+    count: usize,
+}
+
+struct Tagfile<'a> {
+    types: Vec<TagType>,
+    names: Vec<String>,
+    data: &'a [u8],
+    items: Vec<Item>,
+}
+
+fn string_list(buf: &[u8]) -> Vec<String> {
+    buf.split(|b| *b == 0).map(|s| String::from_utf8_lossy(s).into_owned()).collect()
+}
+
+fn lookup<'a>(strings: &'a [String], index: usize, what: &str) -> Result<&'a str> {
+    strings
+        .get(index)
+        .map(|s| s.as_str())
+        .ok_or_else(|| decode_err(format!("{} string {} out of range", what, index)))
+}
+
+fn read_types(type_sections: &[Section]) -> Result<Vec<TagType>> {
+    let strings = section(type_sections, &[b"TSTR", b"TST1"]).map(|s| string_list(s.data)).unwrap_or_default();
+    let fields = section(type_sections, &[b"FSTR", b"FST1"]).map(|s| string_list(s.data)).unwrap_or_default();
+    let Some(names) = section(type_sections, &[b"TNAM", b"TNA1"]) else {
+        return Err(decode_err("no type names"));
+    };
+
+    let mut p = Packed { buf: names.data, pos: 0 };
+    let count = p.index()?;
+    // every type takes at least two bytes, so anything bigger is garbage
+    if count > names.data.len() {
+        return Err(decode_err(format!("{} types in {} bytes", count, names.data.len())));
+    }
+    let mut types = vec![TagType::default(); count.max(1)];
+    for t in types.iter_mut().skip(1) {
+        t.name = lookup(&strings, p.index()?, "type")?.to_string();
+        for _ in 0..p.read()? {
+            let name = lookup(&strings, p.index()?, "template")?.to_string();
+            t.templates.push((name, p.read()?));
+        }
+    }
+
+    let Some(bodies) = section(type_sections, &[b"TBOD", b"TBDY"]) else {
+        return Err(decode_err("no type bodies"));
+    };
+    let mut p = Packed { buf: bodies.data, pos: 0 };
+    while !p.done() {
+        let index = p.index()?;
+        if index == 0 {
+            continue;
+        }
+        let count = types.len();
+        let t = types
+            .get_mut(index)
+            .ok_or_else(|| decode_err(format!("type body for type {} of {}", index, count)))?;
+        t.parent = p.index()?;
+        let flags = p.read()?;
+        if flags & HAS_SUB_TYPE != 0 {
+            t.sub_type = Some(p.read()?);
+        }
+        if flags & HAS_POINTER != 0 {
+            t.pointer = p.index()?;
+        }
+        if flags & HAS_VERSION != 0 {
+            t.version = Some(p.read()?);
+        }
+        if flags & HAS_SIZE != 0 {
+            t.size = Some(p.read()? as u32);
+            let _alignment = p.read()?;
+        }
+        if flags & HAS_ABSTRACT_VALUE != 0 {
+            p.read()?;
+        }
+        if flags & HAS_MEMBERS != 0 {
+            for _ in 0..p.read()? {
+                let name = lookup(&fields, p.index()?, "member")?.to_string();
+                let _flags = p.read()?;
+                let offset = p.read()? as u32;
+                let type_index = p.index()?;
+                t.members.push(TagMember {
+                    name,
+                    offset,
+                    type_index,
+                });
+            }
+        }
+        if flags & HAS_INTERFACES != 0 {
+            for _ in 0..p.read()? {
+                p.read()?;
+                p.read()?;
+            }
+        }
+        if flags & HAS_ATTRIBUTE != 0 {
+            p.read()?;
+        }
+    }
+    Ok(types)
+}
+
+/// Type names with their template parameters filled in, e.g. "hkArray<hkVector4, hkContainerHeapAllocator>"
+fn type_names(types: &[TagType]) -> Vec<String> {
+    fn name(types: &[TagType], index: usize, depth: usize) -> String {
+        let Some(t) = types.get(index).filter(|_| index != 0) else {
+            return "void".to_string();
+        };
+        if t.templates.is_empty() || depth > 4 {
+            return t.name.clone();
+        }
+        let params: Vec<String> = t
+            .templates
+            .iter()
+            .map(|(n, v)| match n.starts_with('t') {
+                true => name(types, *v as usize, depth + 1),
+                false => v.to_string(),
+            })
+            .collect();
+        format!("{}<{}>", t.name, params.join(", "))
+    }
+    (0..types.len()).map(|i| name(types, i, 0)).collect()
+}
+
+impl Tagfile<'_> {
+    /// A type followed by its parents. Typedefs and templates only set what differs from their parent.
+    fn chain(&self, index: usize) -> impl Iterator<Item = &TagType> {
+        let mut next = index;
+        std::iter::from_fn(move || {
+            let t = self.types.get(next).filter(|_| next != 0)?;
+            next = t.parent;
+            Some(t)
+        })
+        .take(MAX_DEPTH)
+    }
+
+    fn sub_type(&self, index: usize) -> u64 {
+        self.chain(index).find_map(|t| t.sub_type).unwrap_or(KIND_VOID)
+    }
+
+    fn size(&self, index: usize) -> Option<usize> {
+        self.chain(index).find_map(|t| t.size).map(|s| s as usize)
+    }
+
+    fn pointer(&self, index: usize) -> usize {
+        self.chain(index).map(|t| t.pointer).find(|p| *p != 0).unwrap_or(0)
+    }
+
+    fn members(&self, index: usize) -> Vec<&TagMember> {
+        let mut chain: Vec<&TagType> = self.chain(index).collect();
+        chain.reverse();
+        chain.iter().flat_map(|t| &t.members).collect()
+    }
+
+    fn bytes(&self, at: usize, len: usize) -> Result<&[u8]> {
+        self.data
+            .get(at..at + len)
+            .ok_or_else(|| decode_err(format!("object data at {:#x} + {} runs past the end", at, len)))
+    }
+
+    fn item(&self, at: usize) -> Result<Option<&Item>> {
+        let index = u32_at(self.data, at)? as usize;
+        if index == 0 {
+            return Ok(None);
+        }
+        match self.items.get(index) {
+            Some(item) => Ok(Some(item)),
+            None => Err(decode_err(format!("item {} of {} referenced at {:#x}", index, self.items.len(), at))),
+        }
+    }
+
+    fn elements(&self, type_index: usize, at: usize, count: usize, depth: usize) -> Result<Value> {
+        let stride = self.size(type_index).unwrap_or(0);
+        if stride == 0 && count > 0 {
+            return Err(decode_err(format!("elements of {} have no size", self.names[type_index])));
+        }
+        let values = (0..count)
+            .map(|i| self.value(type_index, at + i * stride, depth + 1))
+            .collect::<Result<_>>()?;
+        Ok(Value::Array(values))
+    }
+
+    fn value(&self, type_index: usize, at: usize, depth: usize) -> Result<Value> {
+        if depth > MAX_DEPTH {
+            return Err(decode_err("types nest too deep"));
+        }
+        let sub_type = self.sub_type(type_index);
+        let size = self.size(type_index);
+        Ok(match sub_type & 0xf {
+            KIND_BOOL => Value::Bool(self.bytes(at, size.unwrap_or(1))?.iter().any(|b| *b != 0)),
+            KIND_STRING => match self.item(at)? {
+                Some(item) => Value::String(cstr(self.bytes(item.offset, item.count)?)),
+                None => Value::Null,
+            },
+            KIND_INT => {
+                let size = size.unwrap_or(match sub_type & 0x1e000 {
+                    0x2000 => 1,
+                    0x4000 => 2,
+                    0x10000 => 8,
+                    _ => 4,
+                });
+                let mut raw = [0u8; 8];
+                raw[..size.min(8)].copy_from_slice(&self.bytes(at, size.min(8))?[..size.min(8)]);
+                let value = u64::from_le_bytes(raw);
+                if sub_type & INT_SIGNED != 0 {
+                    let shift = 64 - 8 * size.min(8) as u32;
+                    Value::Int(((value << shift) as i64) >> shift)
+                } else {
+                    Value::UInt(value)
+                }
+            }
+            KIND_FLOAT => match size.unwrap_or(4) {
+                2 => Value::Float(half::f16::from_le_bytes(self.bytes(at, 2)?.try_into().unwrap()).to_f64()),
+                8 => Value::Float(f64::from_le_bytes(self.bytes(at, 8)?.try_into().unwrap())),
+                _ => Value::Float(f32::from_le_bytes(self.bytes(at, 4)?.try_into().unwrap()) as f64),
+            },
+            KIND_POINTER => match u32_at(self.data, at)? {
+                0 => Value::Null,
+                index => Value::Ref(index as usize),
+            },
+            KIND_CLASS => {
+                let members = self
+                    .members(type_index)
+                    .into_iter()
+                    .map(|m| Ok((m.name.clone(), self.value(m.type_index, at + m.offset as usize, depth + 1)?)))
+                    .collect::<Result<_>>()?;
+                Value::Object(members)
+            }
+            KIND_ARRAY => match self.item(at)? {
+                Some(item) => {
+                    let element = match self.pointer(type_index) {
+                        0 => item.type_index,
+                        p => p,
+                    };
+                    self.elements(element, item.offset, item.count, depth)?
+                }
+                None => Value::Array(Vec::new()),
+            },
+            KIND_TUPLE => self.elements(self.pointer(type_index), at, (sub_type >> 8) as usize, depth)?,
+            KIND_VOID => Value::Null,
+            _ => Value::Bytes(self.bytes(at, size.unwrap_or(0))?.to_vec()),
+        })
+    }
+}
+
+fn read_tagfile(buf: &[u8]) -> Result<HavokFile> {
+    let top = sections(buf)?;
+    let Some(root) = section(&top, &[b"TAG0", b"TCM0"]) else {
+        return Err(decode_err("no root section"));
+    };
+    let format = match &root.tag {
+        b"TCM0" => Format::Compendium,
+        _ => Format::Tagfile,
+    };
+    let children = sections(root.data)?;
+    let version = section(&children, &[b"SDKV"]).map(|s| cstr(s.data)).unwrap_or_default();
+
+    let Some(type_section) = section(&children, &[b"TYPE"]) else {
+        if section(&children, &[b"TCRF"]).is_some() {
+            return Err(Error::UnsupportedType("havok tagfiles with types in a compendium".to_string()));
+        }
+        return Err(decode_err("no type section"));
+    };
+    let types = read_types(&sections(type_section.data)?)?;
+    let names = type_names(&types);
+
+    let mut items = Vec::new();
+    if let Some(index) = section(&children, &[b"INDX"]) {
+        if let Some(item_section) = section(&sections(index.data)?, &[b"ITEM"]) {
+            for chunk in item_section.data.chunks_exact(12) {
+                let flags_type = u32_at(chunk, 0)?;
+                items.push(Item {
+                    flags: flags_type >> 24,
+                    type_index: (flags_type & 0xff_ffff) as usize,
+                    offset: u32_at(chunk, 4)? as usize,
+                    count: u32_at(chunk, 8)? as usize,
+                });
+            }
+        }
+    }
+    if let Some(bad) = items.iter().find(|i| i.type_index >= types.len()) {
+        return Err(decode_err(format!("item of type {} of {}", bad.type_index, types.len())));
+    }
+
+    let tagfile = Tagfile {
+        data: section(&children, &[b"DATA"]).map(|s| s.data).unwrap_or_default(),
+        types,
+        names,
+        items,
+    };
+
+    let mut objects = Vec::new();
+    for (index, item) in tagfile.items.iter().enumerate().skip(1) {
+        if item.flags & ITEM_OBJECT == 0 {
+            continue;
+        }
+        let value = match item.count {
+            1 => tagfile.value(item.type_index, item.offset, 0)?,
+            count => tagfile.elements(item.type_index, item.offset, count, 0)?,
+        };
+        objects.push(HavokObject {
+            index,
+            type_name: tagfile.names[item.type_index].clone(),
+            value,
+        });
+    }
+
+    let types = tagfile
+        .types
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(index, t)| HavokType {
+            index,
+            name: tagfile.names[index].clone(),
+            kind: kind_name(tagfile.sub_type(index)),
+            parent: (t.parent != 0).then(|| tagfile.names.get(t.parent).cloned().unwrap_or_default()),
+            size: t.size,
+            version: t.version,
+            signature: None,
+            members: t
+                .members
+                .iter()
+                .map(|m| HavokMember {
+                    name: m.name.clone(),
+                    type_name: tagfile.names.get(m.type_index).cloned().unwrap_or_default(),
+                    offset: m.offset,
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(HavokFile {
+        format,
+        version,
+        types,
+        objects,
+    })
+}
+
+// Packfiles
+
+struct PackSection<'a> {
+    tag: String,
+    /// Everything up to the fixup tables
+    data: &'a [u8],
+    local_fixups: &'a [u8],
+    global_fixups: &'a [u8],
+    virtual_fixups: &'a [u8],
+}
+
+/// Packfiles store objects as they were laid out in memory, with fixup tables for pointers and each
+/// object's class. Without the class layouts objects come out as raw bytes plus their pointers.
+fn read_packfile(buf: &[u8]) -> Result<HavokFile> {
+    let header = buf.get(..0x40).ok_or_else(|| decode_err("packfile header runs past the end"))?;
+    let file_version = u32_at(header, 0xc)?;
+    let layout = &header[0x10..0x14];
+    if layout[0] != 8 || layout[1] != 1 {
+        return Err(Error::UnsupportedType(format!(
+            "havok packfiles with {} byte pointers{}",
+            layout[0],
+            if layout[1] == 1 { "" } else { ", big endian" }
+        )));
+    }
+    let section_count = u32_at(header, 0x14)? as usize;
+    let version = cstr(&header[0x28..0x38]);
+    let max_predicate = i16::from_le_bytes(header[0x3c..0x3e].try_into().unwrap());
+    let predicate_size = u16::from_le_bytes(header[0x3e..0x40].try_into().unwrap()) as usize;
+
+    // version 11 added a predicate array after the header and padding to the section headers
+    let (mut pos, section_size) = match file_version >= 11 {
+        true if max_predicate != -1 => (0x40 + predicate_size, 0x40),
+        true => (0x40, 0x40),
+        false => (0x40, 0x30),
+    };
+    let mut sections = Vec::new();
+    for _ in 0..section_count.min(16) {
+        let h = buf.get(pos..pos + 0x30).ok_or_else(|| decode_err("section headers run past the end"))?;
+        let start = u32_at(h, 0x14)? as usize;
+        let offsets: Vec<usize> = (0..6)
+            .map(|i| u32_at(h, 0x18 + i * 4).map(|o| start + o as usize))
+            .collect::<Result<_>>()?;
+        let slice = |from: usize, to: usize| {
+            buf.get(from..to)
+                .ok_or_else(|| decode_err(format!("section range {:#x}..{:#x} out of bounds", from, to)))
+        };
+        sections.push(PackSection {
+            tag: cstr(&h[..0x14]),
+            data: slice(start, offsets[0])?,
+            local_fixups: slice(offsets[0], offsets[1])?,
+            global_fixups: slice(offsets[1], offsets[2])?,
+            virtual_fixups: slice(offsets[2], offsets[3])?,
+        });
+        pos += section_size;
+    }
+
+    // class names: signature, a tab, then the name. Virtual fixups point at the name.
+    let mut classes: HashMap<(usize, usize), (String, u32)> = HashMap::new();
+    for (index, s) in sections.iter().enumerate().filter(|(_, s)| s.tag == "__classnames__") {
+        let mut at = 0;
+        while at + 5 < s.data.len() && s.data[at] != 0xff {
+            let signature = u32_at(s.data, at)?;
+            let name = cstr(&s.data[at + 5..]);
+            let next = at + 5 + name.len() + 1;
+            classes.insert((index, at + 5), (name, signature));
+            at = next;
+        }
+    }
+
+    let Some(data_index) = sections.iter().position(|s| s.tag == "__data__") else {
+        return Err(decode_err("packfile has no __data__ section"));
+    };
+    let data = &sections[data_index];
+
+    let triples = |buf: &[u8]| -> Vec<[usize; 3]> {
+        buf.chunks_exact(12)
+            .map(|c| [0, 4, 8].map(|i| u32::from_le_bytes(c[i..i + 4].try_into().unwrap()) as usize))
+            .filter(|t| t[0] != 0xffff_ffff)
+            .collect()
+    };
+    let mut starts: Vec<(usize, String)> = Vec::new();
+    for [src, section, name] in triples(data.virtual_fixups) {
+        let Some((class, _)) = classes.get(&(section, name)) else {
+            return Err(decode_err(format!("object at {:#x} has no class name", src)));
+        };
+        starts.push((src, class.clone()));
+    }
+    starts.sort_by_key(|s| s.0);
+    let object_at = |offset: usize| match starts.binary_search_by_key(&offset, |s| s.0) {
+        Ok(i) => Some(i),
+        Err(0) => None,
+        Err(i) => Some(i - 1),
+    };
+
+    // every pointer in the data section, as (source, target object, offset into the target)
+    let mut pointers: Vec<(usize, Option<usize>, usize)> = Vec::new();
+    for pair in data.local_fixups.chunks_exact(8) {
+        let src = u32_at(pair, 0)? as usize;
+        if src == 0xffff_ffff {
+            continue;
+        }
+        let dst = u32_at(pair, 4)? as usize;
+        let target = object_at(dst);
+        pointers.push((src, target, dst - target.map(|t| starts[t].0).unwrap_or(0)));
+    }
+    for [src, section, dst] in triples(data.global_fixups) {
+        let target = (section == data_index).then(|| object_at(dst)).flatten();
+        pointers.push((src, target, dst - target.map(|t| starts[t].0).unwrap_or(0)));
+    }
+    pointers.sort_by_key(|p| p.0);
+
+    let mut objects = Vec::new();
+    for (i, (start, class)) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).map(|s| s.0).unwrap_or(data.data.len());
+        let bytes = data
+            .data
+            .get(*start..end)
+            .ok_or_else(|| decode_err(format!("object at {:#x} runs past the data", start)))?;
+        let refs = pointers
+            .iter()
+            .filter(|p| p.0 >= *start && p.0 < end)
+            .map(|(src, target, offset)| {
+                Value::Object(vec![
+                    ("at".to_string(), Value::UInt((src - start) as u64)),
+                    ("to".to_string(), target.map(Value::Ref).unwrap_or(Value::Null)),
+                    ("offset".to_string(), Value::UInt(*offset as u64)),
+                ])
+            })
+            .collect();
+        objects.push(HavokObject {
+            index: i,
+            type_name: class.clone(),
+            value: Value::Object(vec![
+                ("pointers".to_string(), Value::Array(refs)),
+                ("data".to_string(), Value::Bytes(bytes.to_vec())),
+            ]),
+        });
+    }
+
+    let mut types: Vec<(String, u32)> = classes.into_values().collect();
+    types.sort();
+    let types = types
+        .into_iter()
+        .enumerate()
+        .map(|(index, (name, signature))| HavokType {
+            index,
+            name,
+            kind: "class",
+            parent: None,
+            size: None,
+            version: None,
+            signature: Some(signature),
+            members: Vec::new(),
+        })
+        .collect();
+
+    Ok(HavokFile {
+        format: Format::Packfile,
+        version,
+        types,
+        objects,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Smallest packed form of a value, the inverse of `Packed::read`
+    fn packed(v: u64) -> Vec<u8> {
+        match v {
+            0..=0x7f => vec![v as u8],
+            0x80..=0x3fff => vec![0x80 | (v >> 8) as u8, v as u8],
+            0x4000..=0x1f_ffff => vec![0xc0 | (v >> 16) as u8, (v >> 8) as u8, v as u8],
+            0x20_0000..=0x7ff_ffff => vec![0xe0 | (v >> 24) as u8, (v >> 16) as u8, (v >> 8) as u8, v as u8],
+            0x800_0000..=0xffff_ffff => [vec![0xe8], (v as u32).to_be_bytes().to_vec()].concat(),
+            _ => [vec![0xf0], v.to_be_bytes().to_vec()].concat(),
+        }
+    }
+
+    fn read_packed(bytes: &[u8]) -> Result<u64> {
+        Packed { buf: bytes, pos: 0 }.read()
+    }
+
+    fn section(tag: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        [((data.len() + 8) as u32 | 0x4000_0000).to_be_bytes().as_slice(), tag, data].concat()
+    }
+
+    fn strings(list: &[&str]) -> Vec<u8> {
+        list.iter().flat_map(|s| [s.as_bytes(), b"\0"].concat()).collect()
+    }
+
+    fn ints(values: &[u64]) -> Vec<u8> {
+        values.iter().flat_map(|v| packed(*v)).collect()
+    }
+
+    // Type and field string indices in the fixture below
+    const INT: u64 = 1;
+    const FLOAT: u64 = 2;
+    const STRING: u64 = 3;
+    const ARRAY: u64 = 4;
+    const NODE: u64 = 5;
+    const NODE_PTR: u64 = 6;
+    const DERIVED: u64 = 7;
+
+    /// The type section of the fixture: a Node class with one member of each kind, a pointer to it
+    /// and a Derived class adding a member
+    fn type_section() -> Vec<u8> {
+        let tstr = strings(&["int", "float", "char*", "hkArray", "Node", "Node*", "Derived", "tT"]);
+        let fstr = strings(&["id", "weight", "name", "values", "next", "extra"]);
+        let tnam = ints(&[
+            8,
+            0, 0,
+            1, 0,
+            2, 0,
+            3, 1, 7, FLOAT,
+            4, 0,
+            5, 0,
+            6, 0,
+        ]);
+        let tbod = ints(&[
+            INT, 0, HAS_SUB_TYPE | HAS_SIZE, KIND_INT | INT_SIGNED, 4, 4,
+            FLOAT, 0, HAS_SUB_TYPE | HAS_SIZE, KIND_FLOAT, 4, 4,
+            STRING, 0, HAS_SUB_TYPE | HAS_SIZE, KIND_STRING, 8, 8,
+            ARRAY, 0, HAS_SUB_TYPE | HAS_POINTER | HAS_SIZE, KIND_ARRAY, FLOAT, 16, 8,
+            NODE, 0, HAS_SUB_TYPE | HAS_SIZE | HAS_MEMBERS, KIND_CLASS, 40, 8,
+            5,
+            0, 0, 0, INT,
+            1, 0, 4, FLOAT,
+            2, 0, 8, STRING,
+            3, 0, 16, ARRAY,
+            4, 0, 32, NODE_PTR,
+            NODE_PTR, 0, HAS_SUB_TYPE | HAS_POINTER | HAS_SIZE, KIND_POINTER, NODE, 8, 8,
+            DERIVED, NODE, HAS_VERSION | HAS_SIZE | HAS_MEMBERS, 2, 48, 8,
+            1,
+            5, 0, 40, INT,
+        ]);
+        [section(b"TSTR", &tstr), section(b"TNAM", &tnam), section(b"FSTR", &fstr), section(b"TBOD", &tbod)].concat()
+    }
+
+    /// A tagfile assembled by hand following the layout `read_tagfile` implements. No game file could be
+    /// used here, so it checks the reader against itself and not against Havok's own output.
+    fn tagfile() -> Vec<u8> {
+        let mut data = vec![0u8; 120];
+        // Node: id, weight, name -> item 3, values -> item 4, next -> item 2
+        data[0..4].copy_from_slice(&(-5i32).to_le_bytes());
+        data[4..8].copy_from_slice(&1.5f32.to_le_bytes());
+        data[8..12].copy_from_slice(&3u32.to_le_bytes());
+        data[16..20].copy_from_slice(&4u32.to_le_bytes());
+        data[32..36].copy_from_slice(&2u32.to_le_bytes());
+        // Derived: id and extra, everything else null or empty
+        data[48..52].copy_from_slice(&7i32.to_le_bytes());
+        data[88..92].copy_from_slice(&42i32.to_le_bytes());
+        data[96..102].copy_from_slice(b"hello\0");
+        for (i, f) in [1.0f32, 2.0, 3.0].iter().enumerate() {
+            data[104 + i * 4..108 + i * 4].copy_from_slice(&f.to_le_bytes());
+        }
+
+        let items: Vec<u8> = [
+            [0, 0, 0],
+            [(ITEM_OBJECT << 24) | NODE as u32, 0, 1],
+            [(ITEM_OBJECT << 24) | DERIVED as u32, 48, 1],
+            [0x2000_0000, 96, 6],
+            [0x2000_0000 | FLOAT as u32, 104, 3],
+        ]
+        .iter()
+        .flatten()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+
+        let root = [
+            section(b"SDKV", b"20160200"),
+            section(b"DATA", &data),
+            section(b"TYPE", &type_section()),
+            section(b"INDX", &section(b"ITEM", &items)),
+        ]
+        .concat();
+        section(b"TAG0", &root)
+    }
+
+    #[test]
+    fn reads_packed_ints() {
+        assert_eq!(read_packed(&[0x05]).unwrap(), 5);
+        assert_eq!(read_packed(&[0x81, 0x00]).unwrap(), 0x100);
+        assert_eq!(read_packed(&[0xc1, 0x23, 0x45]).unwrap(), 0x12345);
+        assert_eq!(read_packed(&[0xe8, 0x12, 0x34, 0x56, 0x78]).unwrap(), 0x1234_5678);
+        for v in [0, 0x7f, 0x80, 0x3fff, 0x4000, 0x1f_ffff, 0x20_0000, 0x7ff_ffff, 0x800_0000, 0xffff_ffff, 1 << 32, u64::MAX] {
+            assert_eq!(read_packed(&packed(v)).unwrap(), v, "{:#x}", v);
+        }
+        let mut p = Packed { buf: &[0x01, 0x80, 0x80, 0x7f], pos: 0 };
+        assert_eq!([p.read().unwrap(), p.read().unwrap(), p.read().unwrap()], [1, 0x80, 0x7f]);
+        assert!(p.done());
+    }
+
+    #[test]
+    fn rejects_bad_packed_ints() {
+        assert!(read_packed(&[]).is_err());
+        assert!(read_packed(&[0x80]).is_err());
+        assert!(read_packed(&[0xe8, 0, 0, 0]).is_err());
+        assert!(read_packed(&[0xe9, 0, 0, 0, 0]).is_err());
+        assert!(read_packed(&[0xff]).is_err());
+    }
+
+    #[test]
+    fn reads_the_type_table() {
+        let types = read_types(&sections(&type_section()).unwrap()).unwrap();
+        assert_eq!(types.len(), 8);
+        assert_eq!(types[ARRAY as usize].templates, [("tT".to_string(), FLOAT)]);
+        assert_eq!(types[ARRAY as usize].pointer, FLOAT as usize);
+        let node = &types[NODE as usize];
+        assert_eq!(node.size, Some(40));
+        let members: Vec<(&str, u32, usize)> =
+            node.members.iter().map(|m| (m.name.as_str(), m.offset, m.type_index)).collect();
+        assert_eq!(
+            members,
+            [("id", 0, 1), ("weight", 4, 2), ("name", 8, 3), ("values", 16, 4), ("next", 32, 6)]
+        );
+        let derived = &types[DERIVED as usize];
+        assert_eq!((derived.parent, derived.version, derived.sub_type), (NODE as usize, Some(2), None));
+
+        let names = type_names(&types);
+        assert_eq!(names[ARRAY as usize], "hkArray<float>");
+        assert_eq!(names[0], "void");
+    }
+
+    #[test]
+    fn rejects_type_references_out_of_range() {
+        let bad_name = [section(b"TSTR", &strings(&["a"])), section(b"TNAM", &ints(&[2, 0, 0, 5, 0]))].concat();
+        assert!(read_types(&sections(&bad_name).unwrap()).is_err());
+        let bad_body = [
+            section(b"TSTR", &strings(&["a"])),
+            section(b"TNAM", &ints(&[2, 0, 0])),
+            section(b"TBOD", &ints(&[9, 0, 0])),
+        ]
+        .concat();
+        assert!(read_types(&sections(&bad_body).unwrap()).is_err());
+        assert!(read_types(&[]).is_err());
+    }
+
+    #[test]
+    fn reads_a_tagfile() {
+        let file = read_havok(&tagfile()).unwrap();
+        assert_eq!(file.format, Format::Tagfile);
+        assert_eq!(file.version, "20160200");
+        assert_eq!(file.types.len(), 7);
+        let derived = file.types.iter().find(|t| t.name == "Derived").unwrap();
+        assert_eq!((derived.kind, derived.parent.as_deref()), ("class", Some("Node")));
+
+        assert_eq!(file.objects.len(), 2);
+        let node = file.object(1).unwrap();
+        assert_eq!(node.type_name, "Node");
+        assert_eq!(
+            node.value,
+            Value::Object(vec![
+                ("id".to_string(), Value::Int(-5)),
+                ("weight".to_string(), Value::Float(1.5)),
+                ("name".to_string(), Value::String("hello".to_string())),
+                ("values".to_string(), Value::Array(vec![Value::Float(1.0), Value::Float(2.0), Value::Float(3.0)])),
+                ("next".to_string(), Value::Ref(2)),
+            ])
+        );
+        let derived = file.object(2).unwrap();
+        assert_eq!(derived.value.get("id"), Some(&Value::Int(7)));
+        assert_eq!(derived.value.get("extra"), Some(&Value::Int(42)));
+        assert_eq!(derived.value.get("name"), Some(&Value::Null));
+        assert_eq!(derived.value.get("values"), Some(&Value::Array(Vec::new())));
+        assert_eq!(derived.value.get("next"), Some(&Value::Null));
+        assert_eq!(file.objects_of("Derived").count(), 1);
+    }
+
+    #[test]
+    fn finds_a_tagfile_behind_a_header() {
+        let tagfile = tagfile();
+        let resource = [vec![0xaa; 16], tagfile.clone(), vec![0xbb; 8]].concat();
+        assert_eq!(find_havok(&resource), Some(tagfile.as_slice()));
+        assert_eq!(find_havok(&[0xaa; 64]), None);
+    }
+
+    #[test]
+    fn rejects_truncated_tagfiles() {
+        let tagfile = tagfile();
+        for len in [0, 8, 40, tagfile.len() / 2, tagfile.len() - 1] {
+            assert!(read_havok(&tagfile[..len]).is_err(), "{} bytes", len);
+        }
+    }
+}
@@ -1,9 +1,23 @@
 #![deny(clippy::correctness, clippy::suspicious, clippy::complexity)]
 // Uses research and code done by MontagueM at https://github.com/MontagueM/helldivers2,
 // as well as from h3x3r and Xaymar at https://reshax.com/topic/507-helldivers-2-model-extraction-help
+pub mod census;
+pub mod commands;
+pub mod config;
+pub mod dxbc;
+pub mod error;
 pub mod extract;
+pub mod gltf;
+pub mod hash;
+pub mod havok;
 pub mod pndb;
+pub mod report;
+pub mod selection;
+pub mod serve;
+pub mod sniff;
+pub mod steam;
 pub mod structs;
+pub mod verify;
 pub mod types;
 
 use binrw::{BinReaderExt, BinWriterExt};
@@ -16,6 +30,7 @@ use std::{
 };
 
 use extract::*;
+use selection::Selection;
 use structs::*;
 
 #[macro_use]
@@ -24,156 +39,738 @@ use clap::Parser;
 
 #[derive(clap::Parser, Debug)]
 #[command(author, version, about, long_about = None, disable_version_flag(true))]
-struct Args {
-    /// Path to data directory
-    data_path: String,
+struct Cli {
+    /// Path to data directory, found through Steam if not given
+    #[arg(long, global = true, env = "HD2_DATA")]
+    data: Option<String>,
 
-    /// Path to output files to
-    output_path: String,
+    /// Path to the ID cache, built from the data directory if it doesn't exist [default: ids.cache]
+    #[arg(long, global = true, env = "HD2_CACHE")]
+    cache: Option<String>,
 
-    /// Selected bundle file
-    bundle_file: Option<String>,
+    /// Applies names to files from a name database (assets.pndb if no path is given with --pndb=PATH)
+    #[arg(short, long, global = true, env = "HD2_PNDB", num_args = 0..=1, require_equals = true, default_missing_value = "assets.pndb")]
+    pndb: Option<String>,
+
+    /// Also decodes the types whose layouts are guessed rather than checked against game data, which
+    /// are dumped raw otherwise
+    #[arg(long, global = true, env = "HD2_EXPERIMENTAL")]
+    experimental: bool,
+
+    /// Config file to read profiles from [default: $XDG_CONFIG_HOME/helldivers2-rs/config.toml]
+    #[arg(long, global = true, env = "HD2_CONFIG")]
+    config: Option<std::path::PathBuf>,
+
+    /// Profile from the config file to take defaults from
+    #[arg(long, global = true, env = "HD2_PROFILE")]
+    profile: Option<String>,
+
+    /// Settings of the selected profile, filled in after parsing
+    #[arg(skip)]
+    settings: config::Profile,
+
+    /// Install found through Steam when no data directory is given
+    #[arg(skip)]
+    install: Option<steam::SteamInstall>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Builds or inspects the ID cache
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+
+    /// Lists bundles, or the assets matching a selection
+    Ls {
+        #[command(flatten)]
+        selection: SelectionArgs,
+    },
+
+    /// Shows what's inside a bundle, or everything known about an asset id
+    Info {
+        /// Bundle or asset id
+        id: Id,
+    },
+
+    /// Extracts assets, converting the types we have handlers for
+    Extract(ExtractArgs),
+
+    /// Writes a single asset to stdout
+    Cat {
+        id: Id,
+
+        /// Which part of the asset to write
+        #[arg(long, value_enum, default_value_t = Part::Bundle)]
+        part: Part,
+
+        /// Bundle to take the asset from, if it's in more than one
+        #[arg(short, long)]
+        bundle: Option<Id>,
+
+        /// Writes the converted asset instead of raw data
+        #[arg(short, long)]
+        convert: bool,
+    },
+
+    /// Lists the functions, constants and instructions of a lua resource or LuaJIT bytecode file
+    Disasm {
+        /// Lua resource to disassemble
+        #[arg(required_unless_present = "file")]
+        id: Option<Id>,
+
+        /// Bundle to take the resource from, if it's in more than one
+        #[arg(short, long)]
+        bundle: Option<Id>,
+
+        /// Disassembles a file instead, either bytecode or a dumped lua resource
+        #[arg(long, conflicts_with = "id")]
+        file: Option<String>,
+    },
+
+    /// Exports a level as a glTF scene placing its units (needs --experimental)
+    Scene {
+        /// Level resource to export
+        id: Id,
+
+        /// Bundle to take the level from, if it's in more than one
+        #[arg(short, long)]
+        bundle: Option<Id>,
+
+        /// Where to write the .glb, <id>.glb by default
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Exports a unit with its skeleton and animation clips as glTF
+    Anim {
+        /// Unit to export
+        unit: Id,
+
+        /// Animation resources to add as clips (repeatable, needs --experimental)
+        #[arg(short, long = "clip")]
+        clips: Vec<Id>,
+
+        /// Where to write the .glb, <unit>.glb by default
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Exports a unit's collision shapes aligned to its mesh, for checking collision
+    Collision {
+        /// Unit to export, whose physics resource has the same name
+        unit: Id,
+
+        /// Where to write the .glb or .obj, <unit>_collision.glb by default
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Exports the navigation meshes of a navdata resource with a group per area
+    Navmesh {
+        /// Navdata resource to export
+        id: Id,
+
+        /// Bundle to take the navdata from, if it's in more than one
+        #[arg(short, long)]
+        bundle: Option<Id>,
+
+        /// Where to write the .glb or .obj, <id>_navmesh.glb by default
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Searches the name database with a glob, e.g. "*/weapons/*"
+    Search {
+        pattern: String,
+
+        /// Only lists assets of this type, by name or hex type id
+        #[arg(short = 'f', long = "type", value_parser = selection::parse_type_id)]
+        type_id: Option<Id>,
+    },
+
+    /// Looks up names for ids, or prints the whole name database if none are given
+    Names { ids: Vec<Id> },
+
+    /// Lists or checks the packages bundles were built from
+    Packages {
+        #[command(subcommand)]
+        command: PackagesCommand,
+    },
+
+    /// Reports on the asset types in the cache
+    Types {
+        #[command(subcommand)]
+        command: TypesCommand,
+    },
+
+    /// Checks bundles for broken ranges, overlaps, count mismatches and missing files
+    Verify {
+        /// Bundles to check, all of them if none are given (repeatable)
+        #[arg(short, long = "bundle")]
+        bundles: Vec<Id>,
+
+        /// Smallest unreferenced range to report, anything smaller is taken as padding
+        #[arg(long, default_value_t = verify::DEFAULT_MIN_GAP)]
+        min_gap: u64,
+
+        /// Lists every unreferenced range instead of a total per bundle
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Serves assets over a local HTTP JSON API
+    Serve {
+        /// Port to serve on
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+    },
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum CacheCommand {
+    /// Rebuilds the ID cache
+    Build,
+
+    /// Prints a summary of the ID cache
+    Info,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum PackagesCommand {
+    /// Lists each bundle's packages and their names
+    Ls,
+
+    /// Checks that each bundle holds exactly what its packages declare
+    Check {
+        /// Bundles to check, all of them if none are given (repeatable)
+        #[arg(short, long = "bundle")]
+        bundles: Vec<Id>,
+
+        /// Lists every missing and undeclared resource instead of counts
+        #[arg(short, long)]
+        verbose: bool,
+    },
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum TypesCommand {
+    /// Lists every type id with counts, sizes, where its data lives and what format it appears to be
+    Census {
+        /// How many assets of each type to read and sniff, needs the data directory
+        #[arg(long, default_value_t = 16)]
+        samples: usize,
+
+        /// Prints the census as json
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum Part {
+    Bundle,
+    Stream,
+    Gpu,
+}
+
+#[derive(clap::Args, Debug)]
+struct SelectionArgs {
+    /// Types to select, by name or hex type id (repeatable)
+    #[arg(short = 'f', long = "type", value_parser = selection::parse_type_id)]
+    types: Vec<Id>,
+
+    /// Ids to select (repeatable)
+    #[arg(short = 's', long = "id")]
+    ids: Vec<Id>,
+
+    /// Reads ids to select from a file, one hex id per line
+    #[arg(long)]
+    ids_from: Option<String>,
+
+    /// Bundles to select from (repeatable)
+    #[arg(short, long = "bundle")]
+    bundles: Vec<Id>,
+
+    /// Name globs matched against the name database, e.g. "content/fac_helldivers/*" (repeatable)
+    #[arg(long = "name")]
+    names: Vec<String>,
+
+    /// Only select assets of at least this many bytes (bundle + stream + gpu)
+    #[arg(long)]
+    min_size: Option<u64>,
+
+    /// Only select assets of at most this many bytes (bundle + stream + gpu)
+    #[arg(long)]
+    max_size: Option<u64>,
+}
+
+impl SelectionArgs {
+    fn to_selection(&self) -> anyhow::Result<Selection> {
+        let mut selection = Selection {
+            ids: self.ids.iter().copied().collect(),
+            type_ids: self.types.clone(),
+            bundles: self.bundles.clone(),
+            names: self.names.clone(),
+            min_size: self.min_size,
+            max_size: self.max_size,
+        };
+        if let Some(ref ids_from) = self.ids_from {
+            selection.ids.extend(selection::read_id_list(Path::new(ids_from))?);
+        }
+        Ok(selection)
+    }
+}
+
+#[derive(clap::Args, Debug)]
+struct ExtractArgs {
+    /// Path to output files to, taken from the profile if not given
+    output_path: Option<String>,
+
+    #[command(flatten)]
+    selection: SelectionArgs,
 
     /// Extract from all bundles
-    #[arg(short, long)]
-    extract_all: bool,
+    #[arg(short, long, visible_alias = "extract-all")]
+    all: bool,
 
     /// Extracts everyting into one folder (still separated by type)
     #[arg(short, long)]
     one_folder: bool,
 
-    /// Rebuilds the ID cache
-    #[arg(short, long)]
-    build_cache: bool,
+    /// Names bundle folders after their package instead of the bundle id, where the name is known
+    #[arg(long)]
+    package_folders: bool,
 
-    /// Types to extract
-    #[arg(short, value_enum)]
-    filetype: Option<DataTypes>,
+    /// Also extracts the resources the selected assets refer to, e.g. a particle effect's materials and
+    /// textures
+    #[arg(long)]
+    with_deps: bool,
 
-    /// Extract all files with x ID
-    #[arg(short, value_enum)]
-    selected_id: Option<String>,
+    /// Prints what would be extracted without extracting anything
+    #[arg(long)]
+    dry_run: bool,
 
-    /// Uses an assets.pndb file in the same location as the exe to apply names to files
+    /// Keeps extracting past any failure and writes a report of every asset's outcome
     #[arg(short, long)]
-    pndb: bool,
+    keep_going: bool,
+
+    /// Where to write the run report, as csv if it ends in .csv and json otherwise
+    #[arg(long)]
+    report: Option<String>,
+
+    /// Re-runs only the assets that failed in a previous run report
+    #[arg(long)]
+    retry_failed: Option<String>,
 }
 
-pub fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
-    if args.build_cache || !Path::new("ids.cache").exists() {
-        println!("Building cache...");
+impl Cli {
+    fn data_path(&self) -> anyhow::Result<&str> {
+        if let Some(data) = self.data.as_ref().or(self.settings.data.as_ref()) {
+            return Ok(data);
+        }
+        match self.install.as_ref().and_then(|i| i.data_path.to_str()) {
+            Some(data) => Ok(data),
+            None => Err(anyhow::anyhow!(
+                "No data directory given and no Steam install found, pass one with --data or set it in a profile."
+            )),
+        }
+    }
+
+    fn cache_path(&self) -> &str {
+        self.cache
+            .as_deref()
+            .or(self.settings.cache.as_deref())
+            .unwrap_or("ids.cache")
+    }
+
+    fn load_cache(&self) -> anyhow::Result<IdCache> {
+        if !Path::new(self.cache_path()).exists() {
+            return build_cache(self);
+        }
+        eprintln!("Loading cache...");
         let start = Instant::now();
-        let cache = build_id_cache(&args.data_path)?;
-        let mut cache_file = File::create("id_cache.json")?;
-        let json = serde_json::to_string(&cache)?;
-        cache_file.write_all(json.as_bytes())?;
+        let mut reader = BufReader::new(File::open(self.cache_path())?);
 
-        let mut cache_writer = BufWriter::new(File::create("ids.cache")?);
-        cache_writer.write_le(&cache)?;
+        let cache: IdCache = reader.read_le()?;
 
         let end = Instant::now() - start;
-        println!(
-            "Done. {:?} bundles with {:?} files saved to ids.cache in {:?}ms.",
+        eprintln!(
+            "{:?} bundles with {:?} files loaded in {:?}ms.",
             cache.bundles.len(),
             cache.bundles.values().map(|x| x.len()).sum::<usize>(),
             end.as_millis()
         );
-        if args.build_cache {
-            return Ok(());
+        if let (Some(cached), Some(installed)) = (cache.build_id, self.installed_build()) {
+            if cached != installed {
+                eprintln!(
+                    "Warning: cache was built from game build {} but build {} is installed, rebuild it with `cache build`.",
+                    cached, installed
+                );
+            }
         }
+        Ok(cache)
     }
 
-    println!("Loading cache...");
-    let start = Instant::now();
-    let mut reader = BufReader::new(File::open("ids.cache")?);
+    /// Steam build id of the data directory in use, if it belongs to a Steam install
+    fn installed_build(&self) -> Option<u64> {
+        let data_path = self.data_path().ok()?;
+        match self.install {
+            Some(ref install) if install.data_path.to_str() == Some(data_path) => install.build_id,
+            _ => steam::installed_build(Path::new(data_path)),
+        }
+    }
 
-    let cache: IdCache = reader.read_le()?;
+    fn load_namedb(&self) -> anyhow::Result<pndb::Pndb> {
+        let paths = match self.pndb {
+            Some(ref path) => std::slice::from_ref(path),
+            None => self.settings.pndb.as_slice(),
+        };
+        let mut namedb = pndb::Pndb::default();
+        for path in paths {
+            namedb.name_database.extend(pndb::read_pndb(path)?.name_database);
+        }
+        Ok(namedb)
+    }
+}
 
-    let end = Instant::now() - start;
-    println!(
-        "{:?} bundles with {:?} files loaded in {:?}ms.",
-        cache.bundles.len(),
-        cache.bundles.values().map(|x| x.len()).sum::<usize>(),
-        end.as_millis()
-    );
+pub fn main() -> anyhow::Result<()> {
+    let mut cli = Cli::parse();
+    cli.settings = config::Config::load(cli.config.as_deref())?.profile(cli.profile.as_deref())?;
+    if cli.data.is_none() && cli.settings.data.is_none() {
+        cli.install = steam::find_install();
+        if let Some(ref install) = cli.install {
+            eprintln!(
+                "Using Steam install at {} (build {}).",
+                install.data_path.display(),
+                install.build_id.map(|b| b.to_string()).unwrap_or("unknown".to_string())
+            );
+        }
+    }
 
-    let mut namedb = pndb::Pndb::default();
-    if args.pndb {
-        namedb = pndb::read_pndb("assets.pndb")?;
+    match cli.command {
+        Command::Cache { ref command } => match command {
+            CacheCommand::Build => {
+                build_cache(&cli)?;
+            }
+            CacheCommand::Info => {
+                let cache = cli.load_cache()?;
+                commands::cache_info(&cache, Path::new(cli.cache_path()))?;
+            }
+        },
+        Command::Ls { ref selection } => {
+            let cache = cli.load_cache()?;
+            let namedb = cli.load_namedb()?;
+            let selection = selection.to_selection()?;
+            if selection.is_empty() {
+                commands::list_bundles(&cache, &namedb);
+            } else {
+                selection::print_selection(&selection.resolve(&cache, &namedb), &namedb);
+            }
+        }
+        Command::Info { id } => {
+            let cache = cli.load_cache()?;
+            let namedb = cli.load_namedb()?;
+            commands::info(&cache, &namedb, cli.data_path().ok(), id, cli.experimental)?;
+        }
+        Command::Extract(ref args) => {
+            let cache = cli.load_cache()?;
+            let namedb = cli.load_namedb()?;
+            run_extract(&cli, args, &cache, &namedb)?;
+        }
+        Command::Cat {
+            id,
+            part,
+            bundle,
+            convert,
+        } => {
+            let cache = cli.load_cache()?;
+            let namedb = cli.load_namedb()?;
+            let output = match convert {
+                true => commands::CatOutput::Converted { experimental: cli.experimental },
+                false => commands::CatOutput::Raw(part),
+            };
+            let buf = commands::cat(&cache, &namedb, cli.data_path()?, id, bundle, output)?;
+            std::io::stdout().lock().write_all(&buf)?;
+        }
+        Command::Disasm {
+            id,
+            bundle,
+            ref file,
+        } => {
+            let buf = match (id, file) {
+                (_, Some(file)) => std::fs::read(file)?,
+                (Some(id), None) => {
+                    let cache = cli.load_cache()?;
+                    let output = commands::CatOutput::Raw(Part::Bundle);
+                    commands::cat(&cache, &Default::default(), cli.data_path()?, id, bundle, output)?
+                }
+                (None, None) => unreachable!("clap requires an id or a file"),
+            };
+            let dump = types::lua::read_dump(types::lua::strip_header(&buf)?)?;
+            print!("{}", types::lua::disassemble(&dump));
+        }
+        Command::Scene {
+            id,
+            bundle,
+            ref output,
+        } => {
+            let cache = cli.load_cache()?;
+            let namedb = cli.load_namedb()?;
+            let output = output.clone().unwrap_or(format!("{}.glb", id));
+            let count =
+                commands::scene(&cache, &namedb, cli.data_path()?, id, bundle, Path::new(&output), cli.experimental)?;
+            eprintln!("Wrote {} unit instances to {}.", count, output);
+        }
+        Command::Anim {
+            unit,
+            ref clips,
+            ref output,
+        } => {
+            let cache = cli.load_cache()?;
+            let namedb = cli.load_namedb()?;
+            let output = output.clone().unwrap_or(format!("{}.glb", unit));
+            let count =
+                commands::animate(&cache, &namedb, cli.data_path()?, unit, clips, Path::new(&output), cli.experimental)?;
+            eprintln!("Wrote unit {} with {} clips to {}.", unit, count, output);
+        }
+        Command::Collision { unit, ref output } => {
+            let cache = cli.load_cache()?;
+            let output = output.clone().unwrap_or(format!("{}_collision.glb", unit));
+            let count = commands::collision(&cache, cli.data_path()?, unit, Path::new(&output))?;
+            eprintln!("Wrote {} collision shapes of unit {} to {}.", count, unit, output);
+        }
+        Command::Navmesh {
+            id,
+            bundle,
+            ref output,
+        } => {
+            let cache = cli.load_cache()?;
+            let output = output.clone().unwrap_or(format!("{}_navmesh.glb", id));
+            let count = commands::navmesh(&cache, cli.data_path()?, id, bundle, Path::new(&output))?;
+            eprintln!("Wrote {} navigation meshes to {}.", count, output);
+        }
+        Command::Search {
+            ref pattern,
+            type_id,
+        } => {
+            let cache = cli.load_cache()?;
+            let namedb = cli.load_namedb()?;
+            commands::search(&cache, &namedb, pattern, type_id);
+        }
+        Command::Names { ref ids } => {
+            let namedb = cli.load_namedb()?;
+            commands::names(&namedb, ids);
+        }
+        Command::Packages { ref command } => {
+            let cache = cli.load_cache()?;
+            let namedb = cli.load_namedb()?;
+            match command {
+                PackagesCommand::Ls => commands::list_packages(&cache, &namedb),
+                PackagesCommand::Check { bundles, verbose } => {
+                    let problems =
+                        commands::check_packages(&cache, &namedb, cli.data_path()?, bundles, *verbose)?;
+                    if problems > 0 {
+                        return Err(anyhow::anyhow!("{} bundles don't match their packages.", problems));
+                    }
+                }
+            }
+        }
+        Command::Types {
+            command: TypesCommand::Census { samples, json },
+        } => {
+            let cache = cli.load_cache()?;
+            let census = census::census(&cache, cli.data_path().ok(), samples)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&census)?);
+            } else {
+                census::print_census(&census);
+            }
+        }
+        Command::Verify {
+            ref bundles,
+            min_gap,
+            verbose,
+        } => {
+            let problems = run_verify(cli.data_path()?, bundles, min_gap, verbose)?;
+            if problems > 0 {
+                return Err(anyhow::anyhow!("{} problems found.", problems));
+            }
+        }
+        Command::Serve { port } => {
+            let cache = cli.load_cache()?;
+            let namedb = cli.load_namedb()?;
+            serve::serve(&cache, cli.data_path()?, &namedb, port)?;
+        }
     }
 
+    Ok(())
+}
+
+fn run_extract(
+    cli: &Cli,
+    args: &ExtractArgs,
+    cache: &IdCache,
+    namedb: &pndb::Pndb,
+) -> anyhow::Result<()> {
+    let data_path = cli.data_path()?;
+    let Some(output_path) = args.output_path.as_ref().or(cli.settings.output.as_ref()) else {
+        return Err(anyhow::anyhow!(
+            "No output path given, pass one or set it in a profile."
+        ));
+    };
+    let report_path = args
+        .report
+        .as_deref()
+        .or(cli.settings.report.as_deref())
+        .unwrap_or("report.json");
+    let opts = ExtractOptions {
+        one_folder: args.one_folder || cli.settings.one_folder,
+        package_folders: args.package_folders,
+        keep_going: args.keep_going || cli.settings.keep_going,
+        dependencies: args.with_deps,
+        experimental: cli.experimental,
+    };
+    let mut run_report = report::Report::default();
 
-    std::fs::create_dir_all(&args.output_path)?;
-
-    if args.selected_id.is_some() {
-        // for (bundle, headers) in cache.bundles.iter() {
-        //     for header in headers {
-        //         if header.id == id {
-        //             println!("id {:?} is in bundle {:?}", header.id, bundle);
-        //             extract_single(&args.output_path, &args.data_path, bundle, header)?;
-        //         }
-        //     }
-        // }
-        // println!("id {:?} is in bundle {:?}", id, data.0);
-        return extract_single(
-            &cache,
-            &args.output_path,
-            &args.data_path,
-            Id::from(args.selected_id.unwrap()),
-            &namedb
+    if let Some(ref previous) = args.retry_failed {
+        let previous = report::Report::read(Path::new(previous))?;
+        for (bundle, ids) in previous.failures() {
+            let selection = Selection {
+                bundles: vec![bundle],
+                ids: ids.unwrap_or_default(),
+                ..Default::default()
+            };
+            extract(
+                cache,
+                output_path,
+                data_path,
+                &selection,
+                &opts,
+                namedb,
+                &mut run_report
+            )?;
+        }
+    } else {
+        let selection = args.selection.to_selection()?;
+        if selection.is_empty() && !args.all {
+            println!("You must either select something to extract or extract all.");
+            return Ok(());
+        }
+        if args.dry_run {
+            let mut assets = selection.resolve(cache, namedb);
+            if opts.dependencies {
+                assets = add_dependencies(cache, data_path, assets);
+            }
+            selection::print_selection(&assets, namedb);
+            return Ok(());
+        }
+        extract(
+            cache,
+            output_path,
+            data_path,
+            &selection,
+            &opts,
+            namedb,
+            &mut run_report
+        )?;
+    }
+
+    if opts.keep_going || args.retry_failed.is_some() {
+        run_report.write(Path::new(report_path))?;
+        println!(
+            "{} extracted, {} partly extracted, {} skipped, {} failed. Report written to {}.",
+            run_report.count(report::Outcome::Ok),
+            run_report.count(report::Outcome::Partial),
+            run_report.count(report::Outcome::Skipped),
+            run_report.count(report::Outcome::Failed),
+            report_path
         );
     }
 
-    if args.extract_all {
-        for a in std::fs::read_dir(&args.data_path)? {
-            let bundle_name = a?.file_name();
-            let bundle_name = bundle_name.to_str().unwrap();
-            if bundle_name.contains('.') || bundle_name == "game" {
+    Ok(())
+}
+
+/// Verifies the given bundles, or every bundle in the data directory, returning the number of problems
+fn run_verify(data_path: &str, bundles: &[Id], min_gap: u64, verbose: bool) -> anyhow::Result<usize> {
+    let bundles = if bundles.is_empty() {
+        bundle_ids(data_path)?
+    } else {
+        bundles.to_vec()
+    };
+
+    let mut problems = 0;
+    let mut gap_bytes = 0;
+    for bundle_id in &bundles {
+        let check = match verify::verify_bundle(data_path, *bundle_id, min_gap) {
+            Ok(check) => check,
+            Err(e) => {
+                println!("{}: failed to read: {}", bundle_id, e);
+                problems += 1;
                 continue;
             }
-            extract_files(
-                &cache,
-                &args.output_path,
-                &args.data_path,
-                &bundle_name.to_string(),
-                args.filetype,
-                args.one_folder,
-                &namedb
-            )?;
+        };
+        for problem in &check.problems {
+            println!("{}: {}", bundle_id, problem);
+        }
+        problems += check.problems.len();
+
+        let bytes: u64 = check.gaps.iter().map(|g| g.2).sum();
+        gap_bytes += bytes;
+        if verbose {
+            for (file, offset, size) in &check.gaps {
+                println!("{}: {} unreferenced {:#x}..{:#x} ({} bytes)", bundle_id, file, offset, offset + size, size);
+            }
+        } else if !check.gaps.is_empty() {
+            println!("{}: {} unreferenced ranges, {} bytes", bundle_id, check.gaps.len(), bytes);
         }
-        return Ok(());
-    } else if args.bundle_file.is_none() {
-        println!("You must either select a single bundle or extract all.");
-        return Ok(());
     }
-    extract_files(
-        &cache,
-        &args.output_path,
-        &args.data_path,
-        &args.bundle_file.unwrap(),
-        args.filetype,
-        args.one_folder,
-        &namedb
-    )?;
+    println!(
+        "{} bundles checked, {} problems, {} unreferenced bytes.",
+        bundles.len(),
+        problems,
+        gap_bytes
+    );
+    Ok(problems)
+}
 
-    Ok(())
+fn build_cache(cli: &Cli) -> anyhow::Result<IdCache> {
+    eprintln!("Building cache...");
+    let start = Instant::now();
+    let mut cache = build_id_cache(cli.data_path()?)?;
+    cache.build_id = cli.installed_build();
+    let cache_path = Path::new(cli.cache_path());
+    let mut cache_file = File::create(cache_path.with_file_name("id_cache.json"))?;
+    let json = serde_json::to_string(&cache)?;
+    cache_file.write_all(json.as_bytes())?;
+
+    let mut cache_writer = BufWriter::new(File::create(cache_path)?);
+    cache_writer.write_le(&cache)?;
+
+    let end = Instant::now() - start;
+    eprintln!(
+        "Done. {:?} bundles with {:?} files saved to {} in {:?}ms.",
+        cache.bundles.len(),
+        cache.bundles.values().map(|x| x.len()).sum::<usize>(),
+        cli.cache_path(),
+        end.as_millis()
+    );
+    Ok(cache)
 }
 
-fn build_id_cache(data_path: &String) -> anyhow::Result<IdCache> {
+fn build_id_cache(data_path: &str) -> anyhow::Result<IdCache> {
     let mut cache: IdCache = Default::default();
 
-    for a in std::fs::read_dir(data_path)? {
-        let bundle_name = a?.file_name();
-        let bundle_name = bundle_name.to_str().unwrap();
-        if bundle_name.contains('.') || bundle_name == "game" {
-            continue;
-        }
-        let path = Path::new(data_path).join(bundle_name);
+    for bundle_id in bundle_ids(data_path)? {
+        let path = Path::new(data_path).join(bundle_id.to_string());
 
         let mut reader = BufReader::new(File::open(path)?);
         let header: Header = reader.read_le()?;
-        let types: Vec<DataType> = read_types(&mut reader, header)?;
+        let types: Vec<DataType> = read_types(&mut reader, &header)?;
 
         let mut types_dict: HashMap<Id, &DataType> = Default::default();
         for t in &types {
@@ -195,7 +792,7 @@ fn build_id_cache(data_path: &String) -> anyhow::Result<IdCache> {
             });
         }
 
-        cache.bundles.insert(bundle_name.into(), min_headers);
+        cache.bundles.insert(bundle_id, min_headers);
     }
 
     Ok(cache)
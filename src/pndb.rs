@@ -2,7 +2,7 @@ use std::{collections::HashMap, fs::File, io::{BufReader, Cursor}};
 
 use binrw::{BinRead, BinReaderExt, NullString};
 
-use crate::Id;
+use crate::{error::Result, Id};
 
 #[derive(Debug, Default, Clone)]
 pub struct Pndb {
@@ -35,12 +35,12 @@ impl BinRead for Pndb {
 
         let compressed_data: Vec<u8> = binrw::helpers::count_with(compressed_len as usize, u8::read_options)(reader, endian, args)?;
 
-        let decompressed_data = lz4_flex::decompress(&compressed_data, decompressed_len as usize);
-        if let Err(e) = decompressed_data {
-            return Err(binrw::Error::AssertFail { pos, message: format!("Failed to decompress data: {}", e) })
-        }
+        let decompressed_data = match lz4_flex::decompress(&compressed_data, decompressed_len as usize) {
+            Ok(data) => data,
+            Err(e) => return Err(binrw::Error::AssertFail { pos, message: format!("Failed to decompress data: {}", e) }),
+        };
 
-        let mut decompressed_reader = Cursor::new(decompressed_data.unwrap());
+        let mut decompressed_reader = Cursor::new(decompressed_data);
 
         let mut vals: Vec<String> = Vec::new();
         for _ in 0..database_len {
@@ -48,16 +48,16 @@ impl BinRead for Pndb {
             vals.push(val.to_string());
         }
 
-        for i in 0..database_len {
+        for val in vals {
             let key: Id = Id::read_options(&mut decompressed_reader, endian, ())?;
-            name_database.insert(key, vals.get(i as usize).unwrap().to_string());
+            name_database.insert(key, val);
         }
 
         Ok(Pndb { name_database })
     }
 }
 
-pub fn read_pndb(path: &str) -> anyhow::Result<Pndb> {
+pub fn read_pndb(path: &str) -> Result<Pndb> {
     let mut reader = BufReader::new(File::open(path)?);
     let pndb: Pndb = reader.read_le()?;
     Ok(pndb)
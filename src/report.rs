@@ -0,0 +1,134 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{error::Result, Id};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Ok,
+    Skipped,
+    Failed,
+    /// Some parts were written but others couldn't be read
+    Partial,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetRecord {
+    pub bundle: Id,
+    /// `Id::invalid()` when the whole bundle failed before any of its assets could be read
+    pub id: Id,
+    pub type_id: Id,
+    pub outcome: Outcome,
+    pub error: Option<String>,
+    pub bytes_written: u64,
+}
+
+/// Per-asset outcomes of an extraction run
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Report {
+    pub assets: Vec<AssetRecord>,
+}
+
+impl Report {
+    pub fn record(
+        &mut self,
+        bundle: Id,
+        id: Id,
+        type_id: Id,
+        result: std::result::Result<u64, String>,
+    ) {
+        let (outcome, error, bytes_written) = match result {
+            Ok(bytes) => (Outcome::Ok, None, bytes),
+            Err(e) => (Outcome::Failed, Some(e), 0),
+        };
+        self.assets.push(AssetRecord {
+            bundle,
+            id,
+            type_id,
+            outcome,
+            error,
+            bytes_written,
+        });
+    }
+
+    /// An asset written without some of its parts, which still counts as failed for a retry
+    pub fn partial(&mut self, bundle: Id, id: Id, type_id: Id, bytes_written: u64, error: String) {
+        self.assets.push(AssetRecord {
+            bundle,
+            id,
+            type_id,
+            outcome: Outcome::Partial,
+            error: Some(error),
+            bytes_written,
+        });
+    }
+
+    pub fn skip(&mut self, bundle: Id, id: Id, type_id: Id) {
+        self.assets.push(AssetRecord {
+            bundle,
+            id,
+            type_id,
+            outcome: Outcome::Skipped,
+            error: None,
+            bytes_written: 0,
+        });
+    }
+
+    pub fn count(&self, outcome: Outcome) -> usize {
+        self.assets.iter().filter(|a| a.outcome == outcome).count()
+    }
+
+    /// Failed and partly written assets grouped by bundle. A `None` set means the whole bundle failed
+    /// and has to be redone.
+    pub fn failures(&self) -> HashMap<Id, Option<HashSet<Id>>> {
+        let mut failures: HashMap<Id, Option<HashSet<Id>>> = HashMap::new();
+        for a in self.assets.iter().filter(|a| matches!(a.outcome, Outcome::Failed | Outcome::Partial)) {
+            let entry = failures.entry(a.bundle).or_insert_with(|| Some(HashSet::new()));
+            if a.id == Id::invalid() {
+                *entry = None;
+            } else if let Some(ids) = entry {
+                ids.insert(a.id);
+            }
+        }
+        failures
+    }
+
+    /// Writes the report as csv if the path ends in .csv, json otherwise
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        if is_csv(path) {
+            let mut writer = csv::Writer::from_writer(writer);
+            for a in &self.assets {
+                writer.serialize(a)?;
+            }
+            writer.flush()?;
+        } else {
+            serde_json::to_writer_pretty(writer, self)?;
+        }
+        Ok(())
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        if is_csv(path) {
+            let mut assets = Vec::new();
+            for a in csv::Reader::from_reader(reader).deserialize() {
+                assets.push(a?);
+            }
+            Ok(Report { assets })
+        } else {
+            Ok(serde_json::from_reader(reader)?)
+        }
+    }
+}
+
+fn is_csv(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e.eq_ignore_ascii_case("csv"))
+}
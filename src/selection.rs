@@ -0,0 +1,219 @@
+use std::{collections::HashSet, path::Path};
+
+use clap::ValueEnum;
+use wildmatch::WildMatch;
+
+use crate::{error::Result, pndb::Pndb, DataTypes, Id, IdCache, MinimizedIdHeader};
+
+/// Which assets to operate on. Every non-empty list has to match, an empty list matches anything.
+#[derive(Debug, Default, Clone)]
+pub struct Selection {
+    pub ids: HashSet<Id>,
+    pub type_ids: Vec<Id>,
+    pub bundles: Vec<Id>,
+    /// Glob patterns (`*` and `?`) matched against names from the name database
+    pub names: Vec<String>,
+    /// Bounds on the combined bundle, stream and gpu size of an asset
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+}
+
+/// An asset as found in the cache, with its position in the bundle's header list
+#[derive(Debug, Clone, Copy)]
+pub struct SelectedAsset {
+    pub bundle: Id,
+    pub index: usize,
+    pub header: MinimizedIdHeader,
+}
+
+impl Selection {
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+            && self.type_ids.is_empty()
+            && self.bundles.is_empty()
+            && self.names.is_empty()
+            && self.min_size.is_none()
+            && self.max_size.is_none()
+    }
+
+    pub fn matches(&self, bundle: &Id, h: &MinimizedIdHeader, namedb: &Pndb) -> bool {
+        self.matches_with(bundle, h, namedb, &self.patterns())
+    }
+
+    fn patterns(&self) -> Vec<WildMatch> {
+        self.names.iter().map(|n| WildMatch::new(n)).collect()
+    }
+
+    fn matches_with(
+        &self,
+        bundle: &Id,
+        h: &MinimizedIdHeader,
+        namedb: &Pndb,
+        patterns: &[WildMatch],
+    ) -> bool {
+        if !self.bundles.is_empty() && !self.bundles.contains(bundle) {
+            return false;
+        }
+        if !self.ids.is_empty() && !self.ids.contains(&h.id) {
+            return false;
+        }
+        if !self.type_ids.is_empty() && !self.type_ids.contains(&h.type_id) {
+            return false;
+        }
+        if self.min_size.is_some_and(|min| h.total_size() < min)
+            || self.max_size.is_some_and(|max| h.total_size() > max)
+        {
+            return false;
+        }
+        if !patterns.is_empty() {
+            let Some(name) = namedb.name_database.get(&h.id) else {
+                return false;
+            };
+            if !patterns.iter().any(|p| p.matches(name)) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Every matching asset in the cache, grouped by bundle and in bundle order
+    pub fn resolve(&self, cache: &IdCache, namedb: &Pndb) -> Vec<SelectedAsset> {
+        let mut bundles: Vec<&Id> = cache.bundles.keys().collect();
+        bundles.sort_by_key(|b| u64::from(**b));
+
+        let patterns = self.patterns();
+        let mut assets = Vec::new();
+        for bundle in bundles {
+            if !self.bundles.is_empty() && !self.bundles.contains(bundle) {
+                continue;
+            }
+            for (index, header) in cache.bundles[bundle].iter().enumerate() {
+                if self.matches_with(bundle, header, namedb, &patterns) {
+                    assets.push(SelectedAsset {
+                        bundle: *bundle,
+                        index,
+                        header: *header,
+                    });
+                }
+            }
+        }
+        assets
+    }
+}
+
+/// Parses a type given either by its name (as in `-f texture`) or as a hex type id
+pub fn parse_type_id(s: &str) -> std::result::Result<Id, String> {
+    if let Ok(t) = DataTypes::from_str(s, true) {
+        return Ok(t.as_id());
+    }
+    s.parse()
+        .map_err(|_| format!("{:?} is neither a known type nor a hex type id", s))
+}
+
+/// Reads a list of hex ids, one per line. Blank lines and `#` comments are ignored.
+pub fn read_id_list(path: &Path) -> Result<Vec<Id>> {
+    let mut ids = Vec::new();
+    for line in std::fs::read_to_string(path)?.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if !line.is_empty() {
+            ids.push(line.parse()?);
+        }
+    }
+    Ok(ids)
+}
+
+pub fn print_selection(assets: &[SelectedAsset], namedb: &Pndb) {
+    let mut total = 0;
+    for a in assets {
+        let h = &a.header;
+        total += h.total_size();
+        println!(
+            "{} {:>6} {} {:<16} {:>10} {}",
+            a.bundle,
+            a.index,
+            h.id,
+            format!("{:?}", h.type_id.as_enum()),
+            h.total_size(),
+            namedb.name_database.get(&h.id).map(|n| n.as_str()).unwrap_or_default()
+        );
+    }
+    println!("{} assets, {} bytes", assets.len(), total);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(id: u64, type_id: Id, size: u32) -> MinimizedIdHeader {
+        MinimizedIdHeader {
+            id: Id::from(id),
+            type_id,
+            data_size: size,
+            ..Default::default()
+        }
+    }
+
+    fn namedb() -> Pndb {
+        let mut namedb = Pndb::default();
+        namedb.name_database.insert(Id::from(1), "content/fac_helldivers/armor".to_string());
+        namedb
+    }
+
+    #[test]
+    fn empty_selection_matches_anything() {
+        let selection = Selection::default();
+        assert!(selection.is_empty());
+        assert!(selection.matches(&Id::from(9), &header(1, DataTypes::Unit.as_id(), 10), &namedb()));
+    }
+
+    #[test]
+    fn every_filter_has_to_match() {
+        let selection = Selection {
+            type_ids: vec![DataTypes::Unit.as_id()],
+            bundles: vec![Id::from(9)],
+            min_size: Some(10),
+            ..Default::default()
+        };
+        let namedb = namedb();
+        assert!(selection.matches(&Id::from(9), &header(1, DataTypes::Unit.as_id(), 10), &namedb));
+        assert!(!selection.matches(&Id::from(8), &header(1, DataTypes::Unit.as_id(), 10), &namedb));
+        assert!(!selection.matches(&Id::from(9), &header(1, DataTypes::Texture.as_id(), 10), &namedb));
+        assert!(!selection.matches(&Id::from(9), &header(1, DataTypes::Unit.as_id(), 9), &namedb));
+    }
+
+    #[test]
+    fn names_match_globs_and_need_a_name() {
+        let selection = Selection {
+            names: vec!["content/fac_*/arm?r".to_string()],
+            ..Default::default()
+        };
+        let namedb = namedb();
+        assert!(selection.matches(&Id::from(9), &header(1, DataTypes::Unit.as_id(), 0), &namedb));
+        // an asset without a name can't match a name pattern
+        assert!(!selection.matches(&Id::from(9), &header(2, DataTypes::Unit.as_id(), 0), &namedb));
+    }
+
+    #[test]
+    fn resolve_keeps_bundle_order() {
+        let mut cache = IdCache::default();
+        cache.bundles.insert(Id::from(2), vec![header(3, DataTypes::Unit.as_id(), 0)]);
+        cache.bundles.insert(
+            Id::from(1),
+            vec![header(1, DataTypes::Unit.as_id(), 0), header(2, DataTypes::Lua.as_id(), 0)],
+        );
+        let selection = Selection {
+            type_ids: vec![DataTypes::Unit.as_id()],
+            ..Default::default()
+        };
+        let found: Vec<(Id, usize)> =
+            selection.resolve(&cache, &Pndb::default()).iter().map(|a| (a.bundle, a.index)).collect();
+        assert_eq!(found, vec![(Id::from(1), 0), (Id::from(2), 0)]);
+    }
+
+    #[test]
+    fn parses_types_by_name_or_id() {
+        assert_eq!(parse_type_id("texture"), Ok(DataTypes::Texture.as_id()));
+        assert_eq!(parse_type_id("cd4238c6a0c69e32"), Ok(DataTypes::Texture.as_id()));
+        assert!(parse_type_id("not a type").is_err());
+    }
+}
@@ -0,0 +1,415 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Cursor, Read, Seek, SeekFrom},
+};
+
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::{
+    error::Error, pndb::Pndb, DataHeader, DataReaders, DataTypes, Id, IdCache, MinimizedIdHeader,
+};
+
+type HttpResponse = Response<Cursor<Vec<u8>>>;
+
+pub fn serve(cache: &IdCache, data_path: &str, namedb: &Pndb, port: u16) -> anyhow::Result<()> {
+    let server = match Server::http(("127.0.0.1", port)) {
+        Ok(s) => s,
+        Err(e) => return Err(anyhow::anyhow!("failed to bind port {}: {}", port, e)),
+    };
+    println!("Serving on http://127.0.0.1:{}", port);
+
+    for request in server.incoming_requests() {
+        let response = match handle(cache, data_path, namedb, &request) {
+            Ok(r) => r,
+            Err(e) => {
+                println!("{} {}: {:?}", request.method(), request.url(), e);
+                // the asset itself is broken rather than the server
+                match e.downcast_ref::<Error>() {
+                    Some(e) if e.is_asset_error() => error(422, e),
+                    _ => error(500, e),
+                }
+            }
+        };
+        if let Err(e) = request.respond(response) {
+            println!("Failed to respond: {}", e);
+        }
+    }
+    Ok(())
+}
+
+fn handle(
+    cache: &IdCache,
+    data_path: &str,
+    namedb: &Pndb,
+    request: &Request,
+) -> anyhow::Result<HttpResponse> {
+    if *request.method() != Method::Get {
+        return Ok(error(405, "only GET is supported"));
+    }
+    let (path, query) = match request.url().split_once('?') {
+        Some((path, query)) => (path, parse_query(query)),
+        None => (request.url(), HashMap::new()),
+    };
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    match segments[..] {
+        ["bundles"] => Ok(list_bundles(cache)),
+        ["assets"] => list_assets(cache, namedb, &query),
+        ["assets", id] => {
+            let Some(id) = parse_id(id) else {
+                return Ok(error(400, "invalid id"));
+            };
+            Ok(asset_info(cache, namedb, id))
+        }
+        ["assets", id, endpoint] => {
+            let Some(id) = parse_id(id) else {
+                return Ok(error(400, "invalid id"));
+            };
+            let t = match endpoint {
+                "raw" => None,
+                "texture.png" => Some(DataTypes::Texture),
+                "unit.glb" => Some(DataTypes::Unit),
+                "strings.json" => Some(DataTypes::String),
+                _ => return Ok(error(404, "not found")),
+            };
+            let bundle = match query.get("bundle") {
+                Some(b) => match parse_id(b) {
+                    Some(b) if cache.bundles.contains_key(&b) => b,
+                    _ => return Ok(error(404, "bundle not found")),
+                },
+                None => Id::invalid(),
+            };
+            let Ok((bundle, h)) = cache.get_by_id(id, t, bundle) else {
+                return Ok(error(404, "asset not found"));
+            };
+            if t.is_none() {
+                return raw_part(data_path, request, &query, bundle, &h);
+            }
+            let etag = format!("\"{}-{}-{}-{}\"", bundle, h.id, h.data_offset, endpoint);
+            if is_not_modified(request, &etag) {
+                return Ok(not_modified(&etag));
+            }
+            let mut readers = DataReaders::open(data_path, &bundle)?;
+            let mut d: DataHeader = h.into();
+            let (buf, content_type) = match endpoint {
+                "texture.png" => {
+                    let (dds, _) = crate::types::texture::extract_texture(&mut d, &mut readers)?;
+                    (crate::types::texture::dds_to_png(&dds)?, "image/png")
+                }
+                "unit.glb" => {
+                    let lods = crate::types::unit::read_unit(&d, &mut readers)?;
+                    (
+                        crate::types::unit::unit_to_glb(&d, &lods)?,
+                        "model/gltf-binary",
+                    )
+                }
+                _ => {
+                    let (json, _) = crate::types::string::extract_strings(&mut d, &mut readers)?;
+                    (json, "application/json")
+                }
+            };
+            Ok(with_header(
+                with_header(Response::from_data(buf), "Content-Type", content_type),
+                "ETag",
+                &etag,
+            ))
+        }
+        _ => Ok(error(404, "not found")),
+    }
+}
+
+fn list_bundles(cache: &IdCache) -> HttpResponse {
+    let mut bundles: Vec<Value> = cache
+        .bundles
+        .iter()
+        .map(|(id, headers)| {
+            json!({
+                "id": id,
+                "assets": headers.len(),
+                "data_size": headers.iter().map(|h| h.data_size as u64).sum::<u64>(),
+                "stream_data_size": headers.iter().map(|h| h.stream_data_size as u64).sum::<u64>(),
+                "gpu_data_size": headers.iter().map(|h| h.gpu_data_size as u64).sum::<u64>(),
+            })
+        })
+        .collect();
+    bundles.sort_by(|a, b| a["id"].as_str().cmp(&b["id"].as_str()));
+    json_response(&Value::Array(bundles))
+}
+
+fn list_assets(
+    cache: &IdCache,
+    namedb: &Pndb,
+    query: &HashMap<String, String>,
+) -> anyhow::Result<HttpResponse> {
+    let type_filter = match query.get("type").filter(|t| !t.is_empty()) {
+        Some(t) => match DataTypes::from_str(t, true) {
+            Ok(t) => Some(t.as_id()),
+            Err(_) => match parse_id(t) {
+                Some(t) => Some(t),
+                None => return Ok(error(400, "unknown type")),
+            },
+        },
+        None => None,
+    };
+    let name_filter = query
+        .get("name")
+        .map(|n| n.to_lowercase())
+        .filter(|n| !n.is_empty());
+    let offset: usize = query
+        .get("offset")
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(0);
+    let limit: usize = query
+        .get("limit")
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(usize::MAX);
+
+    // the same asset can live in several bundles, list it once with every bundle it's in
+    let mut assets: HashMap<(Id, Id), Vec<Id>> = HashMap::new();
+    for (bundle, headers) in &cache.bundles {
+        for h in headers {
+            if type_filter.is_some_and(|t| t != h.type_id) {
+                continue;
+            }
+            if let Some(ref n) = name_filter {
+                match namedb.name_database.get(&h.id) {
+                    Some(name) if name.to_lowercase().contains(n) => {}
+                    _ => continue,
+                }
+            }
+            assets.entry((h.type_id, h.id)).or_default().push(*bundle);
+        }
+    }
+    let mut assets: Vec<((Id, Id), Vec<Id>)> = assets.into_iter().collect();
+    assets.sort_by_key(|((t, id), _)| (u64::from(*t), u64::from(*id)));
+
+    let total = assets.len();
+    let assets: Vec<Value> = assets
+        .into_iter()
+        .skip(offset)
+        .take(limit)
+        .map(|((type_id, id), bundles)| {
+            json!({
+                "id": id,
+                "name": namedb.name_database.get(&id),
+                "type": format!("{:?}", type_id.as_enum()),
+                "type_id": type_id,
+                "bundles": bundles,
+            })
+        })
+        .collect();
+    Ok(json_response(&json!({ "total": total, "assets": assets })))
+}
+
+fn asset_info(cache: &IdCache, namedb: &Pndb, id: Id) -> HttpResponse {
+    let mut entries: Vec<Value> = Vec::new();
+    for (bundle, headers) in &cache.bundles {
+        for h in headers.iter().filter(|h| h.id == id) {
+            let mut entry = serde_json::to_value(h).unwrap_or_default();
+            entry["bundle"] = json!(bundle);
+            entry["type"] = json!(format!("{:?}", h.type_id.as_enum()));
+            entries.push(entry);
+        }
+    }
+    if entries.is_empty() {
+        return error(404, "asset not found");
+    }
+    json_response(&json!({
+        "id": id,
+        "name": namedb.name_database.get(&id),
+        "entries": entries,
+    }))
+}
+
+fn raw_part(
+    data_path: &str,
+    request: &Request,
+    query: &HashMap<String, String>,
+    bundle: Id,
+    h: &MinimizedIdHeader,
+) -> anyhow::Result<HttpResponse> {
+    let part = query.get("part").map(|p| p.as_str()).unwrap_or("bundle");
+    let (extension, offset, size) = match part {
+        "bundle" => (None, h.data_offset, h.data_size as u64),
+        "stream" => (
+            Some("stream"),
+            h.stream_data_offset as u64,
+            h.stream_data_size as u64,
+        ),
+        "gpu" => (
+            Some("gpu_resources"),
+            h.gpu_data_offset,
+            h.gpu_data_size as u64,
+        ),
+        _ => return Ok(error(400, "part must be one of bundle, stream or gpu")),
+    };
+    if size == 0 {
+        return Ok(error(404, format!("asset has no {} data", part)));
+    }
+
+    let etag = format!("\"{}-{}-{}-{}-{}\"", bundle, h.id, part, offset, size);
+    if is_not_modified(request, &etag) {
+        return Ok(not_modified(&etag));
+    }
+
+    let (start, end) = match header_value(request, "Range") {
+        Some(range) => match parse_range(&range, size) {
+            Some(r) => r,
+            None => {
+                let response = error(416, "invalid range");
+                return Ok(with_header(
+                    response,
+                    "Content-Range",
+                    &format!("bytes */{}", size),
+                ));
+            }
+        },
+        None => (0, size - 1),
+    };
+
+    let mut path = std::path::Path::new(data_path).join(bundle.to_string());
+    if let Some(ext) = extension {
+        path.set_extension(ext);
+    }
+    if !path.exists() {
+        return Ok(error(404, format!("{} file not found", part)));
+    }
+    let mut reader = BufReader::new(File::open(path)?);
+    reader.seek(SeekFrom::Start(offset + start))?;
+    let mut buf = vec![0u8; (end - start + 1) as usize];
+    reader.read_exact(&mut buf)?;
+
+    let mut response = Response::from_data(buf);
+    response = with_header(response, "Content-Type", "application/octet-stream");
+    response = with_header(response, "Accept-Ranges", "bytes");
+    response = with_header(response, "ETag", &etag);
+    if start != 0 || end != size - 1 {
+        response = with_header(
+            response,
+            "Content-Range",
+            &format!("bytes {}-{}/{}", start, end, size),
+        );
+        response = response.with_status_code(206);
+    }
+    Ok(response)
+}
+
+/// Parses a single `bytes=` range into inclusive start/end offsets
+fn parse_range(range: &str, size: u64) -> Option<(u64, u64)> {
+    let range = range.trim().strip_prefix("bytes=")?;
+    if range.contains(',') || size == 0 {
+        return None;
+    }
+    let (start, end) = range.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            (size.saturating_sub(suffix), size - 1)
+        }
+        (start, "") => (start.parse().ok()?, size - 1),
+        (start, end) => (start.parse().ok()?, end.parse::<u64>().ok()?.min(size - 1)),
+    };
+    if start > end || start >= size {
+        return None;
+    }
+    Some((start, end))
+}
+
+fn parse_id(s: &str) -> Option<Id> {
+    u64::from_str_radix(s, 16).ok().map(Id::new)
+}
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter_map(|pair| {
+            let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+            Some((percent_decode(k)?, percent_decode(v)?))
+        })
+        .collect()
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let mut out: Vec<u8> = Vec::with_capacity(s.len());
+    let mut bytes = s.bytes();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'+' => out.push(b' '),
+            b'%' => {
+                let hex = [bytes.next()?, bytes.next()?];
+                out.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+            }
+            _ => out.push(b),
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+fn header_value(request: &Request, name: &'static str) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.to_string())
+}
+
+fn is_not_modified(request: &Request, etag: &str) -> bool {
+    header_value(request, "If-None-Match").is_some_and(|v| v.split(',').any(|t| t.trim() == etag))
+}
+
+fn not_modified(etag: &str) -> HttpResponse {
+    with_header(
+        Response::from_data(Vec::new()).with_status_code(304),
+        "ETag",
+        etag,
+    )
+}
+
+fn with_header(response: HttpResponse, name: &str, value: &str) -> HttpResponse {
+    match Header::from_bytes(name.as_bytes(), value.as_bytes()) {
+        Ok(h) => response.with_header(h),
+        Err(_) => response,
+    }
+}
+
+fn json_response(value: &Value) -> HttpResponse {
+    let body = serde_json::to_vec(value).unwrap_or_default();
+    with_header(
+        Response::from_data(body),
+        "Content-Type",
+        "application/json",
+    )
+}
+
+fn error(status: u16, message: impl std::fmt::Display) -> HttpResponse {
+    json_response(&json!({ "error": message.to_string() })).with_status_code(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ranges() {
+        assert_eq!(parse_range("bytes=0-99", 1000), Some((0, 99)));
+        assert_eq!(parse_range(" bytes=500- ", 1000), Some((500, 999)));
+        assert_eq!(parse_range("bytes=-100", 1000), Some((900, 999)));
+        // suffixes longer than the part and ends past it are clamped
+        assert_eq!(parse_range("bytes=-5000", 1000), Some((0, 999)));
+        assert_eq!(parse_range("bytes=990-5000", 1000), Some((990, 999)));
+    }
+
+    #[test]
+    fn rejects_unsatisfiable_ranges() {
+        assert_eq!(parse_range("bytes=1000-", 1000), None);
+        assert_eq!(parse_range("bytes=50-10", 1000), None);
+        assert_eq!(parse_range("bytes=-0", 1000), None);
+        assert_eq!(parse_range("bytes=0-1,5-6", 1000), None);
+        assert_eq!(parse_range("items=0-1", 1000), None);
+        assert_eq!(parse_range("bytes=a-b", 1000), None);
+        assert_eq!(parse_range("bytes=0-0", 0), None);
+    }
+}
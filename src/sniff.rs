@@ -0,0 +1,69 @@
+use serde::Serialize;
+
+/// File formats recognizable from their first few bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub enum Magic {
+    Dds,
+    Wem,
+    Riff,
+    Bkhd,
+    HavokTag,
+    HavokPack,
+    Dxbc,
+    LuaJit,
+    Lua,
+    Png,
+    Ogg,
+}
+
+/// How many bytes `sniff` looks at
+pub const SNIFF_LEN: usize = 16;
+
+impl Magic {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Magic::Dds => "dds",
+            Magic::Wem => "wem",
+            Magic::Riff => "riff",
+            Magic::Bkhd => "bnk",
+            Magic::HavokTag | Magic::HavokPack => "hkx",
+            Magic::Dxbc => "dxbc",
+            Magic::LuaJit | Magic::Lua => "luac",
+            Magic::Png => "png",
+            Magic::Ogg => "ogg",
+        }
+    }
+}
+
+pub fn sniff(buf: &[u8]) -> Option<Magic> {
+    let at = |offset: usize, magic: &[u8]| buf.get(offset..offset + magic.len()) == Some(magic);
+    let magic = if at(0, b"DDS ") {
+        Magic::Dds
+    } else if at(0, b"RIFF") {
+        // wwise sounds are RIFF/WAVE with their own codecs
+        if at(8, b"WAVE") {
+            Magic::Wem
+        } else {
+            Magic::Riff
+        }
+    } else if at(0, b"BKHD") {
+        Magic::Bkhd
+    } else if at(4, b"TAG0") || at(4, b"TCM0") {
+        Magic::HavokTag
+    } else if at(0, &[0x57, 0xe0, 0xe0, 0x57, 0x10, 0xc0, 0xc0, 0x10]) {
+        Magic::HavokPack
+    } else if at(0, b"DXBC") {
+        Magic::Dxbc
+    } else if at(0, b"\x1bLJ") {
+        Magic::LuaJit
+    } else if at(0, b"\x1bLua") {
+        Magic::Lua
+    } else if at(0, b"\x89PNG") {
+        Magic::Png
+    } else if at(0, b"OggS") {
+        Magic::Ogg
+    } else {
+        return None;
+    };
+    Some(magic)
+}
@@ -0,0 +1,277 @@
+use std::path::{Path, PathBuf};
+
+pub const APP_ID: u32 = 553850;
+
+/// A Helldivers 2 install found through Steam's library files
+#[derive(Debug, Clone)]
+pub struct SteamInstall {
+    pub data_path: PathBuf,
+    pub build_id: Option<u64>,
+}
+
+/// A parsed Valve KeyValues (.vdf/.acf) value
+#[derive(Debug, Clone)]
+enum Vdf {
+    Str(String),
+    Obj(Vec<(String, Vdf)>),
+}
+
+impl Vdf {
+    fn get(&self, key: &str) -> Option<&Vdf> {
+        match self {
+            Vdf::Obj(entries) => entries
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v),
+            Vdf::Str(_) => None,
+        }
+    }
+
+    fn str(&self) -> Option<&str> {
+        match self {
+            Vdf::Str(s) => Some(s),
+            Vdf::Obj(_) => None,
+        }
+    }
+
+    fn entries(&self) -> &[(String, Vdf)] {
+        match self {
+            Vdf::Obj(entries) => entries,
+            Vdf::Str(_) => &[],
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' => tokens.push(c.to_string()),
+            '"' => {
+                let mut s = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some('n') => s.push('\n'),
+                            Some('t') => s.push('\t'),
+                            Some(c) => s.push(c),
+                            None => break,
+                        },
+                        c => s.push(c),
+                    }
+                }
+                // Keep quoted strings apart from braces by prefixing them
+                tokens.push(format!("\"{}", s));
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    tokens
+}
+
+fn parse_object(tokens: &mut std::vec::IntoIter<String>) -> Vdf {
+    let mut entries = Vec::new();
+    while let Some(key) = tokens.next() {
+        let Some(key) = key.strip_prefix('"') else {
+            // a closing brace, or junk we can't make sense of
+            break;
+        };
+        let value = match tokens.next() {
+            Some(t) if t == "{" => parse_object(tokens),
+            Some(t) => match t.strip_prefix('"') {
+                Some(s) => Vdf::Str(s.to_string()),
+                None => break,
+            },
+            None => break,
+        };
+        entries.push((key.to_string(), value));
+    }
+    Vdf::Obj(entries)
+}
+
+fn parse_vdf(text: &str) -> Vdf {
+    parse_object(&mut tokenize(text).into_iter())
+}
+
+fn read_vdf(path: &Path) -> Option<Vdf> {
+    let text = std::fs::read_to_string(path).ok()?;
+    Some(parse_vdf(&text))
+}
+
+/// Standard Steam locations for native and Flatpak installs
+pub fn steam_roots() -> Vec<PathBuf> {
+    let Some(home) = std::env::var_os("HOME").map(PathBuf::from) else {
+        return Vec::new();
+    };
+    let flatpak = home.join(".var/app/com.valvesoftware.Steam");
+    let mut roots: Vec<PathBuf> = Vec::new();
+    for root in [
+        home.join(".steam/steam"),
+        home.join(".steam/root"),
+        home.join(".local/share/Steam"),
+        flatpak.join(".local/share/Steam"),
+        flatpak.join(".steam/steam"),
+        flatpak.join("data/Steam"),
+    ] {
+        let Ok(root) = root.canonicalize() else {
+            continue;
+        };
+        if !roots.contains(&root) {
+            roots.push(root);
+        }
+    }
+    roots
+}
+
+/// Every library folder listed in the roots' libraryfolders.vdf, plus the roots themselves
+fn library_folders() -> Vec<PathBuf> {
+    let mut libraries: Vec<PathBuf> = Vec::new();
+    for root in steam_roots() {
+        let mut found = vec![root.clone()];
+        if let Some(vdf) = read_vdf(&root.join("steamapps/libraryfolders.vdf")) {
+            found.extend(library_paths(&vdf));
+        }
+        for library in found {
+            let library = library.canonicalize().unwrap_or(library);
+            if !libraries.contains(&library) {
+                libraries.push(library);
+            }
+        }
+    }
+    libraries
+}
+
+/// Paths listed in a libraryfolders.vdf
+fn library_paths(vdf: &Vdf) -> Vec<PathBuf> {
+    let folders = vdf.get("libraryfolders").unwrap_or(vdf);
+    folders
+        .entries()
+        .iter()
+        // libraries are keyed by index, next to bookkeeping like "TimeNextStatsReport"
+        .filter(|(key, _)| key.parse::<u32>().is_ok())
+        // Old versions list the path directly, newer ones an object with a "path" key
+        .filter_map(|(_, folder)| folder.str().or(folder.get("path").and_then(Vdf::str)))
+        .map(PathBuf::from)
+        .collect()
+}
+
+fn read_manifest(path: &Path) -> Option<(String, Option<u64>)> {
+    manifest_fields(&read_vdf(path)?)
+}
+
+/// Install directory and build id from an appmanifest
+fn manifest_fields(vdf: &Vdf) -> Option<(String, Option<u64>)> {
+    let state = vdf.get("AppState")?;
+    let install_dir = state.get("installdir")?.str()?.to_string();
+    let build_id = state.get("buildid").and_then(Vdf::str).and_then(|b| b.parse().ok());
+    Some((install_dir, build_id))
+}
+
+fn manifest_name() -> String {
+    format!("appmanifest_{}.acf", APP_ID)
+}
+
+/// Looks through the Steam libraries for an installed copy of the game
+pub fn find_install() -> Option<SteamInstall> {
+    for library in library_folders() {
+        let steamapps = library.join("steamapps");
+        let Some((install_dir, build_id)) = read_manifest(&steamapps.join(manifest_name())) else {
+            continue;
+        };
+        let data_path = steamapps.join("common").join(install_dir).join("data");
+        if data_path.is_dir() {
+            return Some(SteamInstall {
+                data_path,
+                build_id,
+            });
+        }
+    }
+    None
+}
+
+/// Build id of the install a data directory belongs to, if it sits in a Steam library
+pub fn installed_build(data_path: &Path) -> Option<u64> {
+    // <library>/steamapps/common/<installdir>/data
+    let data_path = data_path.canonicalize().ok()?;
+    let steamapps = data_path.parent()?.parent()?.parent()?;
+    read_manifest(&steamapps.join(manifest_name()))?.1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_objects() {
+        let vdf = parse_vdf(
+            r#"
+            // a comment { "not": "a key" }
+            "Outer"
+            {
+                "name"  "a \"quoted\" \\ value"
+                "Inner" { "x" "1" }
+                "empty" ""
+            }
+            "#,
+        );
+        let outer = vdf.get("outer").unwrap();
+        assert_eq!(outer.get("NAME").and_then(Vdf::str), Some(r#"a "quoted" \ value"#));
+        assert_eq!(outer.get("inner").and_then(|i| i.get("x")).and_then(Vdf::str), Some("1"));
+        assert_eq!(outer.get("empty").and_then(Vdf::str), Some(""));
+        assert!(outer.get("not").is_none());
+        assert!(outer.get("name").and_then(|n| n.get("x")).is_none());
+    }
+
+    #[test]
+    fn stops_at_junk_and_unbalanced_input() {
+        let vdf = parse_vdf(r#""a" "1" "b" { "c" "2" "#);
+        assert_eq!(vdf.get("a").and_then(Vdf::str), Some("1"));
+        assert_eq!(vdf.get("b").and_then(|b| b.get("c")).and_then(Vdf::str), Some("2"));
+        assert!(parse_vdf("").entries().is_empty());
+        assert!(parse_vdf(r#"} "a" "1""#).entries().is_empty());
+    }
+
+    #[test]
+    fn lists_library_folders_old_and_new() {
+        let new = parse_vdf(
+            r#""libraryfolders"
+            {
+                "0" { "path" "/home/me/.local/share/Steam" "label" "" "apps" { "553850" "123" } }
+                "1" { "path" "/mnt/games/SteamLibrary" }
+            }"#,
+        );
+        assert_eq!(
+            library_paths(&new),
+            [PathBuf::from("/home/me/.local/share/Steam"), PathBuf::from("/mnt/games/SteamLibrary")]
+        );
+        let old = parse_vdf(
+            r#""LibraryFolders" { "TimeNextStatsReport" "0" "1" "/mnt/games" }"#,
+        );
+        assert_eq!(library_paths(&old), [PathBuf::from("/mnt/games")]);
+    }
+
+    #[test]
+    fn reads_appmanifest_fields() {
+        let manifest = parse_vdf(
+            r#""AppState"
+            {
+                "appid"      "553850"
+                "installdir" "Helldivers 2"
+                "buildid"    "14563781"
+            }"#,
+        );
+        assert_eq!(manifest_fields(&manifest), Some(("Helldivers 2".to_string(), Some(14563781))));
+        let no_build = parse_vdf(r#""AppState" { "installdir" "Helldivers 2" "buildid" "x" }"#);
+        assert_eq!(manifest_fields(&no_build), Some(("Helldivers 2".to_string(), None)));
+        assert_eq!(manifest_fields(&parse_vdf(r#""AppState" { "appid" "1" }"#)), None);
+    }
+}
@@ -1,10 +1,12 @@
-use anyhow::Result;
+use crate::error::{Error, Result};
+use crate::hash::type_hash;
 use binrw::{binread, binrw, BinRead, BinWrite};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
     fs::File,
     io::{BufReader, Read, Seek, SeekFrom},
+    path::Path,
 };
 
 #[derive(
@@ -17,41 +19,185 @@ pub enum DataTypes {
     #[value(name = "unknown")]
     Unknown = 0xFFFFFFFF_FFFFFFFF,
 
-    #[value(name = "wem")]
-    WwiseWem = 0x504b5523_5d21440e,
+    // Type ids are the murmur64a hashes of the engine's resource type names. Value names are the
+    // type names themselves, with the names we used before as aliases.
 
-    #[value(name = "bnk")]
-    WwiseBNK = 0x535A7BD3_E650D799,
+    #[value(name = "wwise_stream", alias = "wem")]
+    WwiseWem = type_hash("wwise_stream"),
 
+    #[value(name = "wwise_bank", alias = "bnk")]
+    WwiseBNK = type_hash("wwise_bank"),
 
-    // TODO: re-check these
+    #[value(name = "wwise_dep")]
+    WwiseDep = type_hash("wwise_dep"),
 
-    // #[value(name = "havok1")]
-    // Havok = 0x5F7203C8_F280DAB8,
+    #[value(name = "wwise_metadata")]
+    WwiseMetadata = type_hash("wwise_metadata"),
 
-    // #[value(name = "havok2")]
-    // Havok2 = 0x1D59BD66_87DB6B33,
+    #[value(name = "wwise_properties")]
+    WwiseProperties = type_hash("wwise_properties"),
+
+    #[value(name = "wwise_event")]
+    WwiseEvent = type_hash("wwise_event"),
 
     #[value(name = "texture")]
-    Texture = 0xCD4238C6_A0C69E32,
+    Texture = type_hash("texture"),
 
     #[value(name = "unit")]
-    Unit = 0xE0A48D0B_E9A7453F,
+    Unit = type_hash("unit"),
+
+    #[value(name = "strings", alias = "string")]
+    String = type_hash("strings"),
+
+    #[value(name = "material")]
+    Material = type_hash("material"),
+
+    #[value(name = "entity")]
+    Entity = type_hash("entity"),
+
+    #[value(name = "bones", alias = "skeleton")]
+    Bones = type_hash("bones"),
+
+    #[value(name = "animation")]
+    Animation = type_hash("animation"),
+
+    #[value(name = "animation_curves")]
+    AnimationCurves = type_hash("animation_curves"),
+
+    #[value(name = "state_machine")]
+    StateMachine = type_hash("state_machine"),
+
+    #[value(name = "lua")]
+    Lua = type_hash("lua"),
+
+    #[value(name = "package")]
+    Package = type_hash("package"),
+
+    #[value(name = "level")]
+    Level = type_hash("level"),
+
+    #[value(name = "particles")]
+    Particles = type_hash("particles"),
+
+    #[value(name = "physics", alias = "havok")]
+    Physics = type_hash("physics"),
+
+    #[value(name = "physics_properties")]
+    PhysicsProperties = type_hash("physics_properties"),
+
+    #[value(name = "havok_physics_properties")]
+    HavokPhysicsProperties = type_hash("havok_physics_properties"),
+
+    #[value(name = "ragdoll_profile")]
+    RagdollProfile = type_hash("ragdoll_profile"),
+
+    #[value(name = "cloth_profile")]
+    ClothProfile = type_hash("cloth_profile"),
+
+    #[value(name = "shading_environment")]
+    ShadingEnvironment = type_hash("shading_environment"),
+
+    #[value(name = "shading_environment_mapping")]
+    ShadingEnvironmentMapping = type_hash("shading_environment_mapping"),
+
+    #[value(name = "font")]
+    Font = type_hash("font"),
+
+    #[value(name = "shader")]
+    Shader = type_hash("shader"),
+
+    #[value(name = "shader_library")]
+    ShaderLibrary = type_hash("shader_library"),
+
+    #[value(name = "shader_library_group")]
+    ShaderLibraryGroup = type_hash("shader_library_group"),
+
+    #[value(name = "render_config")]
+    RenderConfig = type_hash("render_config"),
+
+    #[value(name = "config")]
+    Config = type_hash("config"),
+
+    #[value(name = "data")]
+    Data = type_hash("data"),
+
+    #[value(name = "flow")]
+    Flow = type_hash("flow"),
+
+    #[value(name = "navdata")]
+    Navdata = type_hash("navdata"),
+
+    #[value(name = "network_config")]
+    Network = type_hash("network_config"),
+
+    #[value(name = "surface_properties")]
+    SurfaceProperties = type_hash("surface_properties"),
+
+    #[value(name = "sound_environment")]
+    SoundEnvironment = type_hash("sound_environment"),
+
+    #[value(name = "vector_field")]
+    VectorField = type_hash("vector_field"),
+
+    #[value(name = "prefab")]
+    Prefab = type_hash("prefab"),
+
+    #[value(name = "geometry_group")]
+    GeometryGroup = type_hash("geometry_group"),
+
+    #[value(name = "speedtree")]
+    Speedtree = type_hash("speedtree"),
+
+    #[value(name = "vertex_animation")]
+    VertexAnimation = type_hash("vertex_animation"),
+
+    #[value(name = "baked_lighting")]
+    BakedLighting = type_hash("baked_lighting"),
+
+    #[value(name = "texture_atlas")]
+    TextureAtlas = type_hash("texture_atlas"),
+
+    #[value(name = "decal_definition")]
+    DecalDefinition = type_hash("decal_definition"),
+
+    #[value(name = "liquid_definition")]
+    LiquidDefinition = type_hash("liquid_definition"),
+
+    #[value(name = "hash_lookup")]
+    HashLookup = type_hash("hash_lookup"),
+
+    #[value(name = "bik")]
+    Bik = type_hash("bik"),
+
+    #[value(name = "ivf")]
+    Ivf = type_hash("ivf"),
+
+    #[value(name = "mouse_cursor")]
+    MouseCursor = type_hash("mouse_cursor"),
+
+    #[value(name = "keys")]
+    Keys = type_hash("keys"),
+
+    #[value(name = "scene")]
+    Scene = type_hash("scene"),
+
+    #[value(name = "slug")]
+    Slug = type_hash("slug"),
 
-    #[value(name = "string")]
-    String = 0x0D972BAB_10B40FD3,
+    #[value(name = "slug_album")]
+    SlugAlbum = type_hash("slug_album"),
 
-    // #[value(name = "entity")]
-    // Entity = 0x7d080d3b_89ca3198,
+    #[value(name = "xml")]
+    Xml = type_hash("xml"),
 
-    // #[value(name = "material")]
-    // Material = 0xDFDE6A87_97B4C0EA,
+    #[value(name = "ini")]
+    Ini = type_hash("ini"),
 
-    // Skeleton = 0xe9726b05_01adde18,
+    #[value(name = "mod")]
+    Mod = type_hash("mod"),
 
-    WwiseDep = 0xAF32095C_82F2B070,
-    WwiseMetadata = 0xD50A8B7E_1C82B110,
-    WwiseProperties = 0x5FDD5FE3_91076F9F,
+    #[value(name = "crypto")]
+    Crypto = type_hash("crypto"),
 }
 
 impl DataTypes {
@@ -59,10 +205,21 @@ impl DataTypes {
         match self {
             DataTypes::WwiseWem => "wem",
             DataTypes::WwiseBNK => "bnk",
-            // DataTypes::Havok | DataTypes::Havok2 => "hkt",
             DataTypes::Texture => "dds",
             DataTypes::Unit => "obj",
-            DataTypes::String => "json",
+            DataTypes::String
+            | DataTypes::Package
+            | DataTypes::Level
+            | DataTypes::Physics
+            | DataTypes::HavokPhysicsProperties
+            | DataTypes::RagdollProfile
+            | DataTypes::Animation
+            | DataTypes::Bones
+            | DataTypes::Shader
+            | DataTypes::ShaderLibrary
+            | DataTypes::Font
+            | DataTypes::Navdata => "json",
+            DataTypes::Lua => "luac",
             _ => "bin",
         }
     }
@@ -75,6 +232,10 @@ impl DataTypes {
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct IdCache {
     pub bundles: HashMap<Id, Vec<MinimizedIdHeader>>,
+    /// Steam build id of the install the cache was built from. Stored after the bundles so older caches
+    /// without it still load.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build_id: Option<u64>,
 }
 
 impl IdCache {
@@ -83,9 +244,12 @@ impl IdCache {
         x: Id,
         t: Option<DataTypes>,
         b: Id
-    ) -> anyhow::Result<(Id, MinimizedIdHeader)> {
+    ) -> Result<(Id, MinimizedIdHeader)> {
         if b != Id::invalid() {
-            for header in self.bundles.get(&b).unwrap() {
+            let Some(headers) = self.bundles.get(&b) else {
+                return Err(Error::UnknownBundle(b));
+            };
+            for header in headers {
                 if header.id == x {
                     if let Some(a) = t {
                         if header.type_id != a.as_id() {
@@ -95,7 +259,7 @@ impl IdCache {
                     return Ok((b, *header));
                 }
             }
-            return Err(anyhow::anyhow!("id {} not found in cache for bundle {}", x, b));
+            return Err(Error::IdNotFound { id: x, bundle: Some(b) });
         }
         for (bundle, headers) in self.bundles.iter() {
             for header in headers {
@@ -109,7 +273,7 @@ impl IdCache {
                 }
             }
         }
-        Err(anyhow::anyhow!("id {} not found in cache", x))
+        Err(Error::IdNotFound { id: x, bundle: None })
     }
 }
 
@@ -134,7 +298,8 @@ impl BinRead for IdCache {
             //headers.dedup_by(|a, b| a.id == b.id && a.type_id == b.type_id);
             bundles.insert(id, headers);
         }
-        Ok(IdCache { bundles })
+        let build_id = u64::read_options(reader, endian, ()).ok();
+        Ok(IdCache { bundles, build_id })
     }
 }
 
@@ -157,6 +322,9 @@ impl BinWrite for IdCache {
                 header.write_options(writer, endian, ())?;
             }
         }
+        if let Some(build_id) = self.build_id {
+            build_id.write_options(writer, endian, ())?;
+        }
         Ok(())
     }
 }
@@ -182,6 +350,13 @@ pub struct MinimizedIdHeader {
     pub gpu_data_size: u32,
 }
 
+impl MinimizedIdHeader {
+    /// Combined size of the asset's data in the bundle, stream and gpu_resources files
+    pub fn total_size(&self) -> u64 {
+        self.data_size as u64 + self.stream_data_size as u64 + self.gpu_data_size as u64
+    }
+}
+
 // u64, binrw reads as u64, serde reads as string, both output to string (binrw: hex u64, serde: string)
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub struct Id {
@@ -218,15 +393,14 @@ impl From<Id> for String {
     }
 }
 
-impl From<&str> for Id {
-    fn from(s: &str) -> Self {
-        Id::new(u64::from_str_radix(s, 16).unwrap())
-    }
-}
+impl std::str::FromStr for Id {
+    type Err = Error;
 
-impl From<String> for Id {
-    fn from(s: String) -> Self {
-        Id::new(u64::from_str_radix(&s, 16).unwrap())
+    fn from_str(s: &str) -> Result<Self> {
+        match u64::from_str_radix(s, 16) {
+            Ok(id) => Ok(Id::new(id)),
+            Err(_) => Err(Error::InvalidId(s.to_string())),
+        }
     }
 }
 
@@ -257,8 +431,7 @@ impl<'de> serde::Deserialize<'de> for Id {
         D: serde::Deserializer<'de>,
     {
         let s = String::deserialize(deserializer)?;
-        let id = u64::from_str_radix(&s, 16).unwrap();
-        Ok(Id::new(id))
+        s.parse().map_err(serde::de::Error::custom)
     }
 }
 
@@ -342,28 +515,70 @@ pub struct DataHeader {
 
 impl DataHeader {
     pub fn get_stream_buf(&self, r: &mut Option<BufReader<File>>) -> Result<Vec<u8>> {
-        if self.stream_data_size == 0 {
-            return Err(anyhow::anyhow!("stream data size 0"));
-        }
-        let mut buf = vec![0u8; self.stream_data_size as usize];
-        if r.is_none() {
-            panic!("Stream file referenced but not found.");
-        }
-        if let Some(ref mut reader) = r {
-            reader.seek(SeekFrom::Start(self.stream_data_offset as u64))?;
-            reader.read_exact(&mut buf)?;
-        }
-
-        Ok(buf)
+        let Some(ref mut reader) = r else {
+            return Err(Error::MissingCompanionFile("stream"));
+        };
+        self.read_part(reader, self.stream_data_offset as u64, self.stream_data_size, "stream")
     }
 
     pub fn get_bundle_buf(&self, r: &mut BufReader<File>) -> Result<Vec<u8>> {
-        if self.data_size == 0 {
-            return Err(anyhow::anyhow!("bundle data size 0"));
+        self.read_part(r, self.data_offset, self.data_size, "bundle")
+    }
+
+    pub fn get_gpu_buf(&self, r: &mut Option<BufReader<File>>) -> Result<Vec<u8>> {
+        let Some(ref mut reader) = r else {
+            return Err(Error::MissingCompanionFile("gpu_resources"));
+        };
+        self.read_part(reader, self.gpu_data_offset, self.gpu_data_size, "gpu")
+    }
+
+    /// `len` bytes `at` into the asset's bundle data
+    pub fn get_bundle_range(&self, r: &mut BufReader<File>, at: u64, len: usize) -> Result<Vec<u8>> {
+        self.read_range(r, self.data_offset, self.data_size, at, len, "bundle")
+    }
+
+    /// `len` bytes `at` into the asset's gpu data
+    pub fn get_gpu_range(&self, r: &mut Option<BufReader<File>>, at: u64, len: usize) -> Result<Vec<u8>> {
+        let Some(ref mut reader) = r else {
+            return Err(Error::MissingCompanionFile("gpu_resources"));
+        };
+        self.read_range(reader, self.gpu_data_offset, self.gpu_data_size, at, len, "gpu")
+    }
+
+    fn read_part(
+        &self,
+        r: &mut BufReader<File>,
+        offset: u64,
+        size: u32,
+        part: &'static str,
+    ) -> Result<Vec<u8>> {
+        if size == 0 {
+            return Err(Error::MissingPart { id: self.unk_id, part });
+        }
+        self.read_range(r, offset, size, 0, size as usize, part)
+    }
+
+    /// Reads part of a part, checking the range against the part's size before allocating for it
+    fn read_range(
+        &self,
+        r: &mut BufReader<File>,
+        offset: u64,
+        size: u32,
+        at: u64,
+        len: usize,
+        part: &'static str,
+    ) -> Result<Vec<u8>> {
+        if at.checked_add(len as u64).is_none_or(|end| end > size as u64) {
+            return Err(Error::TruncatedAsset { id: self.unk_id, part });
+        }
+        let mut buf = vec![0u8; len];
+        r.seek(SeekFrom::Start(offset + at))?;
+        if let Err(e) = r.read_exact(&mut buf) {
+            if e.kind() == std::io::ErrorKind::UnexpectedEof {
+                return Err(Error::TruncatedAsset { id: self.unk_id, part });
+            }
+            return Err(e.into());
         }
-        let mut buf = vec![0u8; self.data_size as usize];
-        r.seek(SeekFrom::Start(self.data_offset))?;
-        r.read_exact(&mut buf)?;
 
         Ok(buf)
     }
@@ -422,6 +637,24 @@ impl DataReaders {
         DataReaders(r, None, None)
     }
 
+    /// Opens a bundle along with its .stream and .gpu_resources files, if they exist
+    pub fn open(data_path: &str, bundle_id: &Id) -> Result<Self> {
+        let path = Path::new(data_path).join(bundle_id.to_string());
+        if !path.exists() {
+            return Err(Error::MissingBundle(path));
+        }
+        let mut readers = DataReaders::new(BufReader::new(File::open(&path)?));
+        let stream_path = path.with_extension("stream");
+        if stream_path.exists() {
+            readers.set_stream(BufReader::new(File::open(stream_path)?));
+        }
+        let gpu_path = path.with_extension("gpu_resources");
+        if gpu_path.exists() {
+            readers.set_gpu(BufReader::new(File::open(gpu_path)?));
+        }
+        Ok(readers)
+    }
+
     pub fn bundle(&mut self) -> &mut BufReader<File> {
         &mut self.0
     }
@@ -438,4 +671,42 @@ impl DataReaders {
     pub fn set_gpu(&mut self, r: BufReader<File>) {
         self.2 = Some(r);
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 16 byte file counting up from 0
+    fn reader(name: &str) -> BufReader<File> {
+        let path = std::env::temp_dir().join(format!("hd2-structs-{}-{}", std::process::id(), name));
+        std::fs::write(&path, (0..16).collect::<Vec<u8>>()).unwrap();
+        let file = File::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        BufReader::new(file)
+    }
+
+    fn header(offset: u64, size: u32) -> DataHeader {
+        DataHeader {
+            data_offset: offset,
+            data_size: size,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reads_parts_and_ranges() {
+        let mut r = reader("parts");
+        assert_eq!(header(4, 4).get_bundle_buf(&mut r).unwrap(), [4, 5, 6, 7]);
+        assert_eq!(header(4, 8).get_bundle_range(&mut r, 6, 2).unwrap(), [10, 11]);
+    }
+
+    #[test]
+    fn tells_missing_parts_from_truncated_ones() {
+        let mut r = reader("errors");
+        assert!(matches!(header(4, 0).get_bundle_buf(&mut r), Err(Error::MissingPart { part: "bundle", .. })));
+        assert!(matches!(header(4, 16).get_bundle_buf(&mut r), Err(Error::TruncatedAsset { .. })));
+        assert!(matches!(header(4, 8).get_bundle_range(&mut r, 6, 4), Err(Error::TruncatedAsset { .. })));
+        assert!(matches!(header(4, 8).get_bundle_range(&mut r, u64::MAX, 1), Err(Error::TruncatedAsset { .. })));
+        assert!(matches!(header(0, 4).get_gpu_buf(&mut None), Err(Error::MissingCompanionFile(_))));
+    }
+}
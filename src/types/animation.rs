@@ -0,0 +1,748 @@
+use std::collections::HashMap;
+
+use half::f16;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    error::{Error, Result},
+    gltf::Glb,
+    hash::hash32,
+    havok::{find_havok, read_havok, HavokFile, Value},
+    types::unit::{push_lod_mesh, push_skeleton, read_skeleton, read_unit, Skeleton},
+    DataHeader, DataReaders, DataTypes, Id, IdCache,
+};
+
+/// A decoded clip, with keys per bone in seconds
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Clip {
+    pub name: String,
+    pub duration: f32,
+    pub tracks: Vec<Track>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Track {
+    /// Node index in the unit, for clips that address bones by index
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bone: Option<usize>,
+    /// Bone name, for havok clips that carry one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bone_name: Option<String>,
+    pub translations: Vec<(f32, [f32; 3])>,
+    pub rotations: Vec<(f32, [f32; 4])>,
+    pub scales: Vec<(f32, [f32; 3])>,
+}
+
+impl Track {
+    fn is_empty(&self) -> bool {
+        self.translations.is_empty() && self.rotations.is_empty() && self.scales.is_empty()
+    }
+}
+
+/// Decodes an animation resource: either havok clips wrapped in a small header, or the engine's own
+/// keyframe stream
+pub fn read_animation(d: &DataHeader, r: &mut DataReaders) -> Result<Vec<Clip>> {
+    let buf = d.get_bundle_buf(r.bundle())?;
+    if let Some(havok) = find_havok(&buf) {
+        return havok_clips(&read_havok(havok)?);
+    }
+    let mut clip = read_native(&buf).map_err(|e| match e {
+        Error::Decode(what) => Error::Decode(format!("animation {}, {}", d.unk_id, what)),
+        e => e,
+    })?;
+    clip.name = d.unk_id.to_string();
+    Ok(vec![clip])
+}
+
+pub fn extract_animation(d: &DataHeader, r: &mut DataReaders) -> Result<(Vec<u8>, Option<String>)> {
+    let clips = read_animation(d, r)?;
+    Ok((serde_json::to_vec_pretty(&clips)?, None))
+}
+
+pub fn summarize_animation(d: &DataHeader, r: &mut DataReaders) -> Result<Vec<(&'static str, String)>> {
+    let clips = read_animation(d, r)?;
+    let mut summary = vec![("clips", clips.len().to_string())];
+    for clip in &clips {
+        summary.push((
+            "clip",
+            format!(
+                "{} {:.3}s, {} tracks, {} keys",
+                clip.name,
+                clip.duration,
+                clip.tracks.len(),
+                clip.tracks
+                    .iter()
+                    .map(|t| t.translations.len() + t.rotations.len() + t.scales.len())
+                    .sum::<usize>()
+            ),
+        ));
+    }
+    Ok(summary)
+}
+
+fn decode_err(what: impl std::fmt::Display) -> Error {
+    Error::Decode(what.to_string())
+}
+
+/// Little endian reads over a byte slice, bounds checked
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .buf
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| decode_err(format!("read past the end at {:#x}", self.pos)))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn f32(&mut self) -> Result<f32> {
+        Ok(f32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn f32s<const N: usize>(&mut self) -> Result<[f32; N]> {
+        let mut out = [0.0; N];
+        for o in &mut out {
+            *o = self.f32()?;
+        }
+        Ok(out)
+    }
+
+    fn half3(&mut self) -> Result<[f32; 3]> {
+        Ok([self.u16()?, self.u16()?, self.u16()?].map(|h| f16::from_bits(h).to_f32()))
+    }
+
+    fn align(&mut self, to: usize) {
+        self.pos = self.pos.next_multiple_of(to);
+    }
+}
+
+// Engine clips
+
+/// Smallest three quaternion in 32 bits: the index of the dropped largest component in the top two bits,
+/// then the other three at 10 bits each
+fn smallest_three(packed: u32) -> [f32; 4] {
+    let largest = (packed >> 30) as usize;
+    let mut small = [20, 10, 0].map(|shift| {
+        let q = (packed >> shift) & 0x3ff;
+        (q as f32 / 1023.0 * 2.0 - 1.0) * std::f32::consts::FRAC_1_SQRT_2
+    });
+    let w = (1.0 - small.iter().map(|c| c * c).sum::<f32>()).max(0.0).sqrt();
+    let mut out = [0.0; 4];
+    let mut rest = small.iter_mut();
+    for (i, o) in out.iter_mut().enumerate() {
+        *o = if i == largest { w } else { *rest.next().unwrap() };
+    }
+    out
+}
+
+// Keyframe kinds, in the top two bits of each key
+const KEY_POSITION: u16 = 0;
+const KEY_ROTATION: u16 = 1;
+const KEY_SCALE: u16 = 2;
+const KEY_RAW: u16 = 3;
+
+/// The engine's keyframe stream. After the header (unknown, bone count, length in seconds, data size and
+/// two tables of hashes) comes a bit per bone and channel saying whether its first key is compressed,
+/// those first keys, then keys of any bone in time order until the data size. Compressed keys hold
+/// positions and scales as halfs and rotations as `smallest_three`, raw keys hold floats.
+///
+/// There's no published description of this stream to follow. The layout was worked out by looking at
+/// resources and hasn't been checked by playing clips back, so animations are behind --experimental.
+fn read_native(buf: &[u8]) -> Result<Clip> {
+    let mut r = Reader { buf, pos: 0 };
+    let _unknown = r.u32()?;
+    let bone_count = r.u32()? as usize;
+    let duration = r.f32()?;
+    let size = r.u32()? as usize;
+    let hash_counts = [r.u32()? as usize, r.u32()? as usize];
+    if bone_count > 4096 || !duration.is_finite() || hash_counts.iter().any(|c| *c > buf.len() / 8) {
+        return Err(decode_err(format!("implausible header, {} bones and {}s long", bone_count, duration)));
+    }
+    r.bytes((hash_counts[0] + hash_counts[1]) * 8)?;
+    let _unknown = r.u16()?;
+
+    let flags = r.bytes((bone_count * 3).div_ceil(8))?.to_vec();
+    let compressed = |bone: usize, channel: usize| flags[(bone * 3 + channel) / 8] & (1 << ((bone * 3 + channel) % 8)) != 0;
+
+    let mut tracks: Vec<Track> = (0..bone_count)
+        .map(|bone| Track {
+            bone: Some(bone),
+            ..Default::default()
+        })
+        .collect();
+    for (bone, track) in tracks.iter_mut().enumerate() {
+        let position = if compressed(bone, 0) { r.half3()? } else { r.f32s()? };
+        let rotation = if compressed(bone, 1) { smallest_three(r.u32()?) } else { r.f32s()? };
+        let scale = if compressed(bone, 2) { r.half3()? } else { r.f32s()? };
+        track.translations.push((0.0, position));
+        track.rotations.push((0.0, rotation));
+        track.scales.push((0.0, scale));
+    }
+
+    // each key: kind, bone and the top of the time in ms in one u16, the rest of the time in the next.
+    // raw keys say which channel they're for in a third u16.
+    let end = size.min(buf.len());
+    while r.pos + 4 <= end {
+        let head = r.u16()?;
+        let kind = head >> 14;
+        let bone = ((head >> 4) & 0x3ff) as usize;
+        let time = ((((head & 0xf) as u32) << 16) | r.u16()? as u32) as f32 / 1000.0;
+        let Some(track) = tracks.get_mut(bone) else {
+            return Err(decode_err(format!("key at {:#x} for bone {} of {}", r.pos - 4, bone, bone_count)));
+        };
+        match kind {
+            KEY_POSITION => track.translations.push((time, r.half3()?)),
+            KEY_ROTATION => track.rotations.push((time, smallest_three(r.u32()?))),
+            KEY_SCALE => track.scales.push((time, r.half3()?)),
+            KEY_RAW => match r.u16()? {
+                0 => track.translations.push((time, r.f32s()?)),
+                1 => track.rotations.push((time, r.f32s()?)),
+                2 => track.scales.push((time, r.f32s()?)),
+                0xffff => break,
+                channel => return Err(decode_err(format!("raw key at {:#x} for channel {}", r.pos - 6, channel))),
+            },
+            _ => unreachable!("two bits"),
+        }
+    }
+
+    // glTF wants strictly increasing times, so a stream key at zero loses to the first key
+    for track in &mut tracks {
+        track.translations.sort_by(|a, b| a.0.total_cmp(&b.0));
+        track.translations.dedup_by(|a, b| a.0 == b.0);
+        track.rotations.sort_by(|a, b| a.0.total_cmp(&b.0));
+        track.rotations.dedup_by(|a, b| a.0 == b.0);
+        track.scales.sort_by(|a, b| a.0.total_cmp(&b.0));
+        track.scales.dedup_by(|a, b| a.0 == b.0);
+    }
+    Ok(Clip {
+        name: String::new(),
+        duration,
+        tracks,
+    })
+}
+
+// Havok clips
+
+type Transform = ([f32; 3], [f32; 4], [f32; 3]);
+
+fn number(v: &Value, name: &str) -> Result<f64> {
+    v.get(name)
+        .and_then(|v| v.as_f64())
+        .ok_or_else(|| decode_err(format!("havok animation has no {}", name)))
+}
+
+/// Every spline compressed or uncompressed animation in the file. Tracks get their bone name from the
+/// binding and skeleton when the file has them, and from the annotation tracks otherwise.
+fn havok_clips(havok: &HavokFile) -> Result<Vec<Clip>> {
+    let skeleton: Vec<String> = havok
+        .objects_of("hkaSkeleton")
+        .next()
+        .map(|s| {
+            let bones = s.value.get("bones").map(|b| b.as_array()).unwrap_or_default();
+            bones.iter().map(|b| b.get("name").and_then(|n| n.as_str()).unwrap_or_default().to_string()).collect()
+        })
+        .unwrap_or_default();
+    let mut bindings: HashMap<usize, Vec<usize>> = HashMap::new();
+    for binding in havok.objects_of("hkaAnimationBinding") {
+        if let Some(Value::Ref(animation)) = binding.value.get("animation") {
+            let indices = binding.value.get("transformTrackToBoneIndices").map(|b| b.as_array()).unwrap_or_default();
+            bindings.insert(*animation, indices.iter().filter_map(|i| i.as_usize()).collect());
+        }
+    }
+
+    let mut clips = Vec::new();
+    for object in &havok.objects {
+        let (frame_duration, frames) = match object.type_name.as_str() {
+            "hkaSplineCompressedAnimation" => decode_spline(&object.value)?,
+            "hkaInterleavedUncompressedAnimation" => decode_interleaved(&object.value)?,
+            _ => continue,
+        };
+        let annotations = object.value.get("annotationTracks").map(|a| a.as_array()).unwrap_or_default();
+        let track_count = frames.first().map(|f| f.len()).unwrap_or_default();
+        let mut tracks = Vec::with_capacity(track_count);
+        for index in 0..track_count {
+            let bone = bindings.get(&object.index).and_then(|b| b.get(index).copied()).unwrap_or(index);
+            let bone_name = skeleton.get(bone).cloned().or_else(|| {
+                annotations.get(index).and_then(|a| a.get("trackName")).and_then(|n| n.as_str()).map(String::from)
+            });
+            let mut track = Track {
+                bone: bone_name.is_none().then_some(bone),
+                bone_name,
+                ..Default::default()
+            };
+            for (frame, transforms) in frames.iter().enumerate() {
+                let time = frame as f32 * frame_duration;
+                let (translation, rotation, scale) = transforms[index];
+                track.translations.push((time, translation));
+                track.rotations.push((time, rotation));
+                track.scales.push((time, scale));
+            }
+            tracks.push(track);
+        }
+        clips.push(Clip {
+            name: format!("{}_{}", object.type_name, object.index),
+            duration: number(&object.value, "duration")? as f32,
+            tracks,
+        });
+    }
+    if clips.is_empty() {
+        return Err(decode_err("havok file has no animations we can decode"));
+    }
+    Ok(clips)
+}
+
+/// Whole transforms per frame, translation, rotation and scale as a vector4 each
+fn decode_interleaved(v: &Value) -> Result<(f32, Vec<Vec<Transform>>)> {
+    let tracks = number(v, "numberOfTransformTracks")? as usize;
+    let floats = v.get("transforms").map(|t| t.floats()).unwrap_or_default();
+    let stride = tracks * 12;
+    if stride == 0 {
+        return Ok((0.0, Vec::new()));
+    }
+    let frames: Vec<Vec<Transform>> = floats
+        .chunks_exact(stride)
+        .map(|frame| {
+            frame
+                .chunks_exact(12)
+                .map(|t| ([t[0], t[1], t[2]], [t[4], t[5], t[6], t[7]], [t[8], t[9], t[10]]))
+                .collect()
+        })
+        .collect();
+    let frame_duration = number(v, "duration")? as f32 / (frames.len().max(2) - 1) as f32;
+    Ok((frame_duration, frames))
+}
+
+enum Curve<const N: usize> {
+    Static([f32; N]),
+    Spline {
+        degree: usize,
+        knots: Vec<f32>,
+        points: Vec<[f32; N]>,
+    },
+}
+
+impl<const N: usize> Curve<N> {
+    fn at(&self, frame: f32) -> [f32; N] {
+        match self {
+            Curve::Static(v) => *v,
+            Curve::Spline { degree, knots, points } => evaluate(*degree, knots, points, frame),
+        }
+    }
+}
+
+/// Knot span holding `t`, by binary search like the reference B-spline algorithm
+fn knot_span(degree: usize, t: f32, count: usize, knots: &[f32]) -> usize {
+    if t >= knots[count] {
+        return count - 1;
+    }
+    let (mut low, mut high) = (degree, count);
+    let mut mid = (low + high) / 2;
+    for _ in 0..64 {
+        if t >= knots[mid] && t < knots[mid + 1] {
+            break;
+        }
+        if t < knots[mid] {
+            high = mid;
+        } else {
+            low = mid;
+        }
+        mid = (low + high) / 2;
+    }
+    mid
+}
+
+/// De Boor's basis functions for the span, applied to the control points
+fn evaluate<const N: usize>(degree: usize, knots: &[f32], points: &[[f32; N]], t: f32) -> [f32; N] {
+    let span = knot_span(degree, t, points.len(), knots);
+    let mut basis = vec![0.0f32; degree + 1];
+    basis[0] = 1.0;
+    for i in 1..=degree {
+        for j in (0..i).rev() {
+            let denom = knots[span + i - j] - knots[span - j];
+            let a = if denom == 0.0 { 0.0 } else { (t - knots[span - j]) / denom };
+            let tmp = basis[j] * a;
+            basis[j + 1] += basis[j] - tmp;
+            basis[j] = tmp;
+        }
+    }
+    let mut out = [0.0; N];
+    for (i, b) in basis.iter().enumerate() {
+        for (o, p) in out.iter_mut().zip(points[span - i]) {
+            *o += p * b;
+        }
+    }
+    out
+}
+
+/// Control point count, degree and knots of a spline. `evaluate` needs more control points than the
+/// degree and a knot per control point plus `degree + 1`.
+fn read_knots(r: &mut Reader) -> Result<(usize, usize, Vec<f32>)> {
+    let items = r.u16()? as usize;
+    let degree = r.u8()? as usize;
+    if degree == 0 || degree > 8 || items < degree {
+        return Err(decode_err(format!("spline of degree {} with {} control points at {:#x}", degree, items + 1, r.pos)));
+    }
+    let knots: Vec<f32> = r.bytes(items + degree + 2)?.iter().map(|k| *k as f32).collect();
+    if knots.len() != items + degree + 2 {
+        return Err(decode_err(format!("spline with {} knots at {:#x}", knots.len(), r.pos)));
+    }
+    Ok((items + 1, degree, knots))
+}
+
+/// Position or scale. The mask has a static flag per component in the low bits and a spline flag per
+/// component in bits 4 to 6. Components with neither keep `default`.
+fn read_vector(r: &mut Reader, mask: u8, quantization: u8, default: f32) -> Result<Curve<3>> {
+    let is_static = |c: usize| mask & (1 << c) != 0;
+    let is_spline = |c: usize| mask & (0x10 << c) != 0;
+    if mask & 0x70 == 0 {
+        let mut value = [default; 3];
+        for (c, v) in value.iter_mut().enumerate() {
+            if is_static(c) {
+                *v = r.f32()?;
+            }
+        }
+        return Ok(Curve::Static(value));
+    }
+
+    let (count, degree, knots) = read_knots(r)?;
+    r.align(4);
+    let mut ranges = [(default, default); 3];
+    for (c, range) in ranges.iter_mut().enumerate() {
+        if is_spline(c) {
+            *range = (r.f32()?, r.f32()?);
+        } else if is_static(c) {
+            let v = r.f32()?;
+            *range = (v, v);
+        }
+    }
+    let mut points = Vec::with_capacity(count);
+    for _ in 0..count {
+        let mut point = [0.0; 3];
+        for (c, p) in point.iter_mut().enumerate() {
+            let (min, max) = ranges[c];
+            *p = match (is_spline(c), quantization) {
+                (false, _) => min,
+                (true, 0) => min + (max - min) * (r.u8()? as f32 / 255.0),
+                (true, _) => min + (max - min) * (r.u16()? as f32 / 65535.0),
+            };
+        }
+        points.push(point);
+    }
+    Ok(Curve::Spline { degree, knots, points })
+}
+
+// Rotation quantizations
+const POLAR32: u8 = 0;
+const THREECOMP40: u8 = 1;
+const THREECOMP48: u8 = 2;
+const UNCOMPRESSED: u8 = 5;
+
+/// Three components and the index of the dropped one, which is rebuilt as the largest
+fn three_comp(components: [i32; 3], half_range: f32, dropped: usize, negative: bool) -> [f32; 4] {
+    let scale = std::f32::consts::FRAC_1_SQRT_2 / half_range;
+    let mut small = components.map(|c| c as f32 * scale).into_iter();
+    let sum = components.iter().map(|c| (*c as f32 * scale).powi(2)).sum::<f32>();
+    let w = (1.0 - sum).max(0.0).sqrt() * if negative { -1.0 } else { 1.0 };
+    let mut out = [0.0; 4];
+    for (i, o) in out.iter_mut().enumerate() {
+        *o = if i == dropped { w } else { small.next().unwrap() };
+    }
+    out
+}
+
+fn read_quaternion(r: &mut Reader, quantization: u8) -> Result<[f32; 4]> {
+    Ok(match quantization {
+        POLAR32 => {
+            let v = r.u32()?;
+            let radius = ((v >> 18) & 0x3ff) as f32 / 1023.0;
+            let radius = 1.0 - radius * radius;
+            let phi_theta = (v & 0x3ffff) as f32;
+            let mut phi = phi_theta.sqrt().floor();
+            let mut theta = 0.0;
+            if phi > 0.0 {
+                theta = std::f32::consts::FRAC_PI_4 * (phi_theta - phi * phi) / phi;
+                phi *= std::f32::consts::FRAC_PI_2 / 511.0;
+            }
+            let magnitude = (1.0 - radius * radius).max(0.0).sqrt();
+            let mut q = [
+                phi.sin() * theta.cos() * magnitude,
+                phi.sin() * theta.sin() * magnitude,
+                phi.cos() * magnitude,
+                radius,
+            ];
+            for (i, c) in q.iter_mut().enumerate() {
+                if v & (0x1000_0000 << i) != 0 {
+                    *c = -*c;
+                }
+            }
+            q
+        }
+        THREECOMP40 => {
+            let b = r.bytes(5)?;
+            let v = u64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], 0, 0, 0]);
+            let c = [0, 12, 24].map(|shift| ((v >> shift) & 0xfff) as i32 - 2047);
+            three_comp(c, 2047.0, ((v >> 36) & 3) as usize, (v >> 38) & 1 != 0)
+        }
+        THREECOMP48 => {
+            let [x, y, z] = [r.u16()?, r.u16()?, r.u16()?];
+            let c = [x, y, z].map(|v| (v & 0x7fff) as i32 - 16383);
+            let dropped = (((y >> 14) & 2) | ((x >> 15) & 1)) as usize;
+            three_comp(c, 16383.0, dropped, z >> 15 != 0)
+        }
+        UNCOMPRESSED => r.f32s()?,
+        q => return Err(Error::UnsupportedType(format!("havok rotation quantization {}", q))),
+    })
+}
+
+/// Rotations are static when any low mask bit is set, splines when any high bit is
+fn read_rotation(r: &mut Reader, mask: u8, quantization: u8) -> Result<Curve<4>> {
+    let align = match quantization {
+        POLAR32 | UNCOMPRESSED => 4,
+        THREECOMP48 => 2,
+        _ => 1,
+    };
+    if mask & 0xf0 != 0 {
+        let (count, degree, knots) = read_knots(r)?;
+        r.align(align);
+        let points = (0..count).map(|_| read_quaternion(r, quantization)).collect::<Result<_>>()?;
+        Ok(Curve::Spline { degree, knots, points })
+    } else if mask & 0x0f != 0 {
+        r.align(align);
+        Ok(Curve::Static(read_quaternion(r, quantization)?))
+    } else {
+        Ok(Curve::Static([0.0, 0.0, 0.0, 1.0]))
+    }
+}
+
+/// Spline compressed animations are split into blocks of up to `maxFramesPerBlock` frames. A block has
+/// a four byte mask per transform track and one per float track, then each track's position, rotation
+/// and scale, each 4 byte aligned. Knots are frame numbers within the block.
+fn decode_spline(v: &Value) -> Result<(f32, Vec<Vec<Transform>>)> {
+    let tracks = number(v, "numberOfTransformTracks")? as usize;
+    let float_tracks = number(v, "numberOfFloatTracks")? as usize;
+    let frame_count = number(v, "numFrames")? as usize;
+    let frames_per_block = number(v, "maxFramesPerBlock")? as usize;
+    let frame_duration = number(v, "frameDuration")? as f32;
+    let block_offsets: Vec<usize> = v
+        .get("blockOffsets")
+        .map(|b| b.as_array().iter().filter_map(|o| o.as_usize()).collect())
+        .unwrap_or_default();
+    let data = v
+        .get("data")
+        .and_then(|d| d.as_bytes())
+        .ok_or_else(|| decode_err("spline animation has no data"))?;
+    if frames_per_block < 2 || block_offsets.is_empty() {
+        return Err(decode_err(format!("spline animation with {} frames per block", frames_per_block)));
+    }
+
+    let mut frames = Vec::with_capacity(frame_count);
+    for (block, offset) in block_offsets.iter().enumerate() {
+        let mut r = Reader { buf: &data, pos: *offset };
+        let masks = r.bytes(tracks * 4)?.to_vec();
+        r.bytes(float_tracks)?;
+        r.align(4);
+
+        let mut curves = Vec::with_capacity(tracks);
+        for mask in masks.chunks_exact(4) {
+            let (quantization, position, rotation, scale) = (mask[0], mask[1], mask[2], mask[3]);
+            let p = read_vector(&mut r, position, quantization & 3, 0.0)?;
+            r.align(4);
+            let q = read_rotation(&mut r, rotation, (quantization >> 2) & 0xf)?;
+            r.align(4);
+            let s = read_vector(&mut r, scale, (quantization >> 6) & 3, 1.0)?;
+            r.align(4);
+            curves.push((p, q, s));
+        }
+
+        // blocks share their boundary frame, the next block starts on the last frame of this one
+        let first = block * (frames_per_block - 1);
+        let last = match block + 1 == block_offsets.len() {
+            true => frame_count,
+            false => (first + frames_per_block - 1).min(frame_count),
+        };
+        for frame in first..last {
+            let local = (frame - first) as f32;
+            frames.push(
+                curves
+                    .iter()
+                    .map(|(p, q, s)| {
+                        let rotation = q.at(local);
+                        let length = rotation.iter().map(|c| c * c).sum::<f32>().sqrt();
+                        let rotation = if length > 0.0 { rotation.map(|c| c / length) } else { [0.0, 0.0, 0.0, 1.0] };
+                        (p.at(local), rotation, s.at(local))
+                    })
+                    .collect(),
+            );
+        }
+    }
+    Ok((frame_duration, frames))
+}
+
+// glTF
+
+/// Adds a clip as an animation on the skeleton's joint nodes, returning how many tracks found no bone.
+/// Havok tracks are matched to nodes by name hash.
+pub fn push_clip(glb: &mut Glb, clip: &Clip, skeleton: &Skeleton, joints: &[usize]) -> usize {
+    let mut channels = Vec::new();
+    let mut samplers = Vec::new();
+    let mut unmatched = 0;
+    for track in clip.tracks.iter().filter(|t| !t.is_empty()) {
+        let bone = match (&track.bone_name, track.bone) {
+            (Some(name), _) => skeleton.nodes.iter().position(|n| n.name_hash == hash32(name)),
+            (None, bone) => bone.filter(|b| *b < joints.len()),
+        };
+        let Some(bone) = bone else {
+            unmatched += 1;
+            continue;
+        };
+        for (path, times, output) in [
+            ("translation", track.translations.iter().map(|k| k.0).collect::<Vec<_>>(), {
+                let values: Vec<[f32; 3]> = track.translations.iter().map(|k| k.1).collect();
+                (!values.is_empty()).then(|| glb.push_keys(&values))
+            }),
+            ("rotation", track.rotations.iter().map(|k| k.0).collect(), {
+                let values: Vec<[f32; 4]> = track.rotations.iter().map(|k| k.1).collect();
+                (!values.is_empty()).then(|| glb.push_keys(&values))
+            }),
+            ("scale", track.scales.iter().map(|k| k.0).collect(), {
+                let values: Vec<[f32; 3]> = track.scales.iter().map(|k| k.1).collect();
+                (!values.is_empty()).then(|| glb.push_keys(&values))
+            }),
+        ] {
+            let Some(output) = output else {
+                continue;
+            };
+            samplers.push(json!({
+                "input": glb.push_times(&times),
+                "output": output,
+                "interpolation": "LINEAR",
+            }));
+            channels.push(json!({
+                "sampler": samplers.len() - 1,
+                "target": { "node": joints[bone], "path": path },
+            }));
+        }
+    }
+    if !channels.is_empty() {
+        glb.push_animation(json!({
+            "name": clip.name,
+            "channels": channels,
+            "samplers": samplers,
+        }));
+    }
+    unmatched
+}
+
+/// Builds a glTF with the unit's skeleton as joint nodes, its first lod and an animation per clip. The
+/// mesh isn't skinned, vertex weights aren't decoded yet.
+pub fn animated_unit_to_glb(cache: &IdCache, data_path: &str, unit: Id, clips: &[Clip]) -> Result<Vec<u8>> {
+    let (bundle, h) = cache.get_by_id(unit, Some(DataTypes::Unit), Id::invalid())?;
+    let mut r = DataReaders::open(data_path, &bundle)?;
+    let d = DataHeader::from(h);
+    let skeleton = read_skeleton(&d, &mut r)?;
+
+    let names = match cache.get_by_id(skeleton.bones, Some(DataTypes::Bones), Id::invalid()) {
+        Ok((bones_bundle, h)) => DataReaders::open(data_path, &bones_bundle)
+            .and_then(|mut r| crate::types::bones::read_bones(&DataHeader::from(h), &mut r))
+            .map(|b| b.by_hash())
+            .inspect_err(|e| eprintln!("Failed to read bones {}: {}", skeleton.bones, e))
+            .unwrap_or_default(),
+        Err(_) => {
+            eprintln!("Bones {} not in cache, bones keep their hashes as names.", skeleton.bones);
+            HashMap::new()
+        }
+    };
+
+    let mut glb = Glb::default();
+    let (joints, mut children) = push_skeleton(&mut glb, &skeleton, &names);
+    match read_unit(&d, &mut r) {
+        Ok(lods) => match lods.first().and_then(|lod| push_lod_mesh(&mut glb, &unit.to_string(), lod)) {
+            Some(mesh) => children.push(glb.push_node(json!({ "name": unit.to_string(), "mesh": mesh }))),
+            None => eprintln!("Unit {} has no faces in its first lod, exporting the skeleton only.", unit),
+        },
+        Err(e) => eprintln!("Failed to read the mesh of unit {}: {}", unit, e),
+    }
+    for clip in clips {
+        let unmatched = push_clip(&mut glb, clip, &skeleton, &joints);
+        if unmatched > 0 {
+            eprintln!("{} of {} tracks of {} match no bone of the unit.", unmatched, clip.tracks.len(), clip.name);
+        }
+    }
+
+    // the engine is z up, gltf is y up
+    let root = glb.push_node(json!({
+        "name": unit.to_string(),
+        "rotation": [-std::f32::consts::FRAC_1_SQRT_2, 0.0, 0.0, std::f32::consts::FRAC_1_SQRT_2],
+        "children": children,
+    }));
+    glb.scene.push(root);
+    glb.to_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn knots(bytes: &[u8]) -> Result<(usize, usize, Vec<f32>)> {
+        read_knots(&mut Reader { buf: bytes, pos: 0 })
+    }
+
+    #[test]
+    fn reads_knots() {
+        // 2 items + 1 control points of degree 2, 6 knots
+        let (count, degree, knots) = knots(&[2, 0, 2, 0, 0, 0, 4, 4, 4]).unwrap();
+        assert_eq!((count, degree), (3, 2));
+        assert_eq!(knots, [0.0, 0.0, 0.0, 4.0, 4.0, 4.0]);
+    }
+
+    #[test]
+    fn rejects_splines_evaluate_would_overrun() {
+        // fewer control points than the degree needs
+        assert!(knots(&[1, 0, 3, 0, 0, 0, 0, 4, 4]).is_err());
+        assert!(knots(&[0, 0, 1, 0, 0]).is_err());
+        assert!(knots(&[4, 0, 0, 0, 0, 0, 0, 0, 0]).is_err());
+        assert!(knots(&[4, 0, 9, 0, 0, 0, 0, 0, 0]).is_err());
+        // knots missing
+        assert!(knots(&[2, 0, 2, 0, 0, 0, 4, 4]).is_err());
+    }
+
+    #[test]
+    fn evaluates_splines() {
+        let linear = Curve::Spline {
+            degree: 1,
+            knots: vec![0.0, 0.0, 4.0, 4.0],
+            points: vec![[0.0], [8.0]],
+        };
+        assert_eq!(linear.at(0.0), [0.0]);
+        assert_eq!(linear.at(1.0), [2.0]);
+        assert_eq!(linear.at(4.0), [8.0]);
+
+        let (_, degree, knots) = knots(&[2, 0, 2, 0, 0, 0, 4, 4, 4]).unwrap();
+        let quadratic = Curve::Spline {
+            degree,
+            knots,
+            points: vec![[0.0, 1.0], [4.0, 1.0], [4.0, 1.0]],
+        };
+        assert_eq!(quadratic.at(0.0), [0.0, 1.0]);
+        assert_eq!(quadratic.at(2.0), [3.0, 1.0]);
+        assert_eq!(quadratic.at(4.0), [4.0, 1.0]);
+    }
+}
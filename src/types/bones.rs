@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::{
+    error::{Error, Result},
+    hash::hash32,
+    DataHeader, DataReaders,
+};
+
+/// Bone names of a skeleton, which units only refer to by 32 bit hash
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Bones {
+    pub names: Vec<String>,
+    /// Bone counts of each lod, the lower lods only animating the first bones
+    pub lods: Vec<u32>,
+}
+
+impl Bones {
+    /// Bone names by hash, for looking up unit nodes
+    pub fn by_hash(&self) -> HashMap<u32, String> {
+        self.names.iter().map(|n| (hash32(n), n.clone())).collect()
+    }
+}
+
+/// Bone count, lod count, a hash per bone, the lod bone counts, then the names null terminated
+pub fn read_bones(d: &DataHeader, r: &mut DataReaders) -> Result<Bones> {
+    let buf = d.get_bundle_buf(r.bundle())?;
+    let u32_at = |at: usize| buf.get(at..at + 4).map(|b| u32::from_le_bytes(b.try_into().unwrap()));
+    let (Some(count), Some(lod_count)) = (u32_at(0), u32_at(4)) else {
+        return Err(Error::Decode(format!("bones {}, header runs past the end", d.unk_id)));
+    };
+    let names_at = 8 + (count as usize + lod_count as usize) * 4;
+    if names_at > buf.len() {
+        return Err(Error::Decode(format!("bones {}, {} bones don't fit in {} bytes", d.unk_id, count, buf.len())));
+    }
+    let lods = (0..lod_count as usize).filter_map(|i| u32_at(8 + (count as usize + i) * 4)).collect();
+    let names: Vec<String> = buf[names_at..]
+        .split(|b| *b == 0)
+        .take(count as usize)
+        .map(|n| String::from_utf8_lossy(n).into_owned())
+        .collect();
+
+    // the hashes are there to check the names against
+    for (i, name) in names.iter().enumerate() {
+        if u32_at(8 + i * 4) != Some(hash32(name)) {
+            return Err(Error::Decode(format!("bones {}, name {:?} doesn't match its hash", d.unk_id, name)));
+        }
+    }
+    Ok(Bones { names, lods })
+}
+
+pub fn extract_bones(d: &DataHeader, r: &mut DataReaders) -> Result<(Vec<u8>, Option<String>)> {
+    let bones = read_bones(d, r)?;
+    Ok((serde_json::to_vec_pretty(&bones)?, None))
+}
+
+pub fn summarize_bones(d: &DataHeader, r: &mut DataReaders) -> Result<Vec<(&'static str, String)>> {
+    let bones = read_bones(d, r)?;
+    Ok(vec![
+        ("bones", bones.names.len().to_string()),
+        ("lods", format!("{:?}", bones.lods)),
+    ])
+}
@@ -0,0 +1,643 @@
+use std::{collections::HashMap, collections::HashSet, io::Write};
+
+use serde_json::json;
+
+use crate::{
+    error::Result,
+    gltf::Glb,
+    havok::{HavokFile, Value},
+    types::{
+        physics::read_physics,
+        unit::{push_lod_mesh, read_unit},
+    },
+    DataHeader, DataReaders, DataTypes, Id, IdCache,
+};
+
+// Shapes nest through list, bv tree and transform shapes
+const MAX_DEPTH: usize = 16;
+const SEGMENTS: usize = 12;
+
+/// A collision shape turned into triangles, in the space of the unit
+#[derive(Debug, Default, Clone)]
+pub struct CollisionMesh {
+    /// Shape class and object index, e.g. hkpBoxShape#12
+    pub name: String,
+    /// Name of the rigid body the shape belongs to, if it has one
+    pub body: Option<String>,
+    pub vertices: Vec<[f32; 3]>,
+    pub triangles: Vec<[u32; 3]>,
+}
+
+/// Rows of a 3x4 affine transform, the last column being the translation
+type Affine = [[f32; 4]; 3];
+
+const IDENTITY: Affine = [[1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0]];
+
+fn mul(a: &Affine, b: &Affine) -> Affine {
+    let mut m = [[0.0; 4]; 3];
+    for i in 0..3 {
+        for j in 0..4 {
+            m[i][j] = (0..3).map(|k| a[i][k] * b[k][j]).sum::<f32>() + if j == 3 { a[i][3] } else { 0.0 };
+        }
+    }
+    m
+}
+
+fn apply(a: &Affine, p: [f32; 3]) -> [f32; 3] {
+    std::array::from_fn(|i| a[i][0] * p[0] + a[i][1] * p[1] + a[i][2] * p[2] + a[i][3])
+}
+
+fn from_quat(q: &[f32], t: &[f32], s: &[f32]) -> Affine {
+    let (x, y, z, w) = (q[0], q[1], q[2], q[3]);
+    let r = [
+        [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - z * w), 2.0 * (x * z + y * w)],
+        [2.0 * (x * y + z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - x * w)],
+        [2.0 * (x * z - y * w), 2.0 * (y * z + x * w), 1.0 - 2.0 * (x * x + y * y)],
+    ];
+    std::array::from_fn(|i| [r[i][0] * s[0], r[i][1] * s[1], r[i][2] * s[2], t[i]])
+}
+
+/// hkTransform is three rotation columns and a translation, hkQsTransform a translation, quaternion
+/// and scale. Both pad every vector to four floats.
+fn read_transform(v: Option<&Value>) -> Affine {
+    let f = v.map(|v| v.floats()).unwrap_or_default();
+    match f.len() {
+        16 => std::array::from_fn(|i| [f[i], f[4 + i], f[8 + i], f[12 + i]]),
+        12 => from_quat(&f[4..8], &f[0..3], &f[8..11]),
+        _ => IDENTITY,
+    }
+}
+
+fn vec3(v: Option<&Value>) -> Option<[f32; 3]> {
+    let f = v?.floats();
+    (f.len() >= 3).then(|| [f[0], f[1], f[2]])
+}
+
+fn float(v: &Value, names: &[&str]) -> Option<f32> {
+    names.iter().find_map(|n| v.get(n)?.as_f64()).map(|f| f as f32)
+}
+
+/// The shape a member points to, through the single shape containers some classes wrap it in
+fn shape_ref(v: Option<&Value>) -> Option<usize> {
+    match v? {
+        Value::Ref(index) => Some(*index),
+        v @ Value::Object(_) => shape_ref(v.get("childShape")),
+        _ => None,
+    }
+}
+
+fn refs(v: &Value, out: &mut Vec<usize>) {
+    match v {
+        Value::Ref(index) => out.push(*index),
+        Value::Array(values) => values.iter().for_each(|v| refs(v, out)),
+        Value::Object(members) => members.iter().for_each(|(_, v)| refs(v, out)),
+        _ => {}
+    }
+}
+
+/// Vectors are stored padded to four floats
+fn points(v: Option<&Value>) -> Vec<[f32; 3]> {
+    v.map(|v| v.floats()).unwrap_or_default().chunks_exact(4).map(|c| [c[0], c[1], c[2]]).collect()
+}
+
+fn indices(v: Option<&Value>, stride: usize) -> Vec<[u32; 3]> {
+    let all: Vec<u32> = v.map(|v| v.floats()).unwrap_or_default().iter().map(|f| *f as u32).collect();
+    all.chunks_exact(stride).map(|c| [c[0], c[1], c[2]]).collect()
+}
+
+struct Collector<'a> {
+    havok: &'a HavokFile,
+    meshes: Vec<CollisionMesh>,
+    /// Shape classes we can't turn into triangles, and how often they came up
+    skipped: HashMap<String, usize>,
+}
+
+impl Collector<'_> {
+    fn shape(&mut self, index: usize, xf: &Affine, body: Option<&str>, depth: usize) {
+        let Some(object) = self.havok.object(index) else {
+            return;
+        };
+        if depth > MAX_DEPTH {
+            return;
+        }
+        let v = &object.value;
+        let name = format!("{}#{}", object.type_name, index);
+        let mut mesh = |vertices: Vec<[f32; 3]>, triangles: Vec<[u32; 3]>| {
+            // indices read from the file can point past the vertices, those triangles are dropped whole
+            let triangles: Vec<[u32; 3]> =
+                triangles.into_iter().filter(|t| t.iter().all(|i| (*i as usize) < vertices.len())).collect();
+            if !triangles.is_empty() {
+                self.meshes.push(CollisionMesh {
+                    name: name.clone(),
+                    body: body.map(str::to_string),
+                    vertices: vertices.into_iter().map(|p| apply(xf, p)).collect(),
+                    triangles,
+                });
+            }
+        };
+        match object.type_name.as_str() {
+            "hkpBoxShape" => {
+                let half = vec3(v.get("halfExtents")).unwrap_or_default();
+                let (vertices, triangles) = cuboid(half);
+                mesh(vertices, triangles);
+            }
+            "hkpSphereShape" | "hknpSphereShape" => {
+                let radius = float(v, &["radius", "convexRadius"]).unwrap_or_default();
+                let (vertices, triangles) = capsule([0.0; 3], [0.0; 3], radius);
+                mesh(vertices, triangles);
+            }
+            "hkpCapsuleShape" | "hknpCapsuleShape" => {
+                let a = vec3(v.get("vertexA").or(v.get("a"))).unwrap_or_default();
+                let b = vec3(v.get("vertexB").or(v.get("b"))).unwrap_or_default();
+                let radius = float(v, &["radius", "convexRadius"]).unwrap_or_default();
+                let (vertices, triangles) = capsule(a, b, radius);
+                mesh(vertices, triangles);
+            }
+            "hkpConvexVerticesShape" => {
+                // blocks of four points, transposed to four xs, four ys and four zs
+                let f = v.get("rotatedVertices").map(|v| v.floats()).unwrap_or_default();
+                let mut vertices: Vec<[f32; 3]> =
+                    f.chunks_exact(12).flat_map(|c| (0..4).map(|i| [c[i], c[4 + i], c[8 + i]])).collect();
+                if let Some(count) = v.get("numVertices").and_then(Value::as_usize) {
+                    vertices.truncate(count);
+                }
+                let triangles = convex_hull(&vertices);
+                mesh(vertices, triangles);
+            }
+            "hkpSimpleMeshShape" => {
+                let vertices = points(v.get("vertices"));
+                let index = |t: &Value, name: &str| t.get(name)?.as_usize().and_then(|i| u32::try_from(i).ok());
+                let triangles = v
+                    .get("triangles")
+                    .map(Value::as_array)
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|t| Some([index(t, "a")?, index(t, "b")?, index(t, "c")?]))
+                    .collect();
+                mesh(vertices, triangles);
+            }
+            "hkpStorageExtendedMeshShapeMeshSubpartStorage" => {
+                // 16 bit triangles are padded to four indices, 32 bit ones aren't
+                let vertices = points(v.get("vertices"));
+                let mut triangles = indices(v.get("indices16"), 4);
+                triangles.extend(indices(v.get("indices32"), 3));
+                mesh(vertices, triangles);
+            }
+            "hkpStorageExtendedMeshShape" => {
+                let mut children = Vec::new();
+                refs(v.get("meshstorage").unwrap_or(&Value::Null), &mut children);
+                for child in children {
+                    self.shape(child, xf, body, depth + 1);
+                }
+            }
+            "hkpListShape" => {
+                let children: Vec<usize> = v
+                    .get("childInfo")
+                    .map(Value::as_array)
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|c| shape_ref(c.get("shape")))
+                    .collect();
+                for child in children {
+                    self.shape(child, xf, body, depth + 1);
+                }
+            }
+            "hkpMoppBvTreeShape" | "hkpBvTreeShape" => {
+                if let Some(child) = shape_ref(v.get("child")) {
+                    self.shape(child, xf, body, depth + 1);
+                }
+            }
+            "hkpTransformShape" | "hkpConvexTransformShape" => {
+                if let Some(child) = shape_ref(v.get("childShape")) {
+                    self.shape(child, &mul(xf, &read_transform(v.get("transform"))), body, depth + 1);
+                }
+            }
+            "hkpConvexTranslateShape" => {
+                if let Some(child) = shape_ref(v.get("childShape")) {
+                    let t = vec3(v.get("translation")).unwrap_or_default();
+                    let mut local = IDENTITY;
+                    (0..3).for_each(|i| local[i][3] = t[i]);
+                    self.shape(child, &mul(xf, &local), body, depth + 1);
+                }
+            }
+            // newer shapes with their points reflected as a plain array get a hull
+            _ if v.get("vertices").is_some() && v.get("triangles").is_none() => {
+                let vertices = points(v.get("vertices"));
+                let triangles = convex_hull(&vertices);
+                if triangles.is_empty() {
+                    *self.skipped.entry(object.type_name.clone()).or_default() += 1;
+                }
+                mesh(vertices, triangles);
+            }
+            _ => *self.skipped.entry(object.type_name.clone()).or_default() += 1,
+        }
+    }
+}
+
+/// Collects the collision shapes of a physics resource. Shapes are placed by the rigid bodies using
+/// them; without any bodies, the shapes no other shape contains are taken as they are.
+pub fn collision_meshes(havok: &HavokFile) -> (Vec<CollisionMesh>, HashMap<String, usize>) {
+    let mut c = Collector {
+        havok,
+        meshes: Vec::new(),
+        skipped: HashMap::new(),
+    };
+    let mut bodies = 0;
+    for o in havok.objects_of("hkpRigidBody") {
+        let shape = o.value.get("collidable").and_then(|c| shape_ref(c.get("shape")));
+        let transform = o.value.get("motion").and_then(|m| m.get("motionState")).and_then(|m| m.get("transform"));
+        if let Some(shape) = shape {
+            c.shape(shape, &read_transform(transform), o.value.get("name").and_then(Value::as_str), 0);
+            bodies += 1;
+        }
+    }
+    for o in havok.objects_of("hknpPhysicsSystemData") {
+        for b in o.value.get("bodyCinfos").map(Value::as_array).unwrap_or_default() {
+            let (Some(shape), Some(t), Some(q)) = (shape_ref(b.get("shape")), vec3(b.get("position")), b.get("orientation"))
+            else {
+                continue;
+            };
+            let q = q.floats();
+            let xf = if q.len() == 4 { from_quat(&q, &t, &[1.0; 3]) } else { IDENTITY };
+            c.shape(shape, &xf, b.get("name").and_then(Value::as_str), 0);
+            bodies += 1;
+        }
+    }
+
+    if bodies == 0 {
+        let is_shape = |name: &str| name.ends_with("Shape") || name.ends_with("SubpartStorage");
+        let mut contained = HashSet::new();
+        for o in havok.objects.iter().filter(|o| is_shape(&o.type_name)) {
+            let mut children = Vec::new();
+            refs(&o.value, &mut children);
+            contained.extend(children);
+        }
+        let roots: Vec<usize> = havok
+            .objects
+            .iter()
+            .filter(|o| is_shape(&o.type_name) && !contained.contains(&o.index))
+            .map(|o| o.index)
+            .collect();
+        for index in roots {
+            c.shape(index, &IDENTITY, None, 0);
+        }
+    }
+    (c.meshes, c.skipped)
+}
+
+fn cuboid(h: [f32; 3]) -> (Vec<[f32; 3]>, Vec<[u32; 3]>) {
+    let vertices = (0..8)
+        .map(|i| std::array::from_fn(|axis| if i >> axis & 1 == 1 { h[axis] } else { -h[axis] }))
+        .collect();
+    // two triangles per face, wound outwards
+    let triangles = vec![
+        [0, 2, 1], [1, 2, 3], [4, 5, 6], [5, 7, 6],
+        [0, 1, 4], [1, 5, 4], [2, 6, 3], [3, 6, 7],
+        [0, 4, 2], [2, 4, 6], [1, 3, 5], [3, 7, 5],
+    ];
+    (vertices, triangles)
+}
+
+/// A capsule around the segment from a to b, which is a sphere when they're the same point. Rings of
+/// the top half are moved to b and those of the bottom half to a.
+fn capsule(a: [f32; 3], b: [f32; 3], radius: f32) -> (Vec<[f32; 3]>, Vec<[u32; 3]>) {
+    let axis = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let length = axis.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let z = if length > 1e-6 { axis.map(|x| x / length) } else { [0.0, 0.0, 1.0] };
+    let helper = if z[0].abs() < 0.9 { [1.0, 0.0, 0.0] } else { [0.0, 1.0, 0.0] };
+    let x = normalize(cross(helper, z));
+    let y = cross(z, x);
+
+    let quarter = SEGMENTS / 4;
+    let mut rings = Vec::new();
+    for i in 0..=quarter {
+        rings.push((i as f32 / quarter as f32 * std::f32::consts::FRAC_PI_2, b));
+    }
+    for i in quarter..=quarter * 2 {
+        rings.push((i as f32 / quarter as f32 * std::f32::consts::FRAC_PI_2, a));
+    }
+
+    let mut vertices = Vec::new();
+    for (phi, center) in &rings {
+        for j in 0..SEGMENTS {
+            let theta = j as f32 / SEGMENTS as f32 * std::f32::consts::TAU;
+            let (s, c) = (phi.sin() * radius, phi.cos() * radius);
+            let (u, v) = (theta.cos() * s, theta.sin() * s);
+            vertices.push(std::array::from_fn(|k| center[k] + x[k] * u + y[k] * v + z[k] * c));
+        }
+    }
+    let mut triangles = Vec::new();
+    for i in 0..rings.len() as u32 - 1 {
+        for j in 0..SEGMENTS as u32 {
+            let n = SEGMENTS as u32;
+            let (p, q) = (i * n + j, i * n + (j + 1) % n);
+            triangles.push([p, p + n, q]);
+            triangles.push([q, p + n, q + n]);
+        }
+    }
+    (vertices, triangles)
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn normalize(a: [f32; 3]) -> [f32; 3] {
+    let l = dot(a, a).sqrt();
+    if l > 0.0 {
+        a.map(|x| x / l)
+    } else {
+        a
+    }
+}
+
+/// Triangulates the hull of a point cloud, adding points one at a time and replacing the faces they
+/// can see. Havok only stores the points of convex shapes, not their faces.
+fn convex_hull(p: &[[f32; 3]]) -> Vec<[u32; 3]> {
+    if p.len() < 4 {
+        return Vec::new();
+    }
+    let extent = p.iter().flat_map(|v| v.iter()).fold(0.0f32, |m, x| m.max(x.abs()));
+    let eps = extent.max(1e-3) * 1e-5;
+    let normal = |f: &[usize; 3]| cross(sub(p[f[1]], p[f[0]]), sub(p[f[2]], p[f[0]]));
+    let farthest = |score: &dyn Fn(usize) -> f32| (0..p.len()).max_by(|a, b| score(*a).total_cmp(&score(*b))).unwrap();
+
+    // start from a tetrahedron as large as we can cheaply find
+    let i0 = 0;
+    let i1 = farthest(&|i| dot(sub(p[i], p[i0]), sub(p[i], p[i0])));
+    let i2 = farthest(&|i| {
+        let c = cross(sub(p[i1], p[i0]), sub(p[i], p[i0]));
+        dot(c, c)
+    });
+    let n = normal(&[i0, i1, i2]);
+    let i3 = farthest(&|i| dot(n, sub(p[i], p[i0])).abs());
+    if dot(n, sub(p[i3], p[i0])).abs() <= eps * dot(n, n).sqrt() {
+        // flat, so there's no volume to triangulate
+        return Vec::new();
+    }
+    let mut faces: Vec<[usize; 3]> = Vec::new();
+    for (f, other) in [([i0, i1, i2], i3), ([i0, i1, i3], i2), ([i0, i2, i3], i1), ([i1, i2, i3], i0)] {
+        let outwards = dot(normal(&f), sub(p[other], p[f[0]])) < 0.0;
+        faces.push(if outwards { f } else { [f[0], f[2], f[1]] });
+    }
+
+    for i in 0..p.len() {
+        if [i0, i1, i2, i3].contains(&i) {
+            continue;
+        }
+        let sees = |f: &[usize; 3]| {
+            let n = normal(f);
+            dot(n, sub(p[i], p[f[0]])) > eps * dot(n, n).sqrt()
+        };
+        let (visible, kept): (Vec<[usize; 3]>, Vec<[usize; 3]>) = faces.into_iter().partition(|f| sees(f));
+        faces = kept;
+        if visible.is_empty() {
+            continue;
+        }
+        // the horizon is the edges of visible faces whose other face isn't visible
+        let edges: HashSet<(usize, usize)> = visible.iter().flat_map(|f| [(f[0], f[1]), (f[1], f[2]), (f[2], f[0])]).collect();
+        for &(a, b) in &edges {
+            if !edges.contains(&(b, a)) {
+                faces.push([a, b, i]);
+            }
+        }
+    }
+    faces.into_iter().map(|f| f.map(|i| i as u32)).collect()
+}
+
+/// The physics resource a unit's actors use has the unit's name, and its shapes are in the unit's space.
+/// Actor definitions and physics_properties aren't read, so every shape in the resource is exported.
+fn unit_collision(cache: &IdCache, data_path: &str, unit: Id) -> Result<(Vec<CollisionMesh>, HashMap<String, usize>)> {
+    let (bundle, h) = cache.get_by_id(unit, Some(DataTypes::Physics), Id::invalid())?;
+    let mut r = DataReaders::open(data_path, &bundle)?;
+    let havok = read_physics(&DataHeader::from(h), &mut r)?;
+    Ok(collision_meshes(&havok))
+}
+
+fn report_skipped(unit: Id, skipped: &HashMap<String, usize>) {
+    for (class, count) in skipped {
+        eprintln!("Skipped {} {} shapes of unit {}, they can't be turned into triangles yet.", count, class, unit);
+    }
+}
+
+/// Lod 0 of the unit, if it can be read, to check the collision against
+fn visual_mesh(cache: &IdCache, data_path: &str, unit: Id) -> Option<crate::types::unit::UnitLod> {
+    let read = || -> Result<_> {
+        let (bundle, h) = cache.get_by_id(unit, Some(DataTypes::Unit), Id::invalid())?;
+        let mut r = DataReaders::open(data_path, &bundle)?;
+        Ok(read_unit(&DataHeader::from(h), &mut r)?.into_iter().next())
+    };
+    read().inspect_err(|e| eprintln!("Failed to read the mesh of unit {}: {}", unit, e)).ok().flatten()
+}
+
+/// Builds a glTF with the unit's first lod and a mesh per collision shape, under one z up root
+pub fn collision_to_glb(cache: &IdCache, data_path: &str, unit: Id) -> Result<(Vec<u8>, usize)> {
+    let (meshes, skipped) = unit_collision(cache, data_path, unit)?;
+    report_skipped(unit, &skipped);
+
+    let mut glb = Glb::default();
+    let mut children = Vec::new();
+    let lod = visual_mesh(cache, data_path, unit);
+    if let Some(mesh) = lod.and_then(|lod| push_lod_mesh(&mut glb, &unit.to_string(), &lod)) {
+        children.push(glb.push_node(json!({ "name": unit.to_string(), "mesh": mesh })));
+    }
+    for m in &meshes {
+        let position = glb.push_vec3(&m.vertices, true);
+        let indices: Vec<u32> = m.triangles.iter().flatten().copied().collect();
+        let primitive = json!({ "attributes": { "POSITION": position }, "indices": glb.push_indices(&indices) });
+        let mesh = glb.push_mesh(json!({ "name": m.name, "primitives": [primitive] }));
+        let mut node = json!({ "name": m.name, "mesh": mesh });
+        if let Some(body) = &m.body {
+            node["extras"] = json!({ "body": body });
+        }
+        children.push(glb.push_node(node));
+    }
+
+    // the engine is z up, gltf is y up
+    let root = glb.push_node(json!({
+        "name": unit.to_string(),
+        "rotation": [-std::f32::consts::FRAC_1_SQRT_2, 0.0, 0.0, std::f32::consts::FRAC_1_SQRT_2],
+        "children": children,
+    }));
+    glb.scene.push(root);
+    Ok((glb.to_bytes()?, meshes.len()))
+}
+
+/// Same as `collision_to_glb` with an obj group per shape, the visual mesh being the "visual" group
+pub fn collision_to_obj(cache: &IdCache, data_path: &str, unit: Id) -> Result<(Vec<u8>, usize)> {
+    let (meshes, skipped) = unit_collision(cache, data_path, unit)?;
+    report_skipped(unit, &skipped);
+
+    let mut groups = Vec::new();
+    if let Some(lod) = visual_mesh(cache, data_path, unit) {
+        let vertices: Vec<[f32; 3]> = lod.vertices.iter().map(|v| [v.pos.x, v.pos.y, v.pos.z]).collect();
+        let triangles = lod
+            .parts
+            .iter()
+            .flat_map(|p| p.faces.iter())
+            .map(|f| f.map(|i| i as u32))
+            .filter(|f| f.iter().all(|i| (*i as usize) < vertices.len()))
+            .collect();
+        groups.push(CollisionMesh {
+            name: "visual".to_string(),
+            body: None,
+            vertices,
+            triangles,
+        });
+    }
+    let count = meshes.len();
+    groups.extend(meshes);
+
+    let mut out = Vec::new();
+    let mut base = 1;
+    for CollisionMesh { name, vertices, triangles, .. } in groups {
+        writeln!(out, "g {}", name)?;
+        for v in &vertices {
+            writeln!(out, "v {:?} {:?} {:?}", v[0], v[1], v[2])?;
+        }
+        for [a, b, c] in triangles {
+            writeln!(out, "f {} {} {}", a + base, b + base, c + base)?;
+        }
+        base += vertices.len() as u32;
+    }
+    Ok((out, count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::havok::{Format, HavokObject};
+
+    fn floats(values: &[f32]) -> Value {
+        Value::Array(values.iter().map(|f| Value::Float(*f as f64)).collect())
+    }
+
+    fn triangle(a: u64, b: u64, c: u64) -> Value {
+        Value::Object(vec![
+            ("a".to_string(), Value::UInt(a)),
+            ("b".to_string(), Value::UInt(b)),
+            ("c".to_string(), Value::UInt(c)),
+        ])
+    }
+
+    fn havok(objects: Vec<(&str, Value)>) -> HavokFile {
+        HavokFile {
+            format: Format::Tagfile,
+            version: String::new(),
+            types: Vec::new(),
+            objects: objects
+                .into_iter()
+                .enumerate()
+                .map(|(i, (type_name, value))| HavokObject {
+                    index: i + 1,
+                    type_name: type_name.to_string(),
+                    value,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn drops_triangles_past_the_vertices() {
+        let vertices = floats(&[0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+        let simple = Value::Object(vec![
+            ("vertices".to_string(), vertices.clone()),
+            (
+                "triangles".to_string(),
+                Value::Array(vec![triangle(0, 1, 2), triangle(0, 1, 3), triangle(0, 1, (1 << 32) + 2)]),
+            ),
+        ]);
+        // 16 bit triangles padded to four, then 32 bit ones
+        let subpart = Value::Object(vec![
+            ("vertices".to_string(), vertices),
+            ("indices16".to_string(), floats(&[2.0, 1.0, 0.0, 0.0, 0.0, 9.0, 1.0, 0.0])),
+            ("indices32".to_string(), floats(&[0.0, 2.0, 1.0, 5e9, 1.0, 2.0])),
+        ]);
+        let broken = Value::Object(vec![
+            ("vertices".to_string(), floats(&[0.0; 4])),
+            ("triangles".to_string(), Value::Array(vec![triangle(0, 1, 2)])),
+        ]);
+        let (meshes, skipped) = collision_meshes(&havok(vec![
+            ("hkpSimpleMeshShape", simple),
+            ("hkpStorageExtendedMeshShapeMeshSubpartStorage", subpart),
+            ("hkpSimpleMeshShape", broken),
+        ]));
+        assert!(skipped.is_empty());
+        let triangles: Vec<(&str, Vec<[u32; 3]>)> = meshes.iter().map(|m| (m.name.as_str(), m.triangles.clone())).collect();
+        assert_eq!(
+            triangles,
+            [
+                ("hkpSimpleMeshShape#1", vec![[0, 1, 2]]),
+                ("hkpStorageExtendedMeshShapeMeshSubpartStorage#2", vec![[2, 1, 0], [0, 2, 1]]),
+            ]
+        );
+    }
+
+    fn cube() -> Vec<[f32; 3]> {
+        (0..8).map(|i| [(i & 1) as f32, (i >> 1 & 1) as f32, (i >> 2 & 1) as f32]).collect()
+    }
+
+    /// Checks the faces close up, point away from the centre and use every point in `on_hull`
+    fn check_hull(p: &[[f32; 3]], faces: &[[u32; 3]], on_hull: &[usize]) {
+        let centre = on_hull.iter().fold([0.0; 3], |c, &i| [c[0] + p[i][0], c[1] + p[i][1], c[2] + p[i][2]]);
+        let centre = centre.map(|x| x / on_hull.len() as f32);
+        let mut edges = HashSet::new();
+        for f in faces {
+            let [a, b, c] = f.map(|i| p[i as usize]);
+            assert!(dot(cross(sub(b, a), sub(c, a)), sub(a, centre)) > 0.0, "{:?} faces inwards", f);
+            for edge in [(f[0], f[1]), (f[1], f[2]), (f[2], f[0])] {
+                assert!(edges.insert(edge), "edge {:?} is used twice", edge);
+            }
+        }
+        for &(a, b) in &edges {
+            assert!(edges.contains(&(b, a)), "edge {:?} has no neighbour", (a, b));
+        }
+        let used: HashSet<u32> = faces.iter().flatten().copied().collect();
+        assert_eq!(used, on_hull.iter().map(|&i| i as u32).collect());
+    }
+
+    #[test]
+    fn triangulates_a_cube() {
+        let p = cube();
+        let faces = convex_hull(&p);
+        assert_eq!(faces.len(), 12);
+        check_hull(&p, &faces, &(0..8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn leaves_out_inner_points() {
+        let mut p = cube();
+        p.insert(3, [0.5, 0.5, 0.5]);
+        p.push([0.25, 0.75, 0.5]);
+        let faces = convex_hull(&p);
+        assert_eq!(faces.len(), 12);
+        check_hull(&p, &faces, &[0, 1, 2, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn triangulates_a_tetrahedron() {
+        let p = [[0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [0.0, 2.0, 0.0], [0.0, 0.0, 2.0]];
+        let faces = convex_hull(&p);
+        assert_eq!(faces.len(), 4);
+        check_hull(&p, &faces, &[0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn gives_up_on_degenerate_input() {
+        assert!(convex_hull(&[]).is_empty());
+        assert!(convex_hull(&cube()[..3]).is_empty());
+        // a square, flat
+        assert!(convex_hull(&cube()[..4]).is_empty());
+        // a line
+        assert!(convex_hull(&[[0.0, 0.0, 0.0], [1.0, 1.0, 1.0], [2.0, 2.0, 2.0], [3.0, 3.0, 3.0]]).is_empty());
+        assert!(convex_hull(&[[1.0, 2.0, 3.0]; 6]).is_empty());
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::{
+    error::Result,
+    pndb::Pndb,
+    types::texture::extract_texture,
+    DataHeader, DataReaders, DataTypes, Id, IdCache,
+};
+
+/// What a font resource is known to hold: the TrueType or OpenType files it embeds and the ids of its
+/// atlas textures. The glyph and metrics tables aren't decoded.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Font {
+    pub textures: Vec<Atlas>,
+    pub embedded: Vec<EmbeddedFont>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Atlas {
+    pub id: Id,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct EmbeddedFont {
+    pub offset: usize,
+    pub size: usize,
+    /// ttf or otf
+    pub format: &'static str,
+}
+
+/// Size of the sfnt (TrueType/OpenType) file at the start of `buf`: the end of its furthest table.
+/// Everything the OpenType spec fixes in the header is checked, the binary search fields derived from
+/// the table count, sorted tags and 4 byte aligned tables, so data that only starts with the magic
+/// isn't taken for a font.
+fn sfnt_size(buf: &[u8]) -> Option<(usize, &'static str)> {
+    let format = match buf.get(..4)? {
+        [0, 1, 0, 0] | b"true" => "ttf",
+        b"OTTO" => "otf",
+        _ => return None,
+    };
+    let u16_at = |at: usize| Some(u16::from_be_bytes(buf.get(at..at + 2)?.try_into().unwrap()));
+    let tables = u16_at(4)? as usize;
+    if tables == 0 || tables > 64 {
+        return None;
+    }
+    let search_range = 16 << tables.ilog2();
+    if u16_at(6)? as usize != search_range
+        || u16_at(8)? as u32 != tables.ilog2()
+        || u16_at(10)? as usize != tables * 16 - search_range
+    {
+        return None;
+    }
+
+    let mut end = 12 + tables * 16;
+    let mut previous: Option<&[u8]> = None;
+    for t in 0..tables {
+        let record = buf.get(12 + t * 16..28 + t * 16)?;
+        let tag = &record[..4];
+        if !tag.iter().all(|c| c.is_ascii_graphic() || *c == b' ') || previous.is_some_and(|p| p >= tag) {
+            return None;
+        }
+        previous = Some(tag);
+        let offset = u32::from_be_bytes(record[8..12].try_into().unwrap()) as usize;
+        let len = u32::from_be_bytes(record[12..16].try_into().unwrap()) as usize;
+        if !offset.is_multiple_of(4) {
+            return None;
+        }
+        end = end.max(offset.checked_add(len)?);
+    }
+    (end <= buf.len()).then_some((end, format))
+}
+
+/// Embedded font files. Only 4 byte aligned offsets are tried, which is how the resource data is laid
+/// out around them.
+fn find_fonts(buf: &[u8]) -> Vec<EmbeddedFont> {
+    let mut found = Vec::new();
+    let mut at = 0;
+    while at + 12 <= buf.len() {
+        match sfnt_size(&buf[at..]) {
+            Some((size, format)) => {
+                found.push(EmbeddedFont { offset: at, size, format });
+                at += size.next_multiple_of(4);
+            }
+            None => at += 4,
+        }
+    }
+    found
+}
+
+/// Atlas textures, found by looking for ids of textures in the cache at 8 byte aligned offsets. That's
+/// one pass over the cache to collect the texture ids and a lookup per 8 bytes of the font; a random
+/// value matching one of the ids is unlikely enough not to filter further.
+fn find_textures(cache: &IdCache, buf: &[u8]) -> Vec<Id> {
+    let texture = DataTypes::Texture.as_id();
+    let known: HashSet<u64> = cache
+        .bundles
+        .values()
+        .flatten()
+        .filter(|h| h.type_id == texture)
+        .map(|h| u64::from(h.id))
+        .collect();
+    let mut found: Vec<Id> = Vec::new();
+    for chunk in buf.chunks_exact(8) {
+        let value = u64::from_le_bytes(chunk.try_into().unwrap());
+        if known.contains(&value) && !found.iter().any(|id| u64::from(*id) == value) {
+            found.push(Id::from(value));
+        }
+    }
+    found
+}
+
+pub fn read_font(cache: &IdCache, namedb: &Pndb, d: &DataHeader, r: &mut DataReaders) -> Result<Font> {
+    let buf = d.get_bundle_buf(r.bundle())?;
+    Ok(Font {
+        embedded: find_fonts(&buf),
+        textures: find_textures(cache, &buf)
+            .into_iter()
+            .map(|id| Atlas {
+                id,
+                name: namedb.name_database.get(&id).cloned(),
+            })
+            .collect(),
+    })
+}
+
+pub fn extract_font(cache: &IdCache, namedb: &Pndb, d: &DataHeader, r: &mut DataReaders) -> Result<(Vec<u8>, Option<String>)> {
+    let font = read_font(cache, namedb, d, r)?;
+    Ok((serde_json::to_vec_pretty(&font)?, None))
+}
+
+/// The embedded font files, and the atlas pages converted like any other texture
+pub fn font_files(cache: &IdCache, data_path: &str, d: &DataHeader, r: &mut DataReaders) -> Result<Vec<(String, Vec<u8>)>> {
+    let buf = d.get_bundle_buf(r.bundle())?;
+    let mut files: Vec<(String, Vec<u8>)> = find_fonts(&buf)
+        .iter()
+        .enumerate()
+        .map(|(i, f)| (format!("{}.{}", i, f.format), buf[f.offset..f.offset + f.size].to_vec()))
+        .collect();
+    for (page, id) in find_textures(cache, &buf).into_iter().enumerate() {
+        let (bundle, h) = cache.get_by_id(id, Some(DataTypes::Texture), Id::invalid())?;
+        let mut readers = DataReaders::open(data_path, &bundle)?;
+        let (dds, _) = extract_texture(&mut DataHeader::from(h), &mut readers)?;
+        files.push((format!("page{}.{}", page, DataTypes::Texture.extension()), dds));
+    }
+    Ok(files)
+}
+
+pub fn summarize_font(cache: &IdCache, d: &DataHeader, r: &mut DataReaders) -> Result<Vec<(&'static str, String)>> {
+    let font = read_font(cache, &Pndb::default(), d, r)?;
+    let mut summary = vec![("atlas pages", font.textures.len().to_string())];
+    for f in &font.embedded {
+        summary.push(("embedded", format!("{} of {} bytes at {:#x}", f.format, f.size, f.offset)));
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MinimizedIdHeader;
+
+    const ATLAS: u64 = 0x0102030405060708;
+
+    /// The smallest sfnt the checks accept: a header and one empty head table
+    fn sfnt() -> Vec<u8> {
+        let mut buf = vec![0, 1, 0, 0, 0, 1, 0, 16, 0, 0, 0, 0];
+        buf.extend(b"head");
+        buf.extend([0, 0, 0, 0, 0, 0, 0, 28, 0, 0, 0, 0]);
+        buf
+    }
+
+    fn cache() -> IdCache {
+        let mut cache = IdCache::default();
+        cache.bundles.insert(
+            Id::from(1),
+            vec![MinimizedIdHeader {
+                id: Id::from(ATLAS),
+                type_id: DataTypes::Texture.as_id(),
+                ..Default::default()
+            }],
+        );
+        cache
+    }
+
+    #[test]
+    fn finds_atlas_textures() {
+        let mut buf = 7u64.to_le_bytes().to_vec();
+        buf.extend(ATLAS.to_le_bytes());
+        buf.extend(ATLAS.to_le_bytes());
+        assert_eq!(find_textures(&cache(), &buf), vec![Id::from(ATLAS)]);
+        // ids are only looked for at 8 byte alignment
+        assert!(find_textures(&cache(), &buf[4..]).is_empty());
+    }
+
+    #[test]
+    fn finds_embedded_fonts() {
+        let mut buf = vec![0xff; 8];
+        buf.extend(sfnt());
+        let found = find_fonts(&buf);
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].offset, found[0].size, found[0].format), (8, 28, "ttf"));
+    }
+
+    #[test]
+    fn rejects_magic_without_a_valid_header() {
+        assert!(sfnt_size(&sfnt()).is_some());
+        // wrong search range
+        let mut buf = sfnt();
+        buf[7] = 32;
+        assert!(sfnt_size(&buf).is_none());
+        // table running past the end
+        let mut buf = sfnt();
+        buf[27] = 8;
+        assert!(sfnt_size(&buf).is_none());
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    error::{Error, Result},
+    gltf::{matrix_to_quat, Glb},
+    pndb::Pndb,
+    types::unit::{push_lod_mesh, read_unit},
+    DataHeader, DataReaders, DataTypes, Id, IdCache,
+};
+
+/// The units a level places in the world. Only unit instances are found, by a heuristic scan, so levels
+/// are experimental; lights, volumes and the names instances are given in the editor aren't read.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Scene {
+    pub level: Id,
+    /// Name of the level resource, from the name database
+    pub name: Option<String>,
+    pub units: Vec<UnitInstance>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct UnitInstance {
+    /// Unit resource the instance places
+    pub unit: Id,
+    /// Name of the unit resource, from the name database
+    pub unit_name: Option<String>,
+    /// Where in the level data the instance was found
+    pub offset: u64,
+    pub translation: [f32; 3],
+    /// Quaternion as x, y, z, w
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+}
+
+// How far past a unit id to look for its transform
+const TRANSFORM_SEARCH: usize = 0x40;
+
+fn floats<const N: usize>(buf: &[u8], offset: usize) -> Option<[f32; N]> {
+    let bytes = buf.get(offset..offset + N * 4)?;
+    let mut out = [0f32; N];
+    for (o, c) in out.iter_mut().zip(bytes.chunks_exact(4)) {
+        *o = f32::from_le_bytes(c.try_into().unwrap());
+    }
+    out.iter().all(|f| f.is_finite()).then_some(out)
+}
+
+fn plausible_translation(t: &[f32; 3]) -> bool {
+    t.iter().all(|x| x.abs() < 1e6)
+}
+
+fn plausible_scale(s: &[f32; 3]) -> bool {
+    s.iter().all(|x| x.abs() > 1e-4 && x.abs() < 1e4)
+}
+
+/// Translation, rotation quaternion and scale stored one after the other
+fn read_trs(buf: &[u8], offset: usize) -> Option<([f32; 3], [f32; 4], [f32; 3])> {
+    let t: [f32; 3] = floats(buf, offset)?;
+    let r: [f32; 4] = floats(buf, offset + 12)?;
+    let s: [f32; 3] = floats(buf, offset + 28)?;
+    let norm = r.iter().map(|x| x * x).sum::<f32>().sqrt();
+    ((norm - 1.0).abs() < 1e-3 && plausible_translation(&t) && plausible_scale(&s)).then_some((t, r, s))
+}
+
+/// A row-major 4x4 matrix with the translation in the last row
+fn read_matrix(buf: &[u8], offset: usize) -> Option<([f32; 3], [f32; 4], [f32; 3])> {
+    let m: [f32; 16] = floats(buf, offset)?;
+    if m[3] != 0.0 || m[7] != 0.0 || m[11] != 0.0 || m[15] != 1.0 {
+        return None;
+    }
+    let rows = [[m[0], m[1], m[2]], [m[4], m[5], m[6]], [m[8], m[9], m[10]]];
+    let scale = rows.map(|r| r.iter().map(|x| x * x).sum::<f32>().sqrt());
+    let translation = [m[12], m[13], m[14]];
+    if !plausible_scale(&scale) || !plausible_translation(&translation) {
+        return None;
+    }
+    let r = [0, 1, 2].map(|i| rows[i].map(|x| x / scale[i]));
+    // the rotation part has to be orthogonal
+    let dot = |a: &[f32; 3], b: &[f32; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+    if dot(&r[0], &r[1]).abs() > 1e-3 || dot(&r[0], &r[2]).abs() > 1e-3 || dot(&r[1], &r[2]).abs() > 1e-3 {
+        return None;
+    }
+    Some((translation, matrix_to_quat(&r), scale))
+}
+
+/// Finds the unit instances in a level. This isn't a parser: the instance table's layout isn't mapped
+/// out yet, so it looks for ids of units in the cache and takes the first plausible transform
+/// (translation/quaternion/scale or a 4x4 matrix) within a few bytes after each one. Random data can
+/// pass for an instance, which is why levels are behind --experimental.
+pub fn read_level(cache: &IdCache, namedb: &Pndb, d: &DataHeader, r: &mut DataReaders) -> Result<Scene> {
+    let buf = d.get_bundle_buf(r.bundle())?;
+    let unit_type = DataTypes::Unit.as_id();
+    let units: HashSet<u64> = cache
+        .bundles
+        .values()
+        .flatten()
+        .filter(|h| h.type_id == unit_type)
+        .map(|h| u64::from(h.id))
+        .collect();
+
+    let mut scene = Scene {
+        level: d.unk_id,
+        name: namedb.name_database.get(&d.unk_id).cloned(),
+        ..Default::default()
+    };
+    let mut offset = 0;
+    while offset + 8 <= buf.len() {
+        let value = u64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap());
+        if !units.contains(&value) {
+            offset += 4;
+            continue;
+        }
+        let transform = (offset + 8..offset + 8 + TRANSFORM_SEARCH)
+            .step_by(4)
+            .find_map(|t| read_trs(&buf, t).map(|trs| (t + 40, trs)).or(read_matrix(&buf, t).map(|trs| (t + 64, trs))));
+        match transform {
+            Some((end, (translation, rotation, scale))) => {
+                scene.units.push(UnitInstance {
+                    unit: Id::from(value),
+                    unit_name: namedb.name_database.get(&Id::from(value)).cloned(),
+                    offset: offset as u64,
+                    translation,
+                    rotation,
+                    scale,
+                });
+                offset = end;
+            }
+            None => offset += 8,
+        }
+    }
+    if scene.units.is_empty() {
+        return Err(Error::Decode(format!("level {}, no unit instances found", d.unk_id)));
+    }
+    Ok(scene)
+}
+
+pub fn extract_level(cache: &IdCache, namedb: &Pndb, d: &DataHeader, r: &mut DataReaders) -> Result<(Vec<u8>, Option<String>)> {
+    let scene = read_level(cache, namedb, d, r)?;
+    Ok((serde_json::to_vec_pretty(&scene)?, None))
+}
+
+pub fn summarize_level(cache: &IdCache, d: &DataHeader, r: &mut DataReaders) -> Result<Vec<(&'static str, String)>> {
+    let scene = read_level(cache, &Pndb::default(), d, r)?;
+    let distinct: HashSet<Id> = scene.units.iter().map(|u| u.unit).collect();
+    Ok(vec![
+        ("unit instances", scene.units.len().to_string()),
+        ("distinct units", distinct.len().to_string()),
+    ])
+}
+
+/// Builds a glTF scene with a node per unit instance, each pointing at the first lod of its unit. Units
+/// whose meshes can't be read are still placed, as empty nodes.
+pub fn scene_to_glb(cache: &IdCache, data_path: &str, scene: &Scene) -> Result<Vec<u8>> {
+    let mut glb = Glb::default();
+    let mut meshes: HashMap<Id, Option<usize>> = HashMap::new();
+    let mut readers: HashMap<Id, DataReaders> = HashMap::new();
+
+    let mut children = Vec::new();
+    for instance in &scene.units {
+        let mesh = match meshes.get(&instance.unit) {
+            Some(mesh) => *mesh,
+            None => {
+                let mesh = unit_mesh(cache, data_path, &mut readers, &mut glb, instance.unit)
+                    .inspect_err(|e| eprintln!("Failed to read unit {}: {}", instance.unit, e))
+                    .ok();
+                meshes.insert(instance.unit, mesh);
+                mesh
+            }
+        };
+        let mut node = json!({
+            "name": instance.unit_name.clone().unwrap_or(instance.unit.to_string()),
+            "translation": instance.translation,
+            "rotation": instance.rotation,
+            "scale": instance.scale,
+        });
+        if let Some(mesh) = mesh {
+            node["mesh"] = json!(mesh);
+        }
+        children.push(glb.push_node(node));
+    }
+
+    // the engine is z up, gltf is y up
+    let root = glb.push_node(json!({
+        "name": scene.name.clone().unwrap_or(scene.level.to_string()),
+        "rotation": [-std::f32::consts::FRAC_1_SQRT_2, 0.0, 0.0, std::f32::consts::FRAC_1_SQRT_2],
+        "children": children,
+    }));
+    glb.scene.push(root);
+    glb.to_bytes()
+}
+
+fn unit_mesh(
+    cache: &IdCache,
+    data_path: &str,
+    readers: &mut HashMap<Id, DataReaders>,
+    glb: &mut Glb,
+    unit: Id,
+) -> Result<usize> {
+    let (bundle, h) = cache.get_by_id(unit, Some(DataTypes::Unit), Id::invalid())?;
+    let r = match readers.entry(bundle) {
+        std::collections::hash_map::Entry::Occupied(e) => e.into_mut(),
+        std::collections::hash_map::Entry::Vacant(e) => e.insert(DataReaders::open(data_path, &bundle)?),
+    };
+    let d = DataHeader::from(h);
+    let lods = read_unit(&d, r)?;
+    let Some(lod) = lods.first() else {
+        return Err(Error::Decode(format!("unit {}, no lods", unit)));
+    };
+    push_lod_mesh(glb, &unit.to_string(), lod).ok_or_else(|| Error::Decode(format!("unit {}, no faces in lod 0", unit)))
+}
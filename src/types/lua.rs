@@ -0,0 +1,676 @@
+use std::{
+    fmt::Write,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{
+    error::{Error, Result},
+    DataHeader, DataReaders,
+};
+
+const LJ_MAGIC: &[u8] = b"\x1bLJ";
+
+// dump flags
+const FLAG_BE: u64 = 0x01;
+const FLAG_STRIP: u64 = 0x02;
+
+// kgc constant kinds, anything past STR is a string of (kind - STR) bytes
+const KGC_CHILD: u64 = 0;
+const KGC_TAB: u64 = 1;
+const KGC_I64: u64 = 2;
+const KGC_U64: u64 = 3;
+const KGC_COMPLEX: u64 = 4;
+const KGC_STR: u64 = 5;
+
+// table constant kinds
+const KTAB_NIL: u64 = 0;
+const KTAB_FALSE: u64 = 1;
+const KTAB_TRUE: u64 = 2;
+const KTAB_INT: u64 = 3;
+const KTAB_NUM: u64 = 4;
+const KTAB_STR: u64 = 5;
+
+/// Finds the LuaJIT bytecode in a lua resource, skipping the resource header in front of it
+pub fn strip_header(buf: &[u8]) -> Result<&[u8]> {
+    match buf.windows(LJ_MAGIC.len()).take(0x40).position(|w| w == LJ_MAGIC) {
+        Some(start) => Ok(&buf[start..]),
+        None => Err(Error::BadMagic {
+            pos: 0,
+            found: format!("{:x?}", &buf[..buf.len().min(8)]),
+        }),
+    }
+}
+
+pub fn extract_lua(d: &DataHeader, r: &mut DataReaders) -> Result<(Vec<u8>, Option<String>)> {
+    let buf = d.get_bundle_buf(r.bundle())?;
+    let bytecode = strip_header(&buf)?.to_vec();
+    // the chunk name is the script's path, use it as the file name when it's there
+    let name = read_dump(&bytecode).ok().and_then(|dump| dump.chunk_name).and_then(|n| chunk_path(&n));
+    Ok((bytecode, name))
+}
+
+/// The script path in a chunk name, made relative so it stays inside the output directory. None if
+/// it climbs out of it or has nothing left.
+fn chunk_path(name: &str) -> Option<String> {
+    let name = name.trim_start_matches('@').trim_end_matches(".lua");
+    let mut path = PathBuf::new();
+    for c in Path::new(name).components() {
+        match c {
+            Component::Normal(c) => path.push(c),
+            Component::RootDir | Component::Prefix(_) | Component::CurDir => {}
+            Component::ParentDir => return None,
+        }
+    }
+    if path.as_os_str().is_empty() {
+        return None;
+    }
+    path.to_str().map(str::to_string)
+}
+
+pub fn summarize_lua(d: &DataHeader, r: &mut DataReaders) -> Result<Vec<(&'static str, String)>> {
+    let buf = d.get_bundle_buf(r.bundle())?;
+    let dump = read_dump(strip_header(&buf)?)?;
+    let mut summary = vec![
+        ("luajit version", dump.version.to_string()),
+        ("functions", dump.protos.len().to_string()),
+    ];
+    if let Some(name) = dump.chunk_name {
+        summary.push(("chunk", name));
+    }
+    Ok(summary)
+}
+
+#[derive(Debug, Clone)]
+pub enum TableValue {
+    Nil,
+    Bool(bool),
+    Int(i32),
+    Num(f64),
+    Str(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum GcConstant {
+    /// Index of a previously read prototype
+    Child(usize),
+    Table {
+        array: Vec<TableValue>,
+        hash: Vec<(TableValue, TableValue)>,
+    },
+    I64(i64),
+    U64(u64),
+    Complex(f64, f64),
+    Str(String),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum NumConstant {
+    Int(i32),
+    Num(f64),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Proto {
+    pub flags: u8,
+    pub num_params: u8,
+    pub frame_size: u8,
+    pub first_line: u64,
+    pub num_lines: u64,
+    pub instructions: Vec<u32>,
+    pub upvalues: Vec<u16>,
+    pub gc_constants: Vec<GcConstant>,
+    pub num_constants: Vec<NumConstant>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Dump {
+    pub version: u8,
+    pub flags: u64,
+    pub chunk_name: Option<String>,
+    /// Children come before their parents, the main chunk is last
+    pub protos: Vec<Proto>,
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn truncated(&self) -> Error {
+        Error::Decode(format!("luajit bytecode, truncated at {:#x}", self.pos))
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        let b = *self.buf.get(self.pos).ok_or_else(|| self.truncated())?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&[u8]> {
+        let end = self.pos.checked_add(len).filter(|e| *e <= self.buf.len());
+        let Some(end) = end else {
+            return Err(self.truncated());
+        };
+        let bytes = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn uleb(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let b = self.u8()?;
+            if shift < 64 {
+                value |= ((b & 0x7f) as u64) << shift;
+            }
+            shift += 7;
+            if b & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// A uleb128 whose first byte's low bit is a flag, returned separately
+    fn uleb33(&mut self) -> Result<(u64, bool)> {
+        let first = self.u8()?;
+        let flag = first & 1 != 0;
+        let mut value = (first >> 1) as u64;
+        if first >= 0x80 {
+            value &= 0x3f;
+            let mut shift = 6;
+            loop {
+                let b = self.u8()?;
+                if shift < 64 {
+                    value |= ((b & 0x7f) as u64) << shift;
+                }
+                shift += 7;
+                if b & 0x80 == 0 {
+                    break;
+                }
+            }
+        }
+        Ok((value, flag))
+    }
+
+    fn string(&mut self, len: usize) -> Result<String> {
+        Ok(String::from_utf8_lossy(self.bytes(len)?).into_owned())
+    }
+
+    fn table_value(&mut self) -> Result<TableValue> {
+        let kind = self.uleb()?;
+        Ok(match kind {
+            KTAB_NIL => TableValue::Nil,
+            KTAB_FALSE => TableValue::Bool(false),
+            KTAB_TRUE => TableValue::Bool(true),
+            KTAB_INT => TableValue::Int(self.uleb()? as u32 as i32),
+            KTAB_NUM => {
+                let lo = self.uleb()?;
+                let hi = self.uleb()?;
+                TableValue::Num(f64::from_bits((hi << 32) | (lo & 0xffff_ffff)))
+            }
+            _ => TableValue::Str(self.string((kind - KTAB_STR) as usize)?),
+        })
+    }
+
+    fn u64_pair(&mut self) -> Result<u64> {
+        let lo = self.uleb()?;
+        let hi = self.uleb()?;
+        Ok((hi << 32) | (lo & 0xffff_ffff))
+    }
+}
+
+/// Parses a LuaJIT bytecode dump (as made by `string.dump` or `luajit -b`)
+pub fn read_dump(buf: &[u8]) -> Result<Dump> {
+    let mut r = Reader { buf, pos: 0 };
+    if r.bytes(3)? != LJ_MAGIC {
+        return Err(Error::BadMagic {
+            pos: 0,
+            found: format!("{:x?}", &buf[..buf.len().min(3)]),
+        });
+    }
+    let mut dump = Dump {
+        version: r.u8()?,
+        flags: r.uleb()?,
+        ..Default::default()
+    };
+    if dump.version != 1 && dump.version != 2 {
+        return Err(Error::UnsupportedType(format!("luajit bytecode version {}", dump.version)));
+    }
+    if dump.flags & FLAG_STRIP == 0 {
+        let len = r.uleb()? as usize;
+        dump.chunk_name = Some(r.string(len)?);
+    }
+    let big_endian = dump.flags & FLAG_BE != 0;
+    // prototypes not yet claimed as a child by a later one
+    let mut unclaimed: Vec<usize> = Vec::new();
+
+    loop {
+        let len = r.uleb()? as usize;
+        if len == 0 {
+            break;
+        }
+        let end = r.pos + len;
+        let mut p = Proto {
+            flags: r.u8()?,
+            num_params: r.u8()?,
+            frame_size: r.u8()?,
+            ..Default::default()
+        };
+        let num_upvalues = r.u8()? as usize;
+        let num_gc = r.uleb()? as usize;
+        let num_kn = r.uleb()? as usize;
+        let num_bc = r.uleb()? as usize;
+        let mut debug_len = 0;
+        if dump.flags & FLAG_STRIP == 0 {
+            debug_len = r.uleb()? as usize;
+            if debug_len > 0 {
+                p.first_line = r.uleb()?;
+                p.num_lines = r.uleb()?;
+            }
+        }
+
+        for c in r.bytes(num_bc * 4)?.chunks_exact(4) {
+            let c: [u8; 4] = c.try_into().unwrap();
+            p.instructions.push(if big_endian {
+                u32::from_be_bytes(c)
+            } else {
+                u32::from_le_bytes(c)
+            });
+        }
+        for c in r.bytes(num_upvalues * 2)?.chunks_exact(2) {
+            let c: [u8; 2] = c.try_into().unwrap();
+            p.upvalues.push(if big_endian {
+                u16::from_be_bytes(c)
+            } else {
+                u16::from_le_bytes(c)
+            });
+        }
+
+        for _ in 0..num_gc {
+            let kind = r.uleb()?;
+            let constant = match kind {
+                KGC_CHILD => match unclaimed.pop() {
+                    Some(child) => GcConstant::Child(child),
+                    None => {
+                        return Err(Error::Decode(
+                            "luajit bytecode, child prototype missing".to_string(),
+                        ))
+                    }
+                },
+                KGC_TAB => {
+                    let num_array = r.uleb()? as usize;
+                    let num_hash = r.uleb()? as usize;
+                    let mut array = Vec::new();
+                    for _ in 0..num_array {
+                        array.push(r.table_value()?);
+                    }
+                    let mut hash = Vec::new();
+                    for _ in 0..num_hash {
+                        hash.push((r.table_value()?, r.table_value()?));
+                    }
+                    GcConstant::Table { array, hash }
+                }
+                KGC_I64 => GcConstant::I64(r.u64_pair()? as i64),
+                KGC_U64 => GcConstant::U64(r.u64_pair()?),
+                KGC_COMPLEX => GcConstant::Complex(
+                    f64::from_bits(r.u64_pair()?),
+                    f64::from_bits(r.u64_pair()?),
+                ),
+                _ => GcConstant::Str(r.string((kind - KGC_STR) as usize)?),
+            };
+            p.gc_constants.push(constant);
+        }
+        for _ in 0..num_kn {
+            let (lo, is_num) = r.uleb33()?;
+            p.num_constants.push(if is_num {
+                let hi = r.uleb()?;
+                NumConstant::Num(f64::from_bits((hi << 32) | (lo & 0xffff_ffff)))
+            } else {
+                NumConstant::Int(lo as u32 as i32)
+            });
+        }
+        r.bytes(debug_len)?;
+        if r.pos != end {
+            return Err(Error::Decode(format!(
+                "luajit bytecode, prototype ends at {:#x} instead of {:#x}",
+                r.pos, end
+            )));
+        }
+        unclaimed.push(dump.protos.len());
+        dump.protos.push(p);
+    }
+    Ok(dump)
+}
+
+/// Operand layout of an instruction
+#[derive(Clone, Copy, PartialEq)]
+enum Operands {
+    /// A and 16 bit D
+    AD,
+    /// A, B and C
+    BC,
+}
+
+/// What the D (or C) operand of an instruction refers to, for annotating the listing
+#[derive(Clone, Copy, PartialEq)]
+enum Operand {
+    Plain,
+    Str,
+    Num,
+    Pri,
+    Short,
+    Jump,
+    Func,
+    Upvalue,
+}
+
+use Operand::*;
+use Operands::*;
+
+/// LuaJIT 2.1 opcodes. 2.0 dumps lack ISTYPE, ISNUM, TGETR and TSETR.
+const OPCODES: [(&str, Operands, Operand); 97] = [
+    ("ISLT", AD, Plain),
+    ("ISGE", AD, Plain),
+    ("ISLE", AD, Plain),
+    ("ISGT", AD, Plain),
+    ("ISEQV", AD, Plain),
+    ("ISNEV", AD, Plain),
+    ("ISEQS", AD, Str),
+    ("ISNES", AD, Str),
+    ("ISEQN", AD, Num),
+    ("ISNEN", AD, Num),
+    ("ISEQP", AD, Pri),
+    ("ISNEP", AD, Pri),
+    ("ISTC", AD, Plain),
+    ("ISFC", AD, Plain),
+    ("IST", AD, Plain),
+    ("ISF", AD, Plain),
+    ("ISTYPE", AD, Plain),
+    ("ISNUM", AD, Plain),
+    ("MOV", AD, Plain),
+    ("NOT", AD, Plain),
+    ("UNM", AD, Plain),
+    ("LEN", AD, Plain),
+    ("ADDVN", BC, Num),
+    ("SUBVN", BC, Num),
+    ("MULVN", BC, Num),
+    ("DIVVN", BC, Num),
+    ("MODVN", BC, Num),
+    ("ADDNV", BC, Num),
+    ("SUBNV", BC, Num),
+    ("MULNV", BC, Num),
+    ("DIVNV", BC, Num),
+    ("MODNV", BC, Num),
+    ("ADDVV", BC, Plain),
+    ("SUBVV", BC, Plain),
+    ("MULVV", BC, Plain),
+    ("DIVVV", BC, Plain),
+    ("MODVV", BC, Plain),
+    ("POW", BC, Plain),
+    ("CAT", BC, Plain),
+    ("KSTR", AD, Str),
+    ("KCDATA", AD, Plain),
+    ("KSHORT", AD, Short),
+    ("KNUM", AD, Num),
+    ("KPRI", AD, Pri),
+    ("KNIL", AD, Plain),
+    ("UGET", AD, Upvalue),
+    ("USETV", AD, Plain),
+    ("USETS", AD, Str),
+    ("USETN", AD, Num),
+    ("USETP", AD, Pri),
+    ("UCLO", AD, Jump),
+    ("FNEW", AD, Func),
+    ("TNEW", AD, Plain),
+    ("TDUP", AD, Plain),
+    ("GGET", AD, Str),
+    ("GSET", AD, Str),
+    ("TGETV", BC, Plain),
+    ("TGETS", BC, Str),
+    ("TGETB", BC, Plain),
+    ("TGETR", BC, Plain),
+    ("TSETV", BC, Plain),
+    ("TSETS", BC, Str),
+    ("TSETB", BC, Plain),
+    ("TSETM", AD, Num),
+    ("TSETR", BC, Plain),
+    ("CALLM", BC, Plain),
+    ("CALL", BC, Plain),
+    ("CALLMT", AD, Plain),
+    ("CALLT", AD, Plain),
+    ("ITERC", BC, Plain),
+    ("ITERN", BC, Plain),
+    ("VARG", BC, Plain),
+    ("ISNEXT", AD, Jump),
+    ("RETM", AD, Plain),
+    ("RET", AD, Plain),
+    ("RET0", AD, Plain),
+    ("RET1", AD, Plain),
+    ("FORI", AD, Jump),
+    ("JFORI", AD, Jump),
+    ("FORL", AD, Jump),
+    ("IFORL", AD, Jump),
+    ("JFORL", AD, Jump),
+    ("ITERL", AD, Jump),
+    ("IITERL", AD, Jump),
+    ("JITERL", AD, Jump),
+    ("LOOP", AD, Jump),
+    ("ILOOP", AD, Jump),
+    ("JLOOP", AD, Jump),
+    ("JMP", AD, Jump),
+    ("FUNCF", AD, Plain),
+    ("IFUNCF", AD, Plain),
+    ("JFUNCF", AD, Plain),
+    ("FUNCV", AD, Plain),
+    ("IFUNCV", AD, Plain),
+    ("JFUNCV", AD, Plain),
+    ("FUNCC", AD, Plain),
+    ("FUNCCW", AD, Plain),
+];
+
+fn opcode(version: u8, op: u8) -> Option<(&'static str, Operands, Operand)> {
+    let mut op = op as usize;
+    if version == 1 {
+        // skip over the opcodes 2.1 added
+        for added in [16, 17, 59, 64] {
+            if op >= added {
+                op += 1;
+            }
+        }
+    }
+    OPCODES.get(op).copied()
+}
+
+fn quote(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if c.is_control() => quoted.push_str(&format!("\\{}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn table_value(v: &TableValue) -> String {
+    match v {
+        TableValue::Nil => "nil".to_string(),
+        TableValue::Bool(b) => b.to_string(),
+        TableValue::Int(i) => i.to_string(),
+        TableValue::Num(n) => n.to_string(),
+        TableValue::Str(s) => quote(s),
+    }
+}
+
+fn num_constant(n: &NumConstant) -> String {
+    match n {
+        NumConstant::Int(i) => i.to_string(),
+        NumConstant::Num(n) => n.to_string(),
+    }
+}
+
+/// String constants are indexed from the end of the gc constant list
+fn gc_string(p: &Proto, index: usize) -> Option<&str> {
+    let i = p.gc_constants.len().checked_sub(index + 1)?;
+    match &p.gc_constants[i] {
+        GcConstant::Str(s) => Some(s),
+        _ => None,
+    }
+}
+
+fn annotate(p: &Proto, kind: Operand, value: u32, pc: usize) -> Option<String> {
+    Some(match kind {
+        Plain => return None,
+        Str => quote(gc_string(p, value as usize)?),
+        Num => num_constant(p.num_constants.get(value as usize)?),
+        Pri => ["nil", "false", "true"].get(value as usize)?.to_string(),
+        Short => (value as u16 as i16).to_string(),
+        Jump => format!("=> {:04}", (pc as i64 + 1 + value as i64 - 0x8000) + 1),
+        Func => {
+            let i = p.gc_constants.len().checked_sub(value as usize + 1)?;
+            match p.gc_constants[i] {
+                GcConstant::Child(child) => format!("function {}", child),
+                _ => return None,
+            }
+        }
+        Upvalue => format!("upvalue {}", p.upvalues.get(value as usize)? & 0x3fff),
+    })
+}
+
+/// Lists every prototype in a dump with its constants and instructions, in the spirit of `luajit -bl`
+pub fn disassemble(dump: &Dump) -> String {
+    let mut out = String::new();
+    let chunk = dump.chunk_name.as_deref().unwrap_or("?");
+    for (i, p) in dump.protos.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "-- function {} -- {}:{}-{} params {} framesize {} flags {:#x}{}",
+            i,
+            chunk,
+            p.first_line,
+            p.first_line + p.num_lines,
+            p.num_params,
+            p.frame_size,
+            p.flags,
+            if i == dump.protos.len() - 1 { " (main chunk)" } else { "" }
+        );
+        for (k, c) in p.gc_constants.iter().enumerate() {
+            let c = match c {
+                GcConstant::Child(child) => format!("function {}", child),
+                GcConstant::Table { array, hash } => {
+                    let mut items: Vec<String> = array.iter().map(table_value).collect();
+                    items.extend(
+                        hash.iter()
+                            .map(|(k, v)| format!("[{}] = {}", table_value(k), table_value(v))),
+                    );
+                    format!("{{ {} }}", items.join(", "))
+                }
+                GcConstant::I64(v) => format!("{}LL", v),
+                GcConstant::U64(v) => format!("{}ULL", v),
+                GcConstant::Complex(re, im) => format!("{}+{}i", re, im),
+                GcConstant::Str(s) => quote(s),
+            };
+            let _ = writeln!(out, "  gc {:>3}  {}", k, c);
+        }
+        for (k, n) in p.num_constants.iter().enumerate() {
+            let _ = writeln!(out, "  kn {:>3}  {}", k, num_constant(n));
+        }
+
+        for (pc, ins) in p.instructions.iter().enumerate() {
+            let op = (ins & 0xff) as u8;
+            let a = (ins >> 8) & 0xff;
+            let Some((name, operands, kind)) = opcode(dump.version, op) else {
+                let _ = writeln!(out, "{:04}    ??? {:#010x}", pc + 1, ins);
+                continue;
+            };
+            let (line, annotation) = match operands {
+                AD => {
+                    let d = ins >> 16;
+                    (format!("{:<6} {:>3} {:>5}", name, a, d), annotate(p, kind, d, pc))
+                }
+                BC => {
+                    let b = ins >> 24;
+                    let c = (ins >> 16) & 0xff;
+                    (format!("{:<6} {:>3} {:>3} {:>3}", name, a, b, c), annotate(p, kind, c, pc))
+                }
+            };
+            match annotation {
+                Some(annotation) => {
+                    let _ = writeln!(out, "{:04}    {:<22} ; {}", pc + 1, line, annotation);
+                }
+                None => {
+                    let _ = writeln!(out, "{:04}    {}", pc + 1, line);
+                }
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uleb33(bytes: &[u8]) -> Result<(u64, bool)> {
+        Reader { buf: bytes, pos: 0 }.uleb33()
+    }
+
+    fn name(version: u8, op: u8) -> Option<&'static str> {
+        opcode(version, op).map(|(name, _, _)| name)
+    }
+
+    #[test]
+    fn keeps_chunk_paths_relative() {
+        assert_eq!(chunk_path("@scripts/game/player.lua").as_deref(), Some("scripts/game/player"));
+        assert_eq!(chunk_path("@/etc/x").as_deref(), Some("etc/x"));
+        assert_eq!(chunk_path("./a//b.lua").as_deref(), Some("a/b"));
+        assert_eq!(chunk_path("../../x"), None);
+        assert_eq!(chunk_path("@scripts/../../x.lua"), None);
+        assert_eq!(chunk_path("@/"), None);
+        assert_eq!(chunk_path("@"), None);
+    }
+
+    #[test]
+    fn reads_uleb33() {
+        assert_eq!(uleb33(&[0x02]).unwrap(), (1, false));
+        assert_eq!(uleb33(&[0x03]).unwrap(), (1, true));
+        assert_eq!(uleb33(&[0x7f]).unwrap(), (63, true));
+        // past 6 bits the value continues in the following bytes
+        assert_eq!(uleb33(&[0x80, 0x01]).unwrap(), (64, false));
+        assert_eq!(uleb33(&[0xd9, 0x04]).unwrap(), (300, true));
+        assert_eq!(uleb33(&[0xfe, 0xff, 0xff, 0xff, 0x1f]).unwrap(), (0xffff_ffff, false));
+        assert!(uleb33(&[0x80]).is_err());
+        assert!(uleb33(&[]).is_err());
+    }
+
+    #[test]
+    fn maps_2_1_opcodes() {
+        assert_eq!(name(2, 0), Some("ISLT"));
+        assert_eq!(name(2, 16), Some("ISTYPE"));
+        assert_eq!(name(2, 17), Some("ISNUM"));
+        assert_eq!(name(2, 18), Some("MOV"));
+        assert_eq!(name(2, 59), Some("TGETR"));
+        assert_eq!(name(2, 64), Some("TSETR"));
+        assert_eq!(name(2, 96), Some("FUNCCW"));
+        assert_eq!(name(2, 97), None);
+    }
+
+    #[test]
+    fn maps_2_0_opcodes_around_the_added_ones() {
+        assert_eq!(name(1, 15), Some("ISF"));
+        assert_eq!(name(1, 16), Some("MOV"));
+        assert_eq!(name(1, 56), Some("TGETB"));
+        assert_eq!(name(1, 57), Some("TSETV"));
+        assert_eq!(name(1, 60), Some("TSETM"));
+        assert_eq!(name(1, 61), Some("CALLM"));
+        assert_eq!(name(1, 92), Some("FUNCCW"));
+        assert_eq!(name(1, 93), None);
+    }
+}
@@ -1,5 +1,76 @@
+use crate::{error::Result, DataHeader, DataReaders, DataTypes, Id, IdCache};
+
 // pub mod material;
+pub mod animation;
+pub mod bones;
+pub mod font;
+pub mod collision;
+pub mod level;
+pub mod lua;
+pub mod navmesh;
+pub mod package;
+pub mod particles;
+pub mod physics;
+pub mod shader;
 pub mod texture;
 pub mod unit;
 pub mod wwise;
-pub mod string;
\ No newline at end of file
+pub mod string;
+
+/// Types whose decoders follow a layout that was guessed and hasn't been checked against game data.
+/// They're only decoded with `--experimental`, and dumped raw otherwise.
+pub fn is_experimental(t: DataTypes) -> bool {
+    matches!(t, DataTypes::Animation | DataTypes::Level)
+}
+
+/// Type-specific details of an asset as label/value pairs, for `info`. `None` for types we can't parse.
+pub fn summarize(
+    cache: &IdCache,
+    bundle_id: &Id,
+    d: &mut DataHeader,
+    r: &mut DataReaders,
+) -> Result<Option<Vec<(&'static str, String)>>> {
+    let summary = match d.type_enum {
+        DataTypes::Texture => texture::summarize_texture(d, r)?,
+        DataTypes::Unit => unit::summarize_unit(d, r)?,
+        DataTypes::WwiseBNK => wwise::summarize_bank(cache, bundle_id, d, r)?,
+        DataTypes::WwiseWem => wwise::summarize_wem(d, r)?,
+        DataTypes::String => string::summarize_strings(d, r)?,
+        DataTypes::Package => package::summarize_package(d, r)?,
+        DataTypes::Lua => lua::summarize_lua(d, r)?,
+        DataTypes::Level => level::summarize_level(cache, d, r)?,
+        DataTypes::Physics | DataTypes::HavokPhysicsProperties | DataTypes::RagdollProfile => {
+            physics::summarize_physics(d, r)?
+        }
+        DataTypes::Animation => animation::summarize_animation(d, r)?,
+        DataTypes::Bones => bones::summarize_bones(d, r)?,
+        DataTypes::Particles => particles::summarize_particles(cache, d, r)?,
+        DataTypes::Shader | DataTypes::ShaderLibrary => shader::summarize_shaders(d, r)?,
+        DataTypes::Font => font::summarize_font(cache, d, r)?,
+        DataTypes::Navdata => navmesh::summarize_navdata(d, r)?,
+        _ => {
+            return Ok(None);
+        }
+    };
+    Ok(Some(summary))
+}
+
+/// Other resources an asset needs, for extracting them along with it. Empty for types we don't know
+/// the references of.
+pub fn dependencies(cache: &IdCache, d: &DataHeader, r: &mut DataReaders) -> Result<Vec<Id>> {
+    match d.type_enum {
+        DataTypes::Particles => particles::particle_dependencies(cache, d, r),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Files to write next to a converted asset, in a folder named after it, as name and contents
+pub type Attachments = Vec<(String, Vec<u8>)>;
+
+/// Attachments that take reading other assets, which `convert` can't do
+pub fn attachments(cache: &IdCache, data_path: &str, d: &DataHeader, r: &mut DataReaders) -> Result<Attachments> {
+    match d.type_enum {
+        DataTypes::Font => font::font_files(cache, data_path, d, r),
+        _ => Ok(Vec::new()),
+    }
+}
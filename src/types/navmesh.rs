@@ -0,0 +1,315 @@
+use std::{collections::BTreeMap, io::Write};
+
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    error::{Error, Result},
+    gltf::Glb,
+    havok::{HavokFile, Value},
+    types::physics::read_physics,
+    DataHeader, DataReaders,
+};
+
+// Face and edge references pack a section index above the index itself
+const INDEX_MASK: u32 = 0x3f_ffff;
+const INVALID: u32 = u32::MAX;
+
+/// A navigation mesh: walkable polygons tagged with an area, and off-mesh links (jumps, ladders and
+/// the like) between faces
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct NavMesh {
+    /// Index of the hkaiNavMesh object in the Havok data
+    pub object: usize,
+    pub vertices: Vec<[f32; 3]>,
+    pub polygons: Vec<Polygon>,
+    pub links: Vec<Link>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Polygon {
+    pub vertices: Vec<u32>,
+    /// First word of the face's user data, which is where the area id goes
+    pub area: u32,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Link {
+    pub from_face: usize,
+    pub to_face: Option<usize>,
+    pub start: [f32; 3],
+    pub end: [f32; 3],
+}
+
+fn member_usize(v: &Value, name: &str) -> Option<usize> {
+    v.get(name)?.as_usize()
+}
+
+/// Face or edge index out of a packed reference, `None` for the invalid one
+fn unpack(v: Option<&Value>) -> Option<usize> {
+    let raw = match v? {
+        Value::Int(i) => *i as u32,
+        Value::UInt(u) => *u as u32,
+        _ => return None,
+    };
+    (raw != INVALID).then_some((raw & INDEX_MASK) as usize)
+}
+
+fn midpoint(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    std::array::from_fn(|i| (a[i] + b[i]) / 2.0)
+}
+
+/// Reads every hkaiNavMesh in the Havok data. A face is a run of edges, each starting at a vertex,
+/// followed by its user edges, which are the off-mesh links. Face data holds a number of ints per
+/// face, the first being the area. A face whose edges run past the edge array is an error.
+pub fn navmeshes(havok: &HavokFile) -> Result<Vec<NavMesh>> {
+    let mut meshes = Vec::new();
+    for o in havok.objects_of("hkaiNavMesh") {
+        let v = &o.value;
+        let vertices: Vec<[f32; 3]> = v
+            .get("vertices")
+            .map(|v| v.floats())
+            .unwrap_or_default()
+            .chunks_exact(4)
+            .map(|c| [c[0], c[1], c[2]])
+            .collect();
+        let edges = v.get("edges").map(Value::as_array).unwrap_or_default();
+        let faces = v.get("faces").map(Value::as_array).unwrap_or_default();
+        let face_data: Vec<u32> = v
+            .get("faceData")
+            .map(|v| v.floats())
+            .unwrap_or_default()
+            .iter()
+            .map(|f| *f as u32)
+            .collect();
+        let striding = v.get("faceDataStriding").and_then(Value::as_usize).unwrap_or(0);
+
+        let vertex = |edge: usize| {
+            member_usize(&edges[edge], "a")
+                .filter(|a| *a < vertices.len())
+                .map(|a| a as u32)
+        };
+        let mut mesh = NavMesh {
+            object: o.index,
+            vertices: vertices.clone(),
+            ..Default::default()
+        };
+        for (i, face) in faces.iter().enumerate() {
+            let start = member_usize(face, "startEdgeIndex").unwrap_or(0);
+            let count = member_usize(face, "numEdges").unwrap_or(0);
+            let end = start.checked_add(count).filter(|end| *end <= edges.len()).ok_or_else(|| {
+                Error::Decode(format!(
+                    "navmesh object {}, face {} has {} edges from {} of {}",
+                    o.index,
+                    i,
+                    count,
+                    start,
+                    edges.len()
+                ))
+            })?;
+            let polygon: Option<Vec<u32>> = (start..end).map(vertex).collect();
+            let area = match striding {
+                0 => 0,
+                s => face_data.get(i * s).copied().unwrap_or(0),
+            };
+            mesh.polygons.push(Polygon {
+                vertices: polygon.unwrap_or_default(),
+                area,
+            });
+
+            let start = member_usize(face, "startUserEdgeIndex").unwrap_or(0);
+            let count = member_usize(face, "numUserEdges").unwrap_or(0);
+            for edge in edges.iter().skip(start).take(count) {
+                let (Some(a), Some(b)) = (member_usize(edge, "a"), member_usize(edge, "b")) else {
+                    continue;
+                };
+                let (Some(&pa), Some(&pb)) = (vertices.get(a), vertices.get(b)) else {
+                    continue;
+                };
+                let start = midpoint(pa, pb);
+                // the link lands on the opposite edge, when it has one in this mesh
+                let end = unpack(edge.get("oppositeEdge"))
+                    .and_then(|e| edges.get(e))
+                    .and_then(|e| Some(midpoint(*vertices.get(member_usize(e, "a")?)?, *vertices.get(member_usize(e, "b")?)?)))
+                    .unwrap_or(start);
+                mesh.links.push(Link {
+                    from_face: i,
+                    to_face: unpack(edge.get("oppositeFace")),
+                    start,
+                    end,
+                });
+            }
+        }
+        meshes.push(mesh);
+    }
+    Ok(meshes)
+}
+
+pub fn read_navdata(d: &DataHeader, r: &mut DataReaders) -> Result<Vec<NavMesh>> {
+    let meshes = navmeshes(&read_physics(d, r)?)?;
+    if meshes.is_empty() {
+        return Err(Error::Decode(format!("navdata {}, no hkaiNavMesh found", d.unk_id)));
+    }
+    Ok(meshes)
+}
+
+pub fn extract_navdata(d: &DataHeader, r: &mut DataReaders) -> Result<(Vec<u8>, Option<String>)> {
+    let meshes = read_navdata(d, r)?;
+    Ok((serde_json::to_vec_pretty(&meshes)?, None))
+}
+
+pub fn summarize_navdata(d: &DataHeader, r: &mut DataReaders) -> Result<Vec<(&'static str, String)>> {
+    let meshes = read_navdata(d, r)?;
+    let mut summary = Vec::new();
+    for m in &meshes {
+        let areas = by_area(m).len();
+        summary.push((
+            "navmesh",
+            format!(
+                "{} vertices, {} polygons in {} areas, {} links",
+                m.vertices.len(),
+                m.polygons.len(),
+                areas,
+                m.links.len()
+            ),
+        ));
+    }
+    Ok(summary)
+}
+
+/// Polygons grouped by area, in area order
+fn by_area(mesh: &NavMesh) -> BTreeMap<u32, Vec<&Polygon>> {
+    let mut areas: BTreeMap<u32, Vec<&Polygon>> = BTreeMap::new();
+    for p in mesh.polygons.iter().filter(|p| p.vertices.len() >= 3) {
+        areas.entry(p.area).or_default().push(p);
+    }
+    areas
+}
+
+/// One group per area with the polygons as they are, and the links as lines
+pub fn navmeshes_to_obj(meshes: &[NavMesh]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut base = 1;
+    for (i, mesh) in meshes.iter().enumerate() {
+        writeln!(out, "o navmesh_{}", i)?;
+        for v in &mesh.vertices {
+            writeln!(out, "v {:?} {:?} {:?}", v[0], v[1], v[2])?;
+        }
+        for (area, polygons) in by_area(mesh) {
+            writeln!(out, "g area_{}", area)?;
+            for p in polygons {
+                let indices: Vec<String> = p.vertices.iter().map(|v| (v + base).to_string()).collect();
+                writeln!(out, "f {}", indices.join(" "))?;
+            }
+        }
+        base += mesh.vertices.len() as u32;
+        if !mesh.links.is_empty() {
+            writeln!(out, "g links")?;
+            for link in &mesh.links {
+                writeln!(out, "v {:?} {:?} {:?}", link.start[0], link.start[1], link.start[2])?;
+                writeln!(out, "v {:?} {:?} {:?}", link.end[0], link.end[1], link.end[2])?;
+                writeln!(out, "l {} {}", base, base + 1)?;
+                base += 2;
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// A mesh per area, triangulated as fans, and the links as a line mesh, under one z up root
+pub fn navmeshes_to_glb(meshes: &[NavMesh]) -> Result<Vec<u8>> {
+    const LINES: u32 = 1;
+    let mut glb = Glb::default();
+    let mut children = Vec::new();
+    for (i, mesh) in meshes.iter().enumerate() {
+        let position = glb.push_vec3(&mesh.vertices, true);
+        for (area, polygons) in by_area(mesh) {
+            let indices: Vec<u32> = polygons
+                .iter()
+                .flat_map(|p| (1..p.vertices.len() - 1).flat_map(|k| [p.vertices[0], p.vertices[k], p.vertices[k + 1]]))
+                .collect();
+            let primitive = json!({ "attributes": { "POSITION": position }, "indices": glb.push_indices(&indices) });
+            let name = format!("navmesh_{}_area_{}", i, area);
+            let m = glb.push_mesh(json!({ "name": name, "primitives": [primitive] }));
+            children.push(glb.push_node(json!({ "name": name, "mesh": m, "extras": { "area": area } })));
+        }
+        if !mesh.links.is_empty() {
+            let points: Vec<[f32; 3]> = mesh.links.iter().flat_map(|l| [l.start, l.end]).collect();
+            let position = glb.push_vec3(&points, true);
+            let primitive = json!({ "attributes": { "POSITION": position }, "mode": LINES });
+            let name = format!("navmesh_{}_links", i);
+            let m = glb.push_mesh(json!({ "name": name, "primitives": [primitive] }));
+            children.push(glb.push_node(json!({ "name": name, "mesh": m })));
+        }
+    }
+
+    // the engine is z up, gltf is y up
+    let root = glb.push_node(json!({
+        "name": "navmesh",
+        "rotation": [-std::f32::consts::FRAC_1_SQRT_2, 0.0, 0.0, std::f32::consts::FRAC_1_SQRT_2],
+        "children": children,
+    }));
+    glb.scene.push(root);
+    glb.to_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::havok::{Format, HavokObject};
+
+    fn object(members: &[(&str, Value)]) -> Value {
+        Value::Object(members.iter().map(|(name, v)| (name.to_string(), v.clone())).collect())
+    }
+
+    fn edge(a: u64, b: u64) -> Value {
+        object(&[("a", Value::UInt(a)), ("b", Value::UInt(b))])
+    }
+
+    /// A hand-built navmesh with one triangle whose edges are given by their first index and count
+    fn havok(start: u64, count: u64) -> HavokFile {
+        let vertices = [0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0];
+        let face = object(&[
+            ("startEdgeIndex", Value::UInt(start)),
+            ("numEdges", Value::UInt(count)),
+            ("startUserEdgeIndex", Value::UInt(3)),
+            ("numUserEdges", Value::UInt(0)),
+        ]);
+        let navmesh = object(&[
+            ("faces", Value::Array(vec![face])),
+            ("edges", Value::Array(vec![edge(0, 1), edge(1, 2), edge(2, 0)])),
+            ("vertices", Value::Array(vertices.iter().map(|f| Value::Float(*f)).collect())),
+            ("faceData", Value::Array(vec![Value::Int(5)])),
+            ("faceDataStriding", Value::Int(1)),
+        ]);
+        HavokFile {
+            format: Format::Tagfile,
+            version: String::new(),
+            types: Vec::new(),
+            objects: vec![HavokObject {
+                index: 1,
+                type_name: "hkaiNavMesh".to_string(),
+                value: navmesh,
+            }],
+        }
+    }
+
+    #[test]
+    fn reads_a_navmesh() {
+        let meshes = navmeshes(&havok(0, 3)).unwrap();
+        assert_eq!(meshes.len(), 1);
+        assert_eq!(meshes[0].vertices.len(), 3);
+        assert_eq!(meshes[0].polygons[0].vertices, vec![0, 1, 2]);
+        assert_eq!(meshes[0].polygons[0].area, 5);
+    }
+
+    #[test]
+    fn rejects_faces_past_the_edges() {
+        assert!(matches!(navmeshes(&havok(1, 3)), Err(Error::Decode(_))));
+    }
+
+    #[test]
+    fn rejects_overflowing_edge_ranges() {
+        assert!(matches!(navmeshes(&havok(u64::MAX, 2)), Err(Error::Decode(_))));
+    }
+}
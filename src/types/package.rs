@@ -0,0 +1,154 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::Cursor,
+};
+
+use binrw::{BinRead, BinReaderExt};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    error::{Error, Result},
+    pndb::Pndb,
+    DataHeader, DataReaders, DataTypes, Id, IdCache,
+};
+
+/// A compiled package: the list of resources its bundle was built from
+#[derive(BinRead, Debug, Default, Clone, Serialize)]
+pub struct Package {
+    pub version: u32,
+    pub resource_count: u32,
+    #[br(count = resource_count)]
+    pub resources: Vec<PackageResource>,
+}
+
+#[derive(BinRead, Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct PackageResource {
+    pub type_id: Id,
+    pub name: Id,
+}
+
+pub fn read_package(d: &DataHeader, r: &mut DataReaders) -> Result<Package> {
+    let buf = d.get_bundle_buf(r.bundle())?;
+    // check the count against the data before binrw tries to allocate it
+    let count = buf.get(4..8).map(|c| u32::from_le_bytes(c.try_into().unwrap()));
+    match count {
+        Some(count) if 8 + count as usize * 16 <= buf.len() => {}
+        _ => {
+            return Err(Error::Decode(format!(
+                "package {}, resource count doesn't fit in {} bytes",
+                d.unk_id,
+                buf.len()
+            )))
+        }
+    }
+    Ok(Cursor::new(buf).read_le()?)
+}
+
+pub fn extract_package(d: &DataHeader, r: &mut DataReaders) -> Result<(Vec<u8>, Option<String>)> {
+    let package = read_package(d, r)?;
+    let resources: Vec<_> = package
+        .resources
+        .iter()
+        .map(|p| {
+            json!({
+                "type": format!("{:?}", p.type_id.as_enum()),
+                "type_id": p.type_id,
+                "name": p.name,
+            })
+        })
+        .collect();
+    let out = json!({
+        "version": package.version,
+        "resources": resources,
+    });
+    Ok((serde_json::to_vec_pretty(&out)?, None))
+}
+
+pub fn summarize_package(d: &DataHeader, r: &mut DataReaders) -> Result<Vec<(&'static str, String)>> {
+    let package = read_package(d, r)?;
+    let mut counts: HashMap<Id, usize> = HashMap::new();
+    for p in &package.resources {
+        *counts.entry(p.type_id).or_default() += 1;
+    }
+    let mut counts: Vec<(Id, usize)> = counts.into_iter().collect();
+    counts.sort_by_key(|c| std::cmp::Reverse(c.1));
+
+    let mut summary = vec![
+        ("version", format!("{:#x}", package.version)),
+        ("resources", package.resource_count.to_string()),
+    ];
+    for (type_id, count) in counts {
+        summary.push(("type", format!("{:?} x{}", type_id.as_enum(), count)));
+    }
+    Ok(summary)
+}
+
+/// Package assets in each bundle, from the cache
+pub fn bundle_packages(cache: &IdCache) -> HashMap<Id, Vec<Id>> {
+    let package_type = DataTypes::Package.as_id();
+    let mut packages: HashMap<Id, Vec<Id>> = HashMap::new();
+    for (bundle, headers) in &cache.bundles {
+        for h in headers.iter().filter(|h| h.type_id == package_type) {
+            packages.entry(*bundle).or_default().push(h.id);
+        }
+    }
+    packages
+}
+
+/// Name of a bundle's package, if the bundle has one and the name database knows it
+pub fn bundle_name<'a>(packages: &HashMap<Id, Vec<Id>>, namedb: &'a Pndb, bundle: &Id) -> Option<&'a str> {
+    // bundles are named after the hash of their package name, so try that first
+    if let Some(name) = namedb.name_database.get(bundle) {
+        return Some(name);
+    }
+    packages
+        .get(bundle)?
+        .iter()
+        .find_map(|p| namedb.name_database.get(p))
+        .map(|n| n.as_str())
+}
+
+/// Differences between what a package declares and what its bundle holds
+#[derive(Debug, Default, Serialize)]
+pub struct PackageCheck {
+    pub bundle: Id,
+    pub package: Id,
+    /// Declared by the package but not in the bundle
+    pub missing: Vec<PackageResource>,
+    /// In the bundle but not declared by the package
+    pub undeclared: Vec<PackageResource>,
+}
+
+pub fn check_package(cache: &IdCache, bundle: &Id, package_id: Id, package: &Package) -> Result<PackageCheck> {
+    let Some(headers) = cache.bundles.get(bundle) else {
+        return Err(Error::UnknownBundle(*bundle));
+    };
+    let held: HashSet<PackageResource> = headers
+        .iter()
+        .map(|h| PackageResource {
+            type_id: h.type_id,
+            name: h.id,
+        })
+        .collect();
+    let declared: HashSet<PackageResource> = package.resources.iter().copied().collect();
+
+    // the package doesn't list itself
+    let itself = PackageResource {
+        type_id: DataTypes::Package.as_id(),
+        name: package_id,
+    };
+    Ok(PackageCheck {
+        bundle: *bundle,
+        package: package_id,
+        missing: package.resources.iter().filter(|p| !held.contains(p)).copied().collect(),
+        undeclared: headers
+            .iter()
+            .map(|h| PackageResource {
+                type_id: h.type_id,
+                name: h.id,
+            })
+            .filter(|p| *p != itself && !declared.contains(p))
+            .collect(),
+    })
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+use crate::{error::Result, DataHeader, DataReaders, DataTypes, Id, IdCache};
+
+/// Materials and textures in the cache, by id
+fn referable(cache: &IdCache) -> HashMap<u64, DataTypes> {
+    let types = [DataTypes::Material, DataTypes::Texture];
+    cache
+        .bundles
+        .values()
+        .flatten()
+        .filter_map(|h| types.iter().find(|t| t.as_id() == h.type_id).map(|t| (u64::from(h.id), *t)))
+        .collect()
+}
+
+/// The materials and textures a particle effect uses. The emitter layout isn't known, so this looks for
+/// their ids anywhere in the data, at 4 byte alignment.
+fn scan_references(cache: &IdCache, buf: &[u8]) -> Vec<(Id, DataTypes)> {
+    let known = referable(cache);
+    let mut found: Vec<(Id, DataTypes)> = Vec::new();
+    for at in (0..buf.len().saturating_sub(7)).step_by(4) {
+        let value = u64::from_le_bytes(buf[at..at + 8].try_into().unwrap());
+        if let Some(t) = known.get(&value) {
+            if !found.iter().any(|(id, _)| u64::from(*id) == value) {
+                found.push((Id::from(value), *t));
+            }
+        }
+    }
+    found
+}
+
+/// Referenced resources to extract along with a particle effect
+pub fn particle_dependencies(cache: &IdCache, d: &DataHeader, r: &mut DataReaders) -> Result<Vec<Id>> {
+    let buf = d.get_bundle_buf(r.bundle())?;
+    Ok(scan_references(cache, &buf).into_iter().map(|(id, _)| id).collect())
+}
+
+/// How many materials and textures an effect refers to, for `info`
+pub fn summarize_particles(cache: &IdCache, d: &DataHeader, r: &mut DataReaders) -> Result<Vec<(&'static str, String)>> {
+    let buf = d.get_bundle_buf(r.bundle())?;
+    let references = scan_references(cache, &buf);
+    let materials = references.iter().filter(|(_, t)| *t == DataTypes::Material).count();
+    Ok(vec![
+        ("materials", materials.to_string()),
+        ("textures", (references.len() - materials).to_string()),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MinimizedIdHeader;
+
+    const MATERIAL: u64 = 0x1122334455667788;
+    const TEXTURE: u64 = 0x99aabbccddeeff00;
+
+    fn u32s(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    fn cache() -> IdCache {
+        let mut cache = IdCache::default();
+        cache.bundles.insert(
+            Id::from(1),
+            vec![
+                MinimizedIdHeader {
+                    id: Id::from(MATERIAL),
+                    type_id: DataTypes::Material.as_id(),
+                    ..Default::default()
+                },
+                MinimizedIdHeader {
+                    id: Id::from(TEXTURE),
+                    type_id: DataTypes::Texture.as_id(),
+                    ..Default::default()
+                },
+            ],
+        );
+        cache
+    }
+
+    #[test]
+    fn finds_references_once() {
+        let mut buf = u32s(&[3, 0x41200000]);
+        buf.extend(u32s(&[MATERIAL as u32, (MATERIAL >> 32) as u32, 5]));
+        buf.extend(u32s(&[TEXTURE as u32, (TEXTURE >> 32) as u32]));
+        buf.extend(u32s(&[MATERIAL as u32, (MATERIAL >> 32) as u32]));
+        let found = scan_references(&cache(), &buf);
+        assert_eq!(
+            found,
+            vec![(Id::from(MATERIAL), DataTypes::Material), (Id::from(TEXTURE), DataTypes::Texture)]
+        );
+    }
+
+    #[test]
+    fn ignores_unknown_ids_and_short_data() {
+        let buf = u32s(&[0xdeadbeef, 0xfeedface, MATERIAL as u32]);
+        assert!(scan_references(&cache(), &buf).is_empty());
+        assert!(scan_references(&cache(), &[]).is_empty());
+    }
+}
@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+
+use crate::{
+    error::{Error, Result},
+    havok::{find_havok, read_havok, HavokFile},
+    DataHeader, DataReaders,
+};
+
+/// Decodes the Havok data of physics, ragdoll, havok_physics_properties and navdata resources. It's usually in
+/// the bundle, behind a small header, but look in the stream data too.
+pub fn read_physics(d: &DataHeader, r: &mut DataReaders) -> Result<HavokFile> {
+    let mut parts = vec![d.get_bundle_buf(r.bundle())?];
+    if d.stream_data_size > 0 {
+        parts.push(d.get_stream_buf(r.stream())?);
+    }
+    for buf in &parts {
+        if let Some(havok) = find_havok(buf) {
+            return read_havok(havok);
+        }
+    }
+    Err(Error::Decode(format!("{:?} {}, no havok data found", d.type_enum, d.unk_id)))
+}
+
+pub fn extract_physics(d: &DataHeader, r: &mut DataReaders) -> Result<(Vec<u8>, Option<String>)> {
+    let havok = read_physics(d, r)?;
+    Ok((serde_json::to_vec_pretty(&havok)?, None))
+}
+
+pub fn summarize_physics(d: &DataHeader, r: &mut DataReaders) -> Result<Vec<(&'static str, String)>> {
+    let havok = read_physics(d, r)?;
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for o in &havok.objects {
+        *counts.entry(o.type_name.as_str()).or_default() += 1;
+    }
+    let mut counts: Vec<(&str, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+    let mut summary = vec![
+        ("havok", format!("{:?} {}", havok.format, havok.version)),
+        ("types", havok.types.len().to_string()),
+        ("objects", havok.objects.len().to_string()),
+    ];
+    for (name, count) in counts {
+        summary.push(("object", format!("{} x{}", name, count)));
+    }
+    Ok(summary)
+}
@@ -0,0 +1,121 @@
+use serde::Serialize;
+
+use crate::{
+    dxbc::{find_containers, reflect, Format, Reflection},
+    error::{Error, Result},
+    types::Attachments,
+    DataHeader, DataReaders,
+};
+
+/// A compiled permutation of a shader, found by its container wherever it is in the resource
+#[derive(Debug, Clone, Serialize)]
+pub struct Permutation {
+    pub index: usize,
+    /// Which part of the resource and where in it the container starts
+    pub part: &'static str,
+    pub offset: usize,
+    pub size: usize,
+    #[serde(skip)]
+    pub blob: Vec<u8>,
+    pub reflection: Option<Reflection>,
+    pub error: Option<String>,
+}
+
+impl Permutation {
+    pub fn file_name(&self) -> String {
+        let extension = match self.reflection.as_ref().map(|r| r.format) {
+            Some(Format::Dxil) => "dxil",
+            _ => "dxbc",
+        };
+        match self.reflection.as_ref().and_then(|r| r.shader_model.as_ref()) {
+            Some(model) => format!("{}_{}.{}", self.index, model, extension),
+            None => format!("{}.{}", self.index, extension),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShaderPackage {
+    pub permutations: Vec<Permutation>,
+    /// Distinct vertex shader inputs, which are the vertex layouts meshes drawn with these shaders use
+    pub vertex_layouts: Vec<Vec<String>>,
+}
+
+/// Splits a shader or shader library into its permutations. The package layout around the containers
+/// isn't known, so every part of the resource is searched for them.
+pub fn read_shaders(d: &DataHeader, r: &mut DataReaders) -> Result<ShaderPackage> {
+    let mut parts = vec![("bundle", d.get_bundle_buf(r.bundle())?)];
+    if d.stream_data_size > 0 {
+        parts.push(("stream", d.get_stream_buf(r.stream())?));
+    }
+    if d.gpu_data_size > 0 {
+        parts.push(("gpu", d.get_gpu_buf(r.gpu())?));
+    }
+
+    let mut permutations = Vec::new();
+    for (part, buf) in &parts {
+        for (offset, blob) in find_containers(buf) {
+            // one broken container shouldn't hide the others
+            let (reflection, error) = match reflect(blob) {
+                Ok(reflection) => (Some(reflection), None),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            permutations.push(Permutation {
+                index: permutations.len(),
+                part,
+                offset,
+                size: blob.len(),
+                blob: blob.to_vec(),
+                reflection,
+                error,
+            });
+        }
+    }
+    if permutations.is_empty() {
+        return Err(Error::Decode(format!("{:?} {}, no shader containers found", d.type_enum, d.unk_id)));
+    }
+
+    let mut vertex_layouts: Vec<Vec<String>> = Vec::new();
+    for reflection in permutations.iter().filter_map(|p| p.reflection.as_ref()) {
+        if !reflection.shader_model.as_ref().is_some_and(|m| m.starts_with("vs_")) {
+            continue;
+        }
+        let layout: Vec<String> = reflection
+            .inputs
+            .iter()
+            .filter(|e| e.system_value == 0)
+            .map(|e| format!("{}{} {}", e.semantic, e.index, e.format))
+            .collect();
+        if !vertex_layouts.contains(&layout) {
+            vertex_layouts.push(layout);
+        }
+    }
+    Ok(ShaderPackage {
+        permutations,
+        vertex_layouts,
+    })
+}
+
+/// The package as json, and each permutation's container to be written as a file of its own
+pub fn extract_shaders(d: &DataHeader, r: &mut DataReaders) -> Result<(Vec<u8>, Attachments)> {
+    let package = read_shaders(d, r)?;
+    let json = serde_json::to_vec_pretty(&package)?;
+    Ok((json, package.permutations.into_iter().map(|p| (p.file_name(), p.blob)).collect()))
+}
+
+pub fn summarize_shaders(d: &DataHeader, r: &mut DataReaders) -> Result<Vec<(&'static str, String)>> {
+    let package = read_shaders(d, r)?;
+    let dxil = package
+        .permutations
+        .iter()
+        .filter(|p| p.reflection.as_ref().is_some_and(|r| r.format == Format::Dxil))
+        .count();
+    let broken = package.permutations.iter().filter(|p| p.error.is_some()).count();
+    let mut summary = vec![
+        ("permutations", format!("{} ({} dxil, {} unreadable)", package.permutations.len(), dxil, broken)),
+    ];
+    for layout in &package.vertex_layouts {
+        summary.push(("vertex layout", layout.join(", ")));
+    }
+    Ok(summary)
+}
@@ -2,7 +2,7 @@ use std::{collections::HashMap, io::{Seek, SeekFrom}};
 
 use binrw::{BinRead, BinReaderExt, NullString};
 
-use crate::{DataHeader, DataReaders};
+use crate::{error::Result, DataHeader, DataReaders};
 
 
 #[derive(BinRead, Debug, Default, Clone)]
@@ -22,10 +22,20 @@ pub struct StringFile {
     // here -> EOF: null-terminated strings
 }
 
+pub fn summarize_strings(d: &DataHeader, r: &mut DataReaders) -> Result<Vec<(&'static str, String)>> {
+    let bundle = r.bundle();
+    bundle.seek(SeekFrom::Start(d.data_offset))?;
+    let h: StringFile = bundle.read_le()?;
+    Ok(vec![
+        ("strings", h.string_count.to_string()),
+        ("language", format!("{:#x}", h.language_id)),
+    ])
+}
+
 pub fn extract_strings(
     d: &mut DataHeader,
     r: &mut DataReaders,
-) -> Result<(Vec<u8>, Option<String>), anyhow::Error> {
+) -> Result<(Vec<u8>, Option<String>)> {
     let bundle = &mut r.bundle();
     bundle.seek(SeekFrom::Start(d.data_offset))?;
 
@@ -33,10 +43,7 @@ pub fn extract_strings(
 
     let mut strings: HashMap<u32, String> = Default::default();
 
-    for i in 0..h.string_count {
-        let id = *h.string_ids.get(i as usize).unwrap();
-        let offset = *h.string_offsets.get(i as usize).unwrap();
-
+    for (&id, &offset) in h.string_ids.iter().zip(h.string_offsets.iter()) {
         bundle.seek(SeekFrom::Start(d.data_offset + offset as u64))?;
         let string: NullString = bundle.read_le()?;
         // println!("{}: {}", id, string);
@@ -1,42 +1,169 @@
-use std::io::{Read, Seek, SeekFrom};
-
-use crate::{DataHeader, DataReaders};
+use crate::{
+    error::{Error, Result},
+    DataHeader, DataReaders,
+};
 
 pub fn extract_texture(
     d: &mut DataHeader,
     r: &mut DataReaders,
-) -> Result<(Vec<u8>, Option<String>), anyhow::Error> {
+) -> Result<(Vec<u8>, Option<String>)> {
     let mut out_buf: Vec<u8> = Vec::new();
-    let bundle = &mut r.bundle();
     // TODO: figure out what 0 -> c0 is for
-    bundle.seek(SeekFrom::Start(d.data_offset + 0xc0))?;
-    let mut dds_header = vec![0u8; 0x94];
-    bundle.read_exact(&mut dds_header)?;
+    let dds_header = d.get_bundle_range(r.bundle(), 0xc0, 0x94)?;
     out_buf.extend_from_slice(&dds_header);
     if d.stream_data_size > 0 {
-        let sf = &mut r.stream();
-        if sf.is_none() {
-            return Err(anyhow::anyhow!("Stream file referenced but not found."));
-        }
-        if let Some(ref mut sf) = sf {
-            sf.seek(SeekFrom::Start(d.stream_data_offset as u64))?;
-            let mut data = vec![0u8; d.stream_data_size.try_into().unwrap()];
-            sf.read_exact(&mut data)?;
-            out_buf.extend_from_slice(&data);
+        out_buf.extend_from_slice(&d.get_stream_buf(r.stream())?);
+    } else {
+        out_buf.extend_from_slice(&d.get_gpu_buf(r.gpu())?);
+    }
+    Ok((out_buf, None))
+}
+
+/// Dimensions and format from the dds header stored in the bundle
+pub fn summarize_texture(d: &DataHeader, r: &mut DataReaders) -> Result<Vec<(&'static str, String)>> {
+    let dds = d.get_bundle_range(r.bundle(), 0xc0, 0x94)?;
+    if &dds[0..4] != b"DDS " {
+        return Err(Error::BadMagic {
+            pos: d.data_offset + 0xc0,
+            found: format!("{:x?}", &dds[0..4]),
+        });
+    }
+    let read_u32 = |off: usize| u32::from_le_bytes(dds[off..off + 4].try_into().unwrap());
+    let format = if &dds[0x54..0x58] == b"DX10" {
+        format!("dxgi {}", read_u32(0x80))
+    } else {
+        String::from_utf8_lossy(&dds[0x54..0x58]).into_owned()
+    };
+    Ok(vec![
+        ("size", format!("{}x{}", read_u32(0x10), read_u32(0xC))),
+        ("mips", read_u32(0x1C).to_string()),
+        ("format", format),
+        ("data in", if d.stream_data_size > 0 { "stream" } else { "gpu_resources" }.to_string()),
+    ])
+}
+
+/// Decodes the top mip of a dds (as written by `extract_texture`) into an rgba png.
+pub fn dds_to_png(dds: &[u8]) -> Result<Vec<u8>> {
+    if dds.len() < 0x80 || &dds[0..4] != b"DDS " {
+        return Err(Error::BadMagic {
+            pos: 0,
+            found: format!("{:x?}", &dds[..dds.len().min(4)]),
+        });
+    }
+    let read_u32 = |off: usize| u32::from_le_bytes(dds[off..off + 4].try_into().unwrap());
+    let height = read_u32(0xC) as usize;
+    let width = read_u32(0x10) as usize;
+    let four_cc = &dds[0x54..0x58];
+
+    let (format, data) = if four_cc == b"DX10" {
+        if dds.len() < 0x94 {
+            return Err(Error::Decode("dds, missing dx10 header".to_string()));
         }
+        (read_u32(0x80), &dds[0x94..])
     } else {
-        let gf = &mut r.gpu();
-        if gf.is_none() {
-            return Err(anyhow::anyhow!(
-                "GPU Resource file referenced but not found."
-            ));
+        let format = match four_cc {
+            b"DXT1" => 71,
+            b"DXT3" => 74,
+            b"DXT5" => 77,
+            b"ATI1" | b"BC4U" => 80,
+            b"ATI2" | b"BC5U" => 83,
+            _ => 0,
+        };
+        (format, &dds[0x80..])
+    };
+
+    // check the header's size against the data before allocating for it
+    let blocks = width.div_ceil(4).checked_mul(height.div_ceil(4));
+    let needed = match format {
+        70..=72 | 79..=81 => blocks.and_then(|b| b.checked_mul(8)),
+        73..=78 | 82..=84 | 94..=99 => blocks.and_then(|b| b.checked_mul(16)),
+        27..=32 | 87 | 88 | 90..=93 => width.checked_mul(height).and_then(|p| p.checked_mul(4)),
+        _ => return Err(Error::UnsupportedType(format!("dxgi format {}", format))),
+    };
+    if needed.is_none_or(|n| n > data.len()) {
+        return Err(Error::Decode(format!("{}x{} image, not enough data", width, height)));
+    }
+
+    // decoders write bgra packed into u32s
+    let mut image = vec![0u32; width * height];
+    let decoded = match format {
+        70..=72 => texture2ddecoder::decode_bc1(data, width, height, &mut image),
+        73..=75 => texture2ddecoder::decode_bc2(data, width, height, &mut image),
+        76..=78 => texture2ddecoder::decode_bc3(data, width, height, &mut image),
+        79..=81 => texture2ddecoder::decode_bc4(data, width, height, &mut image),
+        82..=84 => texture2ddecoder::decode_bc5(data, width, height, &mut image),
+        94 | 95 => texture2ddecoder::decode_bc6_unsigned(data, width, height, &mut image),
+        96 => texture2ddecoder::decode_bc6_signed(data, width, height, &mut image),
+        97..=99 => texture2ddecoder::decode_bc7(data, width, height, &mut image),
+        // 89 is R10G10B10_XR_BIAS_A2, which isn't 8 bits a channel
+        27..=32 | 87 | 88 | 90..=93 => {
+            // R8G8B8A8 vs B8G8R8A8
+            let swap = format < 87;
+            for (px, c) in image.iter_mut().zip(data.chunks_exact(4)) {
+                *px = if swap {
+                    u32::from_le_bytes([c[2], c[1], c[0], c[3]])
+                } else {
+                    u32::from_le_bytes([c[0], c[1], c[2], c[3]])
+                };
+            }
+            Ok(())
         }
-        if let Some(ref mut gf) = gf {
-            gf.seek(SeekFrom::Start(d.gpu_data_offset))?;
-            let mut data = vec![0u8; d.gpu_data_size.try_into().unwrap()];
-            gf.read_exact(&mut data)?;
-            out_buf.extend_from_slice(&data);
+        _ => return Err(Error::UnsupportedType(format!("dxgi format {}", format))),
+    };
+    if let Err(e) = decoded {
+        return Err(Error::Decode(format!("texture: {}", e)));
+    }
+
+    let mut rgba: Vec<u8> = Vec::with_capacity(image.len() * 4);
+    for px in image {
+        let [b, g, r, a] = px.to_le_bytes();
+        rgba.extend_from_slice(&[r, g, b, a]);
+    }
+
+    let mut out_buf: Vec<u8> = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out_buf, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&rgba)?;
+    writer.finish()?;
+    Ok(out_buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A dds with a dx10 header, hand-built with only the fields `dds_to_png` reads
+    fn dds(width: u32, height: u32, format: u32, data: &[u8]) -> Vec<u8> {
+        let mut dds = vec![0u8; 0x94];
+        dds[0..4].copy_from_slice(b"DDS ");
+        dds[0xC..0x10].copy_from_slice(&height.to_le_bytes());
+        dds[0x10..0x14].copy_from_slice(&width.to_le_bytes());
+        dds[0x54..0x58].copy_from_slice(b"DX10");
+        dds[0x80..0x84].copy_from_slice(&format.to_le_bytes());
+        dds.extend_from_slice(data);
+        dds
+    }
+
+    #[test]
+    fn decodes_8_bit_formats() {
+        for format in [28, 87, 88, 91] {
+            let png = dds_to_png(&dds(2, 2, format, &[0x80; 16])).unwrap();
+            assert_eq!(&png[1..4], b"PNG");
         }
     }
-    Ok((out_buf, None))
+
+    #[test]
+    fn rejects_xr_bias() {
+        assert!(matches!(dds_to_png(&dds(2, 2, 89, &[0x80; 16])), Err(Error::UnsupportedType(_))));
+    }
+
+    #[test]
+    fn rejects_short_data() {
+        assert!(matches!(dds_to_png(&dds(2, 2, 87, &[0x80; 15])), Err(Error::Decode(_))));
+        assert!(matches!(dds_to_png(&dds(8, 8, 71, &[0; 31])), Err(Error::Decode(_))));
+        assert!(matches!(dds_to_png(&dds(u32::MAX, u32::MAX, 87, &[])), Err(Error::Decode(_))));
+        assert!(dds_to_png(b"DDS ").is_err());
+    }
 }
@@ -1,24 +1,34 @@
-use std::{collections::HashMap, io::{BufReader, Cursor, Read, Seek, SeekFrom, Write}};
+use std::{collections::HashMap, io::{BufReader, Cursor, Seek, SeekFrom, Write}};
 
 use binrw::{BinRead, BinReaderExt};
 use half::f16;
+use serde_json::json;
 
-use crate::{DataHeader, DataReaders, Id, IdCache, U32IdMap};
+use crate::{
+    error::{Error, Result},
+    gltf::Glb,
+    DataHeader, DataReaders, Id, IdCache, U32IdMap,
+};
 
 pub fn extract_unit(
     _cache: &IdCache,
     d: &mut DataHeader,
     readers: &mut DataReaders
-) -> Result<(Vec<u8>, Option<String>), anyhow::Error> {
-    let mut out_buf: Vec<u8> = Vec::new();
-    let r = readers.bundle();
-    r.seek(SeekFrom::Start(d.data_offset))?;
-    let mut data = vec![0u8; d.data_size.try_into().unwrap()];
-    r.read_exact(&mut data)?;
+) -> Result<(Vec<u8>, Option<String>)> {
+    let lods = read_unit(d, readers)?;
+    Ok((unit_to_obj(d, &lods)?, None))
+}
+
+/// Decodes every LOD of a unit into vertices and per-part faces.
+pub fn read_unit(
+    d: &DataHeader,
+    readers: &mut DataReaders
+) -> Result<Vec<UnitLod>> {
+    let mut lods: Vec<UnitLod> = Vec::new();
+    let data = d.get_bundle_buf(readers.bundle())?;
     let mut mr = BufReader::new(Cursor::new(data));
     let mh: UnitHeader = mr.read_le()?;
     let mut mesh: Mesh = Default::default();
-    println!("{:#?}", mh);
     for i in 0..mh.part_count {
         let mut sub_parts: HashMap<u32, PartDef> = Default::default();
 
@@ -53,29 +63,25 @@ pub fn extract_unit(
             });
         }
     }
-    for i in 0..mh.offsets.len() {
+    for (i, lod_offset) in mh.offsets.iter().enumerate() {
         // println!("{:?}", i);
-        let off = mh.lod_offset + mh.offsets.get(i).unwrap() + 0x160;
+        let off = mh.lod_offset + lod_offset + 0x160;
         mr.seek(SeekFrom::Start(off.into()))?;
 
         let ml: MeshLod = mr.read_le()?;
-        println!("{:#?}", ml);
 
-        // let size = ml.vtx_count * ml.stride as u32;
-        let mut data = vec![0u8; ml.vtx_size as usize];
-        let gf = readers.gpu();
-        if gf.is_none() {
-            panic!("GPU Resource file referenced but not found.");
-        }
-        if let Some(ref mut gf) = gf {
-            gf.seek(SeekFrom::Start(d.gpu_data_offset + ml.vtx_offset as u64))?;
-            gf.read_exact(&mut data)?;
+        let mut lod: UnitLod = Default::default();
+
+        if ml.stride <= 0 && ml.vtx_count > 0 || ml.vtx_count as u64 * ml.stride.max(0) as u64 > ml.vtx_size as u64 {
+            return Err(Error::Decode(format!(
+                "unit {}, {} vertices of {} bytes don't fit in {} bytes",
+                d.unk_id, ml.vtx_count, ml.stride, ml.vtx_size
+            )));
         }
+        let data = d.get_gpu_range(readers.gpu(), ml.vtx_offset as u64, ml.vtx_size as usize)?;
         let mut gr = BufReader::new(Cursor::new(data));
         for _ in 0..ml.vtx_count {
             let mut vtx: Vertex = Default::default();
-            // let mut vpos = Vec::new();
-            // let mut vt = Vec::new();
             // TODO: this is no good and bad. figure out what Diver does (something like Hellextractor's method?)
             match ml.stride {
                 16 => {
@@ -90,8 +96,6 @@ pub fn extract_unit(
                     let uv: HalfVector2 = gr.read_le()?;
                     vtx.pos = pos;
                     vtx.uv = uv.into();
-                    // vpos.push(pos);
-                    // vt.push(uv);
                 }
                 24 => {
                     let pos: Vector3 = gr.read_le()?;
@@ -100,8 +104,6 @@ pub fn extract_unit(
                     let _uv2: HalfVector2 = gr.read_le()?;
                     vtx.pos = pos;
                     vtx.uv = uv.into();
-                    // vpos.push(pos);
-                    // vt.push(uv);
                 }
                 28 => {
                     let pos: Vector3 = gr.read_le()?;
@@ -110,8 +112,6 @@ pub fn extract_unit(
                     // this just has a single float?
                     vtx.pos = pos;
                     vtx.uv = uv.into();
-                    // vpos.push(pos);
-                    // vt.push(uv);
                     gr.seek_relative(0x8)?;
                 }
                 32 => {
@@ -169,113 +169,36 @@ pub fn extract_unit(
                 }
                 _ => {}
             }
-            mesh.vertices.push(vtx);
+            lod.vertices.push(vtx);
         }
 
-        let mut data = vec![0u8; ml.idx_size as usize];
-        if gf.is_none() {
-            panic!("GPU Resource file referenced but not found.");
-        }
-        if let Some(ref mut gf) = gf {
-            gf.seek(SeekFrom::Start(d.gpu_data_offset + ml.idx_offset as u64))?;
-            gf.read_exact(&mut data)?;
-        }
+        let data = d.get_gpu_range(readers.gpu(), ml.idx_offset as u64, ml.idx_size as usize)?;
         let mut gr = BufReader::new(Cursor::new(data));
-        let idx_stride = ml.idx_size / ml.idx_count;
-        // println!("{:#?} {:?}", ml, idx_count);
-        // for _ in 0..idx_count {
-        //     // let mut dat = gr.read_le::<[u16;3]>()?.to_vec();
-        //     mesh.indices.push(gr.read_le::<[u16; 3]>()?.to_vec());
-        // }
-        // println!("{:x?}: {:#?}", d.unk_id, mh);
+        let idx_stride = ml.idx_size.checked_div(ml.idx_count).unwrap_or(0);
 
-        // if !mesh.parts.contains_key(i) {
-        //     continue;
-        // }
-        let part_defs = mesh.parts.get(&(i as i32)).unwrap();
-        let mut a = 0;
-        // let mut attrs_writer = fbx_writer.new_node("vertices").unwrap();
-        // for vert in mesh.vert_pos.clone().into_iter() {
-        //     attrs_writer.append_arr_f32_from_iter(ArrayAttributeEncoding::Direct, vert.into_iter()).unwrap();
-        // }
-        // fbx_writer.close_node().unwrap();
-        for vert in mesh.vertices.iter() {
-            // let vert = mh.vertices.get(i).unwrap();
-            a += 1;
-            out_buf.write_all(format!("i {:?}\n", a).as_bytes())?;
-            out_buf.write_all(
-                format!("v {:?} {:?} {:?}\n", vert.pos.x, vert.pos.y, vert.pos.z).as_bytes(),
-            )?;
-            out_buf.write_all(format!("vt {:?} {:?}\n", vert.uv.x, vert.uv.y).as_bytes())?;
-            out_buf.write_all(
-                format!("vn {:?} {:?} {:?}\n", vert.norm.x, vert.norm.y, vert.norm.z).as_bytes(),
-            )?;
-        }
         // part code from https://github.com/MontagueM/helldivers2
         // dont know if its functioning for everything yet, helmet model is messed up (8C12FFEFB4D020BC)
-
-        // out_buf.write_all(format!("o {:?}_{}_{:x?}\n", d.unk4c, d.unk_id, i).as_bytes())?;
-        // println!("o {:?}_{}_{:x?}", d.unk4c, d.unk_id, i);
-
+        let part_defs = mesh.parts.get(&(i as i32)).map(|p| p.as_slice()).unwrap_or_default();
         for part in part_defs {
             // println!("{:#?}", part);
-            out_buf
-                .write_all(format!("o {:?}_{}_{:x?}\n", d.unk4c, d.unk_id, part.id).as_bytes())?;
-            let mut local_indices: Vec<Vec<u64>> = Default::default();
+            let mut faces: Vec<[u64; 3]> = Default::default();
             for a in (0..part.def.idx_count).step_by(3) {
-                // for a in (0..ml.idx_count).step_by(3) {
                 let b = a * idx_stride as i32;
                 gr.seek(SeekFrom::Start(b as u64))?;
-                // let mut vec1 = gr.read_le::<[u16; 3]>()?.to_vec();
-                let mut vec1: Vec<u64> = Vec::new();
-                match idx_stride {
-                    1 => {
-                        vec1 = gr.read_le::<[u8; 3]>()?.iter().map(|x| *x as u64).collect();
-                    }
-                    2 => {
-                        vec1 = gr
-                            .read_le::<[u16; 3]>()?
-                            .iter()
-                            .map(|x| *x as u64)
-                            .collect();
-                    }
-                    4 => {
-                        vec1 = gr
-                            .read_le::<[u32; 3]>()?
-                            .iter()
-                            .map(|x| *x as u64)
-                            .collect();
-                    }
-                    8 => {
-                        vec1 = gr.read_le::<[u64; 3]>()?.to_vec();
-                    }
-                    _ => {}
-                }
-                let mut vec2 = Vec::new();
-                for b in vec1.iter_mut() {
-                    // vec2.push(*b + ml.vtx_offset as u64);
-                    vec2.push(*b + part.def.vtx_offset as u64);
-                }
-                local_indices.push(vec2);
-            }
-
-            for idx in local_indices {
-                out_buf.write_all(
-                    format!(
-                        "f {:?}/{:?}/{:?} {:?}/{:?}/{:?} {:?}/{:?}/{:?}\n",
-                        idx[0] + 1,
-                        idx[0] + 1,
-                        idx[0] + 1,
-                        idx[1] + 1,
-                        idx[1] + 1,
-                        idx[1] + 1,
-                        idx[2] + 1,
-                        idx[2] + 1,
-                        idx[2] + 1
-                    )
-                    .as_bytes(),
-                )?;
+                let vec1: [u64; 3] = match idx_stride {
+                    1 => gr.read_le::<[u8; 3]>()?.map(|x| x as u64),
+                    2 => gr.read_le::<[u16; 3]>()?.map(|x| x as u64),
+                    4 => gr.read_le::<[u32; 3]>()?.map(|x| x as u64),
+                    8 => gr.read_le::<[u64; 3]>()?,
+                    _ => continue,
+                };
+                faces.push(vec1.map(|x| x + part.def.vtx_offset as u64));
             }
+            lod.parts.push(LodPart {
+                id: part.id,
+                material_id: part.material_id,
+                faces,
+            });
         }
         // TODO: materials + textures
         // for part in part_defs {
@@ -286,8 +209,301 @@ pub fn extract_unit(
 
         //     }
         // }
+        lods.push(lod);
+    }
+    Ok(lods)
+}
+
+pub fn summarize_unit(d: &DataHeader, r: &mut DataReaders) -> Result<Vec<(&'static str, String)>> {
+    let lods = read_unit(d, r)?;
+    let mut summary = vec![("lods", lods.len().to_string())];
+    for lod in &lods {
+        summary.push((
+            "lod",
+            format!(
+                "{} vertices, {} parts, {} faces",
+                lod.vertices.len(),
+                lod.parts.len(),
+                lod.parts.iter().map(|p| p.faces.len()).sum::<usize>()
+            ),
+        ));
+    }
+    Ok(summary)
+}
+
+pub fn unit_to_obj(d: &DataHeader, lods: &[UnitLod]) -> Result<Vec<u8>> {
+    let mut out_buf: Vec<u8> = Vec::new();
+    // obj indices are global to the file, so every lod's faces are offset past the previous lods' vertices
+    let mut base = 0;
+    for lod in lods {
+        for (a, vert) in lod.vertices.iter().enumerate() {
+            out_buf.write_all(format!("i {:?}\n", a + 1).as_bytes())?;
+            out_buf.write_all(
+                format!("v {:?} {:?} {:?}\n", vert.pos.x, vert.pos.y, vert.pos.z).as_bytes(),
+            )?;
+            out_buf.write_all(format!("vt {:?} {:?}\n", vert.uv.x, vert.uv.y).as_bytes())?;
+            out_buf.write_all(
+                format!("vn {:?} {:?} {:?}\n", vert.norm.x, vert.norm.y, vert.norm.z).as_bytes(),
+            )?;
+        }
+        for part in &lod.parts {
+            out_buf
+                .write_all(format!("o {:?}_{}_{:x?}\n", d.unk4c, d.unk_id, part.id).as_bytes())?;
+            for idx in &part.faces {
+                let [x, y, z] = idx.map(|i| i + base + 1);
+                out_buf.write_all(
+                    format!("f {x:?}/{x:?}/{x:?} {y:?}/{y:?}/{y:?} {z:?}/{z:?}/{z:?}\n").as_bytes(),
+                )?;
+            }
+        }
+        base += lod.vertices.len() as u64;
+    }
+    Ok(out_buf)
+}
+
+/// Builds a binary glTF with one mesh per lod and one primitive per part. Only the first lod is
+/// placed in the scene, the rest are left as unreferenced meshes.
+pub fn unit_to_glb(d: &DataHeader, lods: &[UnitLod]) -> Result<Vec<u8>> {
+    let mut glb = Glb::default();
+    for (i, lod) in lods.iter().enumerate() {
+        // a mesh needs at least one primitive, so lods without faces are left out
+        let Some(mesh) = push_lod_mesh(&mut glb, &format!("{}_lod{}", d.unk_id, i), lod) else {
+            continue;
+        };
+        let node = glb.push_node(json!({ "name": format!("{}_lod{}", d.unk_id, i), "mesh": mesh }));
+        if i == 0 {
+            glb.scene.push(node);
+        }
     }
-    Ok((out_buf, None))
+    glb.to_bytes()
+}
+
+/// Adds a lod as a mesh with one primitive per part, returning the mesh index. Lods without a
+/// usable face add nothing and return None.
+pub fn push_lod_mesh(glb: &mut Glb, name: &str, lod: &UnitLod) -> Option<usize> {
+    // a face with any index out of range is dropped whole, keeping the rest aligned
+    let parts: Vec<(&LodPart, Vec<u32>)> = lod
+        .parts
+        .iter()
+        .map(|part| {
+            let indices: Vec<u32> = part
+                .faces
+                .iter()
+                .filter(|face| face.iter().all(|x| (*x as usize) < lod.vertices.len()))
+                .flat_map(|face| face.map(|x| x as u32))
+                .collect();
+            (part, indices)
+        })
+        .filter(|(_, indices)| !indices.is_empty())
+        .collect();
+    if parts.is_empty() {
+        return None;
+    }
+
+    let positions: Vec<[f32; 3]> = lod.vertices.iter().map(|v| [v.pos.x, v.pos.y, v.pos.z]).collect();
+    let normals: Vec<[f32; 3]> = lod.vertices.iter().map(|v| [v.norm.x, v.norm.y, v.norm.z]).collect();
+    let uvs: Vec<[f32; 2]> = lod.vertices.iter().map(|v| [v.uv.x, v.uv.y]).collect();
+    let position = glb.push_vec3(&positions, true);
+    let normal = glb.push_vec3(&normals, false);
+    let uv = glb.push_vec2(&uvs);
+
+    let mut primitives = Vec::new();
+    for (part, indices) in parts {
+        primitives.push(json!({
+            "attributes": { "POSITION": position, "NORMAL": normal, "TEXCOORD_0": uv },
+            "indices": glb.push_indices(&indices),
+            "extras": { "part_id": format!("{:x}", part.id), "material": part.material_id },
+        }));
+    }
+    Some(glb.push_mesh(json!({
+        "name": name,
+        "primitives": primitives,
+    })))
+}
+
+// Where the unit header keeps its bones resource and the offset of its node transforms
+const BONES_ID_OFFSET: usize = 0x10;
+const TRANSFORMS_OFFSET: usize = 0x3C;
+
+/// The unit's scene graph, which animations drive
+#[derive(Debug, Default, Clone)]
+pub struct Skeleton {
+    /// Bones resource with the node names
+    pub bones: Id,
+    pub nodes: Vec<Node>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Node {
+    /// Hash of the node name, see `hash::hash32`
+    pub name_hash: u32,
+    pub parent: Option<usize>,
+    pub translation: [f32; 3],
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+}
+
+/// Reads the node transforms: a count and 12 bytes of padding, then per node a local transform (3x3
+/// rotation, translation, scale and a spare float), a world matrix, the parent index and the name hash.
+pub fn read_skeleton(d: &DataHeader, readers: &mut DataReaders) -> Result<Skeleton> {
+    let data = d.get_bundle_buf(readers.bundle())?;
+    let err = |what: &str| Error::Decode(format!("unit {} skeleton, {}", d.unk_id, what));
+    let u32_at = |at: usize| {
+        data.get(at..at + 4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+            .ok_or_else(|| err("runs past the end"))
+    };
+    let f32_at = |at: usize| u32_at(at).map(f32::from_bits);
+
+    let bones = Id::from(
+        u32_at(BONES_ID_OFFSET)? as u64 | (u32_at(BONES_ID_OFFSET + 4)? as u64) << 32,
+    );
+    let start = u32_at(TRANSFORMS_OFFSET)? as usize;
+    let count = u32_at(start)? as usize;
+    // 64 byte local and world transforms, 4 byte parent entry and name hash per node
+    if count == 0 || start + 16 + count * 136 > data.len() {
+        return Err(err(&format!("{} nodes don't fit", count)));
+    }
+    let locals = start + 16;
+    let parents = locals + count * 128;
+    let names = parents + count * 4;
+
+    let mut nodes = Vec::with_capacity(count);
+    for i in 0..count {
+        let at = locals + i * 64;
+        let f = |n: usize| f32_at(at + n * 4);
+        let rows = [[f(0)?, f(1)?, f(2)?], [f(3)?, f(4)?, f(5)?], [f(6)?, f(7)?, f(8)?]];
+        let parent = (u32_at(parents + i * 4)? & 0xffff) as usize;
+        nodes.push(Node {
+            name_hash: u32_at(names + i * 4)?,
+            parent: (parent != 0xffff).then_some(parent),
+            translation: [f(9)?, f(10)?, f(11)?],
+            rotation: crate::gltf::matrix_to_quat(&rows),
+            scale: [f(12)?, f(13)?, f(14)?],
+        });
+        if nodes[i].parent.is_some_and(|p| p >= i) {
+            return Err(err(&format!("node {} comes before its parent", i)));
+        }
+    }
+    Ok(Skeleton { bones, nodes })
+}
+
+/// Column major 4x4 matrix of a translation, rotation and scale
+fn trs_matrix(t: [f32; 3], [x, y, z, w]: [f32; 4], s: [f32; 3]) -> [f32; 16] {
+    let r = [
+        [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y + z * w), 2.0 * (x * z - y * w)],
+        [2.0 * (x * y - z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z + x * w)],
+        [2.0 * (x * z + y * w), 2.0 * (y * z - x * w), 1.0 - 2.0 * (x * x + y * y)],
+    ];
+    let mut m = [0.0; 16];
+    for (col, axis) in r.iter().enumerate() {
+        for row in 0..3 {
+            m[col * 4 + row] = axis[row] * s[col];
+        }
+    }
+    m[12..15].copy_from_slice(&t);
+    m[15] = 1.0;
+    m
+}
+
+fn mat_mul(a: &[f32; 16], b: &[f32; 16]) -> [f32; 16] {
+    let mut m = [0.0; 16];
+    for col in 0..4 {
+        for row in 0..4 {
+            m[col * 4 + row] = (0..4).map(|k| a[k * 4 + row] * b[col * 4 + k]).sum();
+        }
+    }
+    m
+}
+
+/// Inverse of an affine matrix, through the inverse of its 3x3 part
+fn affine_inverse(m: &[f32; 16]) -> [f32; 16] {
+    let a = |row: usize, col: usize| m[col * 4 + row];
+    let det = a(0, 0) * (a(1, 1) * a(2, 2) - a(2, 1) * a(1, 2)) - a(0, 1) * (a(1, 0) * a(2, 2) - a(1, 2) * a(2, 0))
+        + a(0, 2) * (a(1, 0) * a(2, 1) - a(1, 1) * a(2, 0));
+    if det.abs() < f32::EPSILON {
+        return trs_matrix([0.0; 3], [0.0, 0.0, 0.0, 1.0], [1.0; 3]);
+    }
+    let mut inv = [0.0; 16];
+    for row in 0..3 {
+        for col in 0..3 {
+            // cofactor of the transposed element
+            let (r0, r1) = ([1, 0, 0][col], [2, 2, 1][col]);
+            let (c0, c1) = ([1, 0, 0][row], [2, 2, 1][row]);
+            let minor = a(r0, c0) * a(r1, c1) - a(r0, c1) * a(r1, c0);
+            let sign = if (row + col) % 2 == 0 { 1.0 } else { -1.0 };
+            inv[col * 4 + row] = sign * minor / det;
+        }
+    }
+    for row in 0..3 {
+        inv[12 + row] = -(0..3).map(|k| inv[k * 4 + row] * m[12 + k]).sum::<f32>();
+    }
+    inv[15] = 1.0;
+    inv
+}
+
+/// Adds a node per skeleton node and a skin over them, returning the joint nodes and the root joints.
+/// Names come from `names` by hash, falling back to the hash itself.
+pub fn push_skeleton(glb: &mut Glb, skeleton: &Skeleton, names: &HashMap<u32, String>) -> (Vec<usize>, Vec<usize>) {
+    let first = glb.nodes.len();
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); skeleton.nodes.len()];
+    let mut roots = Vec::new();
+    for (i, node) in skeleton.nodes.iter().enumerate() {
+        match node.parent {
+            Some(parent) => children[parent].push(first + i),
+            None => roots.push(first + i),
+        }
+    }
+
+    let mut world: Vec<[f32; 16]> = Vec::with_capacity(skeleton.nodes.len());
+    let mut joints = Vec::with_capacity(skeleton.nodes.len());
+    for (node, children) in skeleton.nodes.iter().zip(children) {
+        let local = trs_matrix(node.translation, node.rotation, node.scale);
+        // parents always come first
+        world.push(match node.parent {
+            Some(parent) => mat_mul(&world[parent], &local),
+            None => local,
+        });
+        let mut json = json!({
+            "name": names.get(&node.name_hash).cloned().unwrap_or(format!("{:08x}", node.name_hash)),
+            "translation": node.translation,
+            "rotation": node.rotation,
+            "scale": node.scale,
+        });
+        if !children.is_empty() {
+            json["children"] = json!(children);
+        }
+        joints.push(glb.push_node(json));
+    }
+
+    let inverse_binds: Vec<u8> = world.iter().flat_map(affine_inverse).flat_map(|f| f.to_le_bytes()).collect();
+    let view = glb.push_view(&inverse_binds, None);
+    let accessor = glb.push_accessor(json!({
+        "bufferView": view,
+        "componentType": 5126,
+        "count": joints.len(),
+        "type": "MAT4",
+    }));
+    glb.push_skin(json!({
+        "joints": joints,
+        "inverseBindMatrices": accessor,
+        "skeleton": roots.first(),
+    }));
+    (joints, roots)
+}
+
+#[derive(Debug, Default)]
+pub struct UnitLod {
+    pub vertices: Vec<Vertex>,
+    pub parts: Vec<LodPart>,
+}
+
+#[derive(Debug, Default)]
+pub struct LodPart {
+    pub id: u32,
+    pub material_id: Id,
+    /// Vertex indices into the lod, with the part's vertex offset already applied
+    pub faces: Vec<[u64; 3]>,
 }
 
 #[derive(BinRead, Debug, Default)]
@@ -458,3 +674,45 @@ impl From<HalfVector3> for Vector3 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lod(faces: &[[u64; 3]]) -> UnitLod {
+        UnitLod {
+            vertices: (0..3).map(|_| Vertex::default()).collect(),
+            parts: vec![LodPart {
+                id: 1,
+                material_id: Id::invalid(),
+                faces: faces.to_vec(),
+            }],
+        }
+    }
+
+    #[test]
+    fn drops_faces_out_of_range() {
+        let mut glb = Glb::default();
+        assert_eq!(push_lod_mesh(&mut glb, "a", &lod(&[[0, 1, 2], [0, 1, 3]])), Some(0));
+        let primitives = glb.meshes[0]["primitives"].as_array().unwrap();
+        assert_eq!(primitives.len(), 1);
+        let indices = primitives[0]["indices"].as_u64().unwrap() as usize;
+        assert_eq!(glb.accessors[indices]["count"], 3);
+    }
+
+    #[test]
+    fn skips_lods_without_faces() {
+        let mut glb = Glb::default();
+        assert_eq!(push_lod_mesh(&mut glb, "a", &lod(&[[0, 1, 3]])), None);
+        assert_eq!(push_lod_mesh(&mut glb, "b", &UnitLod::default()), None);
+        assert!(glb.meshes.is_empty());
+        assert!(glb.accessors.is_empty());
+
+        let d = DataHeader::default();
+        let bytes = unit_to_glb(&d, &[lod(&[]), lod(&[[2, 1, 0]])]).unwrap();
+        let json_len = u32::from_le_bytes(bytes[12..16].try_into().unwrap()) as usize;
+        let json: serde_json::Value = serde_json::from_slice(&bytes[20..20 + json_len]).unwrap();
+        assert_eq!(json["meshes"].as_array().unwrap().len(), 1);
+        assert_eq!(json["nodes"][0]["name"], format!("{}_lod1", d.unk_id));
+    }
+}
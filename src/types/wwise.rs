@@ -1,7 +1,9 @@
-use crate::{DataHeader, DataReaders, DataTypes, Id, IdCache};
-use anyhow::Result;
+use crate::{
+    error::{Error, Result},
+    DataHeader, DataReaders, DataTypes, Id, IdCache,
+};
 use binrw::{BinReaderExt, NullString};
-use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::io::{Cursor, Seek, SeekFrom};
 
 const BANK_KEY: [u8; 8] = [0xac, 0xbc, 0x11, 0x92, 0x38, 0x70, 0x10, 0xa3];
 
@@ -10,7 +12,7 @@ pub fn extract_bank(
     bundle_id: &Id,
     d: &mut DataHeader,
     r: &mut DataReaders,
-) -> Result<(Vec<u8>, Option<String>), anyhow::Error> {
+) -> Result<(Vec<u8>, Option<String>)> {
     let mut path: Option<String> = None;
     let mut buf = if d.stream_data_size != 0 {
         d.get_stream_buf(r.stream())?
@@ -33,21 +35,63 @@ pub fn extract_bank(
             Some(path_buf.to_string())
         };
 
-        let mut buf2 = vec![0u8; bnk_size as usize];
-        c.read_exact(&mut buf2)?;
-        buf2
+        let start = c.position() as usize;
+        buf1.get(start..start + bnk_size as usize)
+            .ok_or(Error::TruncatedAsset { id: d.unk_id, part: "bundle" })?
+            .to_vec()
     };
-    buf[8..0x10]
+    let part = if d.stream_data_size != 0 { "stream" } else { "bundle" };
+    buf.get_mut(8..0x10)
+        .ok_or(Error::TruncatedAsset { id: d.unk_id, part })?
         .iter_mut()
         .zip(BANK_KEY.iter())
         .for_each(|(x1, x2)| *x1 ^= *x2);
     Ok((buf, path))
 }
 
+pub fn summarize_bank(
+    cache: &IdCache,
+    bundle_id: &Id,
+    d: &mut DataHeader,
+    r: &mut DataReaders,
+) -> Result<Vec<(&'static str, String)>> {
+    let (buf, path) = extract_bank(cache, bundle_id, d, r)?;
+    let mut summary = vec![("bank size", buf.len().to_string())];
+    if let Some(path) = path {
+        summary.push(("path", path));
+    }
+    Ok(summary)
+}
+
+/// Format details from the wem's RIFF fmt chunk
+pub fn summarize_wem(d: &mut DataHeader, r: &mut DataReaders) -> Result<Vec<(&'static str, String)>> {
+    let buf = d.get_stream_buf(r.stream())?;
+    let mut summary = vec![("size", buf.len().to_string())];
+    if buf.len() < 12 || &buf[0..4] != b"RIFF" {
+        return Ok(summary);
+    }
+    let mut off = 12;
+    while off + 8 <= buf.len() {
+        let chunk_size = u32::from_le_bytes(buf[off + 4..off + 8].try_into().unwrap()) as usize;
+        if &buf[off..off + 4] == b"fmt " && off + 16 <= buf.len() {
+            let u16_at = |o: usize| u16::from_le_bytes([buf[o], buf[o + 1]]);
+            summary.push(("codec", format!("{:#x}", u16_at(off + 8))));
+            summary.push(("channels", u16_at(off + 10).to_string()));
+            summary.push((
+                "sample rate",
+                u32::from_le_bytes(buf[off + 12..off + 16].try_into().unwrap()).to_string(),
+            ));
+            break;
+        }
+        off += 8 + chunk_size + (chunk_size & 1);
+    }
+    Ok(summary)
+}
+
 pub fn extract_wem(
     d: &mut DataHeader,
     r: &mut DataReaders,
-) -> Result<(Vec<u8>, Option<String>), anyhow::Error> {
+) -> Result<(Vec<u8>, Option<String>)> {
     let buf = d.get_stream_buf(r.stream())?;
     Ok((buf, None))
 }
@@ -0,0 +1,155 @@
+use std::{io::Seek, path::Path};
+
+use crate::{
+    error::Result,
+    extract::read_bundle,
+    DataHeader, DataReaders, Id,
+};
+
+/// Gaps no bigger than this are taken to be alignment padding and not reported
+pub const DEFAULT_MIN_GAP: u64 = 256;
+
+/// Everything found wrong with one bundle
+#[derive(Debug, Default)]
+pub struct BundleCheck {
+    pub bundle: Id,
+    /// Broken ranges, missing files and mismatched counts. Any of these fails verification.
+    pub problems: Vec<String>,
+    /// Unreferenced byte ranges, as (file, offset, size). Only informational.
+    pub gaps: Vec<(&'static str, u64, u64)>,
+}
+
+/// A byte range an asset claims in one of the bundle's files
+struct Range {
+    start: u64,
+    end: u64,
+    id: Id,
+}
+
+/// Offset and size of an asset's data in one of the files
+type RangeOf = fn(&DataHeader) -> (u64, u64);
+
+fn file_len(path: &Path) -> Option<u64> {
+    std::fs::metadata(path).ok().map(|m| m.len())
+}
+
+/// Checks a bundle's data headers against its type table and the files they point into
+pub fn verify_bundle(data_path: &str, bundle_id: Id, min_gap: u64) -> Result<BundleCheck> {
+    let mut check = BundleCheck {
+        bundle: bundle_id,
+        ..Default::default()
+    };
+    let path = Path::new(data_path).join(bundle_id.to_string());
+    let mut readers = DataReaders::open(data_path, &bundle_id)?;
+    let bundle = read_bundle(readers.bundle())?;
+    let headers_end = readers.bundle().stream_position()?;
+
+    // headers are laid out grouped by type, in type table order
+    let type_total: u64 = bundle.data_types.iter().map(|t| t.data_count).sum();
+    if bundle.header.data_count != type_total {
+        check.problems.push(format!(
+            "header says {} assets but the type table adds up to {}",
+            bundle.header.data_count, type_total
+        ));
+    }
+    let mut headers = bundle.data_headers.iter();
+    for t in &bundle.data_types {
+        let mismatched = headers
+            .by_ref()
+            .take(t.data_count as usize)
+            .filter(|d| d.type_id != t.type_id)
+            .count();
+        if mismatched > 0 {
+            check.problems.push(format!(
+                "{} of the {} assets listed under type {} have another type",
+                mismatched, t.data_count, t.type_id
+            ));
+        }
+    }
+
+    let files: [(&'static str, Option<u64>, u64, RangeOf); 3] = [
+        ("bundle", file_len(&path), headers_end, |d| (d.data_offset, d.data_size as u64)),
+        (
+            "stream",
+            file_len(&path.with_extension("stream")),
+            0,
+            |d| (d.stream_data_offset as u64, d.stream_data_size as u64),
+        ),
+        (
+            "gpu_resources",
+            file_len(&path.with_extension("gpu_resources")),
+            0,
+            |d| (d.gpu_data_offset, d.gpu_data_size as u64),
+        ),
+    ];
+    for (file, len, data_start, range_of) in files {
+        let mut ranges: Vec<Range> = Vec::new();
+        for d in &bundle.data_headers {
+            let (offset, size) = range_of(d);
+            if size == 0 {
+                continue;
+            }
+            // both come straight from the header, so a corrupt one can overflow
+            let Some(end) = offset.checked_add(size) else {
+                check.problems.push(format!(
+                    "{} {} range of {:#x} bytes at {:#x} is corrupt, its end overflows",
+                    d.unk_id, file, size, offset
+                ));
+                continue;
+            };
+            ranges.push(Range {
+                start: offset,
+                end,
+                id: d.unk_id,
+            });
+        }
+        if ranges.is_empty() {
+            continue;
+        }
+        let Some(len) = len else {
+            check.problems.push(format!(
+                "{} assets point into the {} file, which doesn't exist",
+                ranges.len(),
+                file
+            ));
+            continue;
+        };
+
+        ranges.sort_by_key(|r| (r.start, r.end));
+        for r in ranges.iter().filter(|r| r.end > len) {
+            check.problems.push(format!(
+                "{} {} range {:#x}..{:#x} runs past the end of the file ({:#x})",
+                r.id, file, r.start, r.end, len
+            ));
+        }
+        if let Some(r) = ranges.iter().find(|r| r.start < data_start) {
+            check.problems.push(format!(
+                "{} {} range starts at {:#x}, inside the bundle's headers",
+                r.id, file, r.start
+            ));
+        }
+
+        let mut covered = data_start;
+        let mut previous: Option<&Range> = None;
+        for r in &ranges {
+            if let Some(p) = previous.filter(|p| r.start < p.end) {
+                check.problems.push(format!(
+                    "{} {} range {:#x}..{:#x} overlaps {} at {:#x}..{:#x}",
+                    r.id, file, r.start, r.end, p.id, p.start, p.end
+                ));
+            }
+            if r.start > covered && r.start - covered > min_gap {
+                check.gaps.push((file, covered, r.start - covered));
+            }
+            if r.end > covered {
+                covered = r.end;
+                previous = Some(r);
+            }
+        }
+        if len > covered && len - covered > min_gap {
+            check.gaps.push((file, covered, len - covered));
+        }
+    }
+
+    Ok(check)
+}